@@ -22,7 +22,6 @@ fn main() {
     {
         let message = Message::new(b"ctx", b"I'd far rather be happy than right any day.");
         let mut keypairs: Vec<_> = (0..3)
-            .into_iter()
             .map(|_| Keypair::<TinyBLS<Bls12_377, ark_bls12_377::Config>>::generate(thread_rng()))
             .collect();
         let pub_keys_in_sig_grp: Vec<PublicKeyInSignatureGroup<TinyBLS377>> = keypairs
@@ -1,12 +1,12 @@
-#[cfg(feature = "experimental")]
+#[cfg(feature = "std")]
 use tnt_bls::{distinct::DistinctMessages, Keypair, Message, Signed, ZBLS};
 
 /// Run using
 /// ```sh
-/// cargo run --features "experimental" --example aggregated
+/// cargo run --example aggregated
 /// ```
 fn main() {
-    #[cfg(feature = "experimental")]
+    #[cfg(feature = "std")]
     {
         let mut keypairs = [
             Keypair::<ZBLS>::generate(::rand::thread_rng()),
@@ -32,11 +32,8 @@ fn main() {
             .map(|(m, k)| k.sign(m))
             .collect::<Vec<_>>();
 
-        let publickeys = keypairs
-            .iter()
-            .map(|k| k.public.clone())
-            .collect::<Vec<_>>();
-        let pops = keypairs.iter_mut().map(|k|(ProofOfPossessionGenerator::<ZBLS, Sha256, PublicKey<ZBLS>, SchnorrPoP<ZBLS>>::generate_pok(k))).collect::<Vec<_>>();
+        let publickeys = keypairs.iter().map(|k| k.public).collect::<Vec<_>>();
+        let pops = keypairs.iter_mut().map(ProofOfPossessionGenerator::<ZBLS, Sha256, PublicKey<ZBLS>, SchnorrPoP<ZBLS>>::generate_pok).collect::<Vec<_>>();
 
         //first make sure public keys have valid pop
         let publickeys = publickeys
@@ -53,7 +50,7 @@ fn main() {
         let batch_poped = msgs.iter().zip(publickeys).zip(sigs).fold(
             MultiMessageSignatureAggregatorAssumingPoP::<ZBLS>::new(),
             |mut bpop, ((message, publickey), sig)| {
-                bpop.add_message_n_publickey(message, &publickey);
+                bpop.add_message_n_publickey(message, publickey);
                 bpop.add_signature(&sig);
                 bpop
             },
@@ -0,0 +1,396 @@
+//! ## Randomized batch verification
+//!
+//! We verify many independent signatures and aggregates together by
+//! checking one random linear combination of their verification
+//! equations.  We weight the `i`th item by a 128 bit scalar `r_i`
+//! unknown to the signers, so that an invalid item passes only if
+//! its error cancels out with probability `2^-128`.  We thus check
+//!
+//! `e(-g, sum r_i sigma_i) prod_i prod_j e(r_i pk_ij, H(m_ij)) = 1`
+//!
+//! with a single multi-Miller loop and one final exponentiation,
+//! instead of one final exponentiation per item.
+//!
+//! If a batch fails then `BatchVerifier::blame` locates the invalid
+//! items by recursive bisection, reusing the weighted and prepared
+//! points.  With `k` invalid items among `n`, we check at most
+//! `2 k log2(n)` sub-batches, each with one final exponentiation and
+//! one Miller loop over only its own pairs.  We provide `blame` for
+//! when an aggregate fails but its individual signatures remain
+//! available.
+//!
+//! We check only the pairing equations, like `verifiers::verify_simple`,
+//! so any invariants enforced by an item's own `Signed::verify`, like
+//! message distinctness, remain the caller's responsibility.
+
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+
+use ark_ec::CurveGroup;
+use ark_ff::Zero;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::Rng;
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake128,
+};
+
+use crate::single::Signature;
+use crate::{EngineBLS, Signed};
+
+/// Batch verifier for any mix of `Signed` values.
+pub struct BatchVerifier<E: EngineBLS> {
+    key: Shake128,
+    /// Weighted signature of every item
+    signatures: Vec<E::SignatureGroup>,
+    /// Index of every item's first pair in `publickeys` and `messages`
+    offsets: Vec<usize>,
+    signature: E::SignatureGroup,
+    publickeys: Vec<E::PublicKeyGroup>,
+    messages: Vec<E::SignatureGroup>,
+    strict: bool,
+    /// Items rejected by strict validation
+    invalid: Vec<usize>,
+}
+
+impl<E: EngineBLS> Clone for BatchVerifier<E> {
+    fn clone(&self) -> BatchVerifier<E> {
+        BatchVerifier {
+            key: self.key.clone(),
+            signatures: self.signatures.clone(),
+            offsets: self.offsets.clone(),
+            signature: self.signature,
+            publickeys: self.publickeys.clone(),
+            messages: self.messages.clone(),
+            strict: self.strict,
+            invalid: self.invalid.clone(),
+        }
+    }
+}
+
+impl<E: EngineBLS> BatchVerifier<E> {
+    /// Create a batch verifier whose weights derive from `rng`.
+    pub fn new_with_rng<R: Rng>(mut rng: R) -> BatchVerifier<E> {
+        let mut key = Shake128::default();
+        key.update(b"BLS batch verification:");
+        key.update(&rng.gen::<[u8; 32]>());
+        BatchVerifier {
+            key,
+            signatures: Vec::new(),
+            offsets: Vec::new(),
+            signature: E::SignatureGroup::zero(),
+            publickeys: Vec::new(),
+            messages: Vec::new(),
+            strict: false,
+            invalid: Vec::new(),
+        }
+    }
+
+    /// Validate the signature and public keys of every item we add,
+    /// like `verifiers::verify_strict`, so that items with identity
+    /// or non-subgroup points fail and get blamed.
+    pub fn strict(mut self) -> BatchVerifier<E> {
+        self.strict = true;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn new() -> BatchVerifier<E> {
+        BatchVerifier::new_with_rng(thread_rng())
+    }
+
+    /// Number of items added so far.
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// Weight for the next item, which never repeats because we
+    /// include the item's position.
+    fn weight(&self) -> E::Scalar {
+        let mut t = self.key.clone();
+        t.update(&(self.signatures.len() as u64).to_le_bytes());
+        let mut b = [0u8; 16];
+        t.finalize_xof().read(&mut b);
+        E::Scalar::from(u128::from_le_bytes(b))
+    }
+
+    /// Add the verification equation of `signed` to the batch.
+    ///
+    /// We multiply the public keys by the item's weight, as in
+    /// `delinear`, and hash every message to the signature curve.
+    pub fn add<S: Signed<E = E>>(&mut self, signed: S) {
+        let weight = self.weight();
+        let signature = signed.signature();
+        let mut valid = !self.strict || signature.validate().is_ok();
+        let signature = signature.0 * weight;
+        self.signatures.push(signature);
+        self.offsets.push(self.publickeys.len());
+        self.signature += signature;
+        for (message, publickey) in signed.messages_and_publickeys() {
+            valid &= !self.strict || publickey.borrow().validate().is_ok();
+            self.publickeys.push(publickey.borrow().0 * weight);
+            self.messages
+                .push(message.borrow().hash_to_signature_curve::<E>());
+        }
+        if !valid {
+            self.invalid.push(self.signatures.len() - 1);
+        }
+    }
+
+    /// Weighted sum of all signatures added so far.
+    pub fn signature(&self) -> Signature<E> {
+        Signature(self.signature)
+    }
+
+    /// Verify all items together with one final exponentiation.
+    ///
+    /// An empty batch verifies.
+    pub fn verify(&self) -> bool {
+        if !self.invalid.is_empty() {
+            return false;
+        }
+        let mut messages = self.messages.clone();
+        messages.push(self.signature);
+        let mut messages = E::SignatureGroup::normalize_batch(&messages);
+        let signature = E::prepare_signature(messages.pop().unwrap());
+        let prepared = self.prepare(messages);
+        E::verify_prepared(signature, prepared.iter())
+    }
+
+    fn prepare(
+        &self,
+        messages: Vec<E::SignatureGroupAffine>,
+    ) -> Vec<(E::PublicKeyPrepared, E::SignaturePrepared)> {
+        E::PublicKeyGroup::normalize_batch(&self.publickeys)
+            .into_iter()
+            .zip(messages)
+            .map(|(pk, m)| (E::prepare_public_key(pk), E::prepare_signature(m)))
+            .collect()
+    }
+
+    /// Indices of the items which fail verification, in increasing order.
+    ///
+    /// We return no indices if the whole batch verifies, and otherwise
+    /// bisect recursively, skipping every half which verifies.
+    pub fn blame(&self) -> Vec<usize> {
+        let prepared = self.prepare(E::SignatureGroup::normalize_batch(&self.messages));
+        let mut failed = Vec::new();
+        if !self.is_empty() {
+            self.bisect(&prepared, 0, self.len(), &mut failed);
+        }
+        failed.extend(self.invalid.iter().copied());
+        failed.sort_unstable();
+        failed.dedup();
+        failed
+    }
+
+    fn bisect(
+        &self,
+        prepared: &[(E::PublicKeyPrepared, E::SignaturePrepared)],
+        start: usize,
+        end: usize,
+        failed: &mut Vec<usize>,
+    ) {
+        let pairs = self.offsets[start]..self.offsets.get(end).copied().unwrap_or(prepared.len());
+        let signature = self.signatures[start..end]
+            .iter()
+            .fold(E::SignatureGroup::zero(), |acc, s| acc + s);
+        let signature = E::prepare_signature(signature.into_affine());
+        if E::verify_prepared(signature, prepared[pairs].iter()) {
+            return;
+        }
+        if end - start == 1 {
+            failed.push(start);
+            return;
+        }
+        let middle = start + (end - start) / 2;
+        self.bisect(prepared, start, middle, failed);
+        self.bisect(prepared, middle, end, failed);
+    }
+}
+
+/// Indices of the invalid items among `signed`, like the individual
+/// signatures behind an aggregate which failed to verify.
+#[cfg(feature = "std")]
+pub fn blame<E, S, I>(signed: I) -> Vec<usize>
+where
+    E: EngineBLS,
+    S: Signed<E = E>,
+    I: IntoIterator<Item = S>,
+{
+    let mut batch = BatchVerifier::new();
+    for s in signed {
+        batch.add(s);
+    }
+    batch.blame()
+}
+
+#[cfg(feature = "std")]
+impl<E: EngineBLS> Default for BatchVerifier<E> {
+    fn default() -> BatchVerifier<E> {
+        BatchVerifier::new()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::multi_pop_aggregator::MultiMessageSignatureAggregatorAssumingPoP;
+    use crate::single::{Keypair, SignedMessage};
+    use crate::single_pop_aggregator::SignatureAggregatorAssumingPoP;
+    use crate::{verifiers, Message, TinyBLS381, ZBLS};
+
+    fn mixed_batch<E: EngineBLS>() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12);
+        let mut keypairs = (0..4)
+            .map(|_| Keypair::<E>::generate(&mut rng))
+            .collect::<Vec<_>>();
+        let message = Message::new(b"ctx", b"block");
+        let other = Message::new(b"ctx", b"other block");
+
+        let single = keypairs[0].signed_message(&message);
+
+        let mut aggregated = SignatureAggregatorAssumingPoP::<E>::new(message.clone());
+        for k in keypairs.iter_mut() {
+            aggregated.add_signature(&k.sign(&message));
+            aggregated.add_publickey(&k.public);
+        }
+
+        let mut multi = MultiMessageSignatureAggregatorAssumingPoP::<E>::new();
+        multi.aggregate(&keypairs[1].signed_message(&message));
+        multi.aggregate(&keypairs[2].signed_message(&other));
+        multi.aggregate(&keypairs[3].signed_message(&other));
+
+        let mut batch = BatchVerifier::<E>::new_with_rng(&mut rng);
+        assert!(batch.is_empty());
+        assert!(batch.verify());
+        batch.add(&single);
+        batch.add(&aggregated);
+        batch.add(&multi);
+        batch.add(&single);
+        assert_eq!(batch.len(), 4);
+        assert!(batch.verify());
+
+        // One bad item spoils the batch.
+        let mut bad = batch.clone();
+        bad.add(&SignedMessage {
+            message: other.clone(),
+            publickey: single.publickey,
+            signature: single.signature,
+        });
+        assert!(!bad.verify());
+
+        // Errors which cancel in the unweighted sum do not cancel here.
+        let delta = other.hash_to_signature_curve::<E>();
+        let first = SignedMessage {
+            message: message.clone(),
+            publickey: single.publickey,
+            signature: Signature(single.signature.0 + delta),
+        };
+        let second = keypairs[1].signed_message(&other);
+        let second = SignedMessage {
+            signature: Signature(second.signature.0 - delta),
+            ..second
+        };
+        let mut unweighted = MultiMessageSignatureAggregatorAssumingPoP::<E>::new();
+        unweighted.aggregate(&first);
+        unweighted.aggregate(&second);
+        assert!(verifiers::verify_simple(&unweighted));
+        let mut cancelling = BatchVerifier::<E>::new_with_rng(&mut rng);
+        cancelling.add(&first);
+        cancelling.add(&second);
+        assert!(!cancelling.verify());
+    }
+
+    fn blame_for<E: EngineBLS>() {
+        let mut rng = ChaCha8Rng::seed_from_u64(13);
+        let messages = (0..3u8)
+            .map(|i| Message::new(b"ctx", &[i]))
+            .collect::<Vec<_>>();
+        let mut keypairs = (0..4)
+            .map(|_| Keypair::<E>::generate(&mut rng))
+            .collect::<Vec<_>>();
+        let mut sigs = (0..13)
+            .map(|i| keypairs[i % 4].signed_message(&messages[i % 3]))
+            .collect::<Vec<_>>();
+        assert!(blame(sigs.iter()).is_empty());
+
+        let invalid = [0, 6, 7, 12];
+        for i in invalid.iter() {
+            sigs[*i].message = messages[(i + 1) % 3].clone();
+        }
+        assert_eq!(blame(sigs.iter()), invalid);
+
+        // Partially aggregated items blame whole aggregates.
+        let mut batch = BatchVerifier::<E>::new_with_rng(&mut rng);
+        let mut aggregates = Vec::new();
+        for chunk in sigs.chunks(4) {
+            let mut multi = MultiMessageSignatureAggregatorAssumingPoP::<E>::new();
+            for sig in chunk.iter() {
+                multi.aggregate(sig);
+            }
+            aggregates.push(multi);
+        }
+        for a in aggregates.iter() {
+            batch.add(a);
+        }
+        assert!(!batch.verify());
+        assert_eq!(batch.blame(), [0, 1, 3]);
+        for (i, a) in aggregates.iter().enumerate() {
+            assert_eq!(i == 2, verifiers::verify_simple(a));
+        }
+
+        // Singletons blame themselves.
+        let mut one = BatchVerifier::<E>::new_with_rng(&mut rng);
+        one.add(&sigs[0]);
+        assert_eq!(one.blame(), [0]);
+        assert!(BatchVerifier::<E>::new_with_rng(&mut rng)
+            .blame()
+            .is_empty());
+    }
+
+    #[test]
+    fn strict_batches_blame_identity_points() {
+        let mut rng = ChaCha8Rng::seed_from_u64(15);
+        let message = Message::new(b"ctx", b"block");
+        let mut keypair = Keypair::<ZBLS>::generate(&mut rng);
+        let good = keypair.signed_message(&message);
+        let identity = SignedMessage::<ZBLS> {
+            message: message.clone(),
+            publickey: crate::PublicKey(Zero::zero()),
+            signature: Signature(Zero::zero()),
+        };
+
+        let mut lax = BatchVerifier::<ZBLS>::new_with_rng(&mut rng);
+        lax.add(&good);
+        lax.add(&identity);
+        assert!(lax.verify());
+
+        let mut strict = BatchVerifier::<ZBLS>::new_with_rng(&mut rng).strict();
+        strict.add(&good);
+        strict.add(&identity);
+        strict.add(&good);
+        assert!(!strict.verify());
+        assert_eq!(strict.blame(), [1]);
+    }
+
+    #[test]
+    fn blame_invalid_items() {
+        blame_for::<ZBLS>();
+        blame_for::<TinyBLS381>();
+    }
+
+    #[test]
+    fn batch_verification() {
+        mixed_batch::<ZBLS>();
+        mixed_batch::<TinyBLS381>();
+    }
+}
@@ -0,0 +1,253 @@
+//! ## Boneh-Drijvers-Neven multi-signatures
+//!
+//! We implement the [MSP multi-signatures](https://eprint.iacr.org/2018/483.pdf)
+//! by Boneh, Drijvers and Neven, which aggregate signatures on one
+//! message without proofs-of-possession.  We derive one coefficient
+//! `a_i = H(t, pk_i)` for every public key `pk_i` from a hash `t` of the
+//! whole sorted public key set, so no signer can choose their key to
+//! cancel the others' keys.  The aggregate public key is `sum a_i pk_i`
+//! and the aggregate signature is `sum a_i sigma_i`, which then verify
+//! with one pairing check like any other BLS signature.
+//!
+//! Signers who know the signer set may multiply their own signature by
+//! their `coefficient`, in which case aggregators merely add signatures.
+//! Otherwise `aggregate_signatures` applies the coefficients itself.
+//!
+//! As in `delinear`, we use 128 bit coefficients, which suffices for
+//! security but halves the cost of scalar multiplications.
+
+use alloc::vec::Vec;
+use core::iter::once;
+
+use ark_ff::Zero;
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake128,
+};
+
+use crate::serialize::SerializableToBytes;
+use crate::single::{PublicKey, Signature};
+use crate::{EngineBLS, Message, Signed};
+
+/// Hash of the sorted public key set, from which we derive coefficients.
+fn key_set_hash<E: EngineBLS>(publickeys: &[PublicKey<E>]) -> Shake128 {
+    let mut encodings = publickeys
+        .iter()
+        .map(|pk| pk.to_bytes())
+        .collect::<Vec<_>>();
+    encodings.sort_unstable();
+    let mut t = Shake128::default();
+    t.update(b"BDN key aggregation:");
+    t.update(&(encodings.len() as u64).to_le_bytes());
+    for encoding in encodings.iter() {
+        t.update(encoding);
+    }
+    t
+}
+
+fn coefficient_from_hash<E: EngineBLS>(t: &Shake128, publickey: &PublicKey<E>) -> E::Scalar {
+    let mut t = t.clone();
+    t.update(&publickey.to_bytes());
+    let mut b = [0u8; 16];
+    t.finalize_xof().read(&mut b);
+    E::Scalar::from(u128::from_le_bytes(b))
+}
+
+/// Coefficients for `publickeys`, in the same order.
+pub fn coefficients<E: EngineBLS>(publickeys: &[PublicKey<E>]) -> Vec<E::Scalar> {
+    let t = key_set_hash(publickeys);
+    publickeys
+        .iter()
+        .map(|publickey| coefficient_from_hash(&t, publickey))
+        .collect()
+}
+
+/// Coefficient by which the signer with `publickey` multiplies their
+/// signature, or `None` if they do not belong to `publickeys`.
+pub fn coefficient<E: EngineBLS>(
+    publickeys: &[PublicKey<E>],
+    publickey: &PublicKey<E>,
+) -> Option<E::Scalar> {
+    if !publickeys.contains(publickey) {
+        return None;
+    }
+    Some(coefficient_from_hash(&key_set_hash(publickeys), publickey))
+}
+
+/// Aggregate public key `sum a_i pk_i` of a signer set.
+pub fn aggregate_public_keys<E: EngineBLS>(publickeys: &[PublicKey<E>]) -> PublicKey<E> {
+    PublicKey(
+        publickeys
+            .iter()
+            .zip(coefficients(publickeys))
+            .fold(E::PublicKeyGroup::zero(), |acc, (pk, a)| acc + pk.0 * a),
+    )
+}
+
+/// Aggregate signature `sum a_i sigma_i` from signers who did not
+/// multiply their own signatures by their coefficients.
+pub fn aggregate_signatures<E: EngineBLS>(signed: &[(PublicKey<E>, Signature<E>)]) -> Signature<E> {
+    let publickeys = signed.iter().map(|(pk, _)| *pk).collect::<Vec<_>>();
+    Signature(
+        signed
+            .iter()
+            .zip(coefficients(&publickeys))
+            .fold(E::SignatureGroup::zero(), |acc, ((_, sig), a)| {
+                acc + sig.0 * a
+            }),
+    )
+}
+
+/// One message with a BDN multi-signature by a signer set.
+///
+/// We store only the aggregate public key, so verifiers should build
+/// this from the signer set they expect.
+pub struct BDNSignedMessage<E: EngineBLS> {
+    message: Message,
+    publickey: PublicKey<E>,
+    signature: Signature<E>,
+}
+
+impl<E: EngineBLS> Clone for BDNSignedMessage<E> {
+    fn clone(&self) -> BDNSignedMessage<E> {
+        BDNSignedMessage {
+            message: self.message.clone(),
+            publickey: self.publickey,
+            signature: self.signature,
+        }
+    }
+}
+
+impl<E: EngineBLS> BDNSignedMessage<E> {
+    /// Aggregate individual signatures on `message`.
+    pub fn new(message: &Message, signed: &[(PublicKey<E>, Signature<E>)]) -> BDNSignedMessage<E> {
+        let publickeys = signed.iter().map(|(pk, _)| *pk).collect::<Vec<_>>();
+        BDNSignedMessage {
+            message: message.clone(),
+            publickey: aggregate_public_keys(&publickeys),
+            signature: aggregate_signatures(signed),
+        }
+    }
+
+    /// Attach an already aggregated `signature` by `publickeys`.
+    pub fn from_aggregate(
+        message: &Message,
+        publickeys: &[PublicKey<E>],
+        signature: Signature<E>,
+    ) -> BDNSignedMessage<E> {
+        BDNSignedMessage {
+            message: message.clone(),
+            publickey: aggregate_public_keys(publickeys),
+            signature,
+        }
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// Aggregate public key of the signer set.
+    pub fn publickey(&self) -> &PublicKey<E> {
+        &self.publickey
+    }
+}
+
+impl<E: EngineBLS> Signed for &BDNSignedMessage<E> {
+    type E = E;
+
+    type M = Message;
+    type PKG = PublicKey<E>;
+
+    type PKnM = ::core::iter::Once<(Message, PublicKey<E>)>;
+
+    fn messages_and_publickeys(self) -> Self::PKnM {
+        once((self.message.clone(), self.publickey))
+    }
+
+    fn signature(&self) -> Signature<E> {
+        self.signature
+    }
+
+    /// Verify with one pairing check, rejecting empty signer sets.
+    fn verify(self) -> bool {
+        !self.publickey.0.is_zero() && self.signature.verify(&self.message, &self.publickey)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::single::Keypair;
+    use crate::{verifiers, TinyBLS381, BLS377, ZBLS};
+
+    fn multi_signature<E: EngineBLS>() {
+        let message = Message::new(b"ctx", b"BDN message");
+        let mut keypairs = (0..5)
+            .map(|_| Keypair::<E>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let publickeys = keypairs.iter().map(|k| k.public).collect::<Vec<_>>();
+        let signed = keypairs
+            .iter_mut()
+            .map(|k| (k.public, k.sign(&message)))
+            .collect::<Vec<_>>();
+
+        let bdn = BDNSignedMessage::new(&message, &signed);
+        assert!(bdn.verify());
+        assert!(verifiers::verify_simple(&bdn));
+        assert!(verifiers::verify_with_distinct_messages(&bdn, false));
+
+        // Coefficients depend on the set, not the order of signers.
+        let mut reversed = signed.clone();
+        reversed.reverse();
+        assert!(BDNSignedMessage::new(&message, &reversed).publickey == bdn.publickey);
+
+        // Signers may apply their coefficients themselves.
+        let signature = Signature(
+            signed
+                .iter()
+                .fold(E::SignatureGroup::zero(), |acc, (pk, sig)| {
+                    acc + sig.0 * coefficient(&publickeys, pk).unwrap()
+                }),
+        );
+        assert!(signature == (&bdn).signature());
+        assert!(BDNSignedMessage::from_aggregate(&message, &publickeys, signature).verify());
+        let outsider = Keypair::<E>::generate(thread_rng()).public;
+        assert!(coefficient(&publickeys, &outsider).is_none());
+
+        // Coefficients bind the whole signer set.
+        assert!(!BDNSignedMessage::from_aggregate(&message, &publickeys[1..], signature).verify());
+        let subset = BDNSignedMessage::new(&message, &signed[1..]);
+        assert!(subset.verify());
+        assert!(subset.signature != bdn.signature);
+        let wrong = Message::new(b"ctx", b"other message");
+        assert!(!BDNSignedMessage::from_aggregate(&wrong, &publickeys, signature).verify());
+
+        // Empty signer sets never verify.
+        assert!(!BDNSignedMessage::<E>::new(&message, &[]).verify());
+    }
+
+    #[test]
+    fn multi_signatures_for_all_engines() {
+        multi_signature::<ZBLS>();
+        multi_signature::<BLS377>();
+        multi_signature::<TinyBLS381>();
+    }
+
+    #[test]
+    fn rogue_keys_fail() {
+        let message = Message::new(b"ctx", b"rogue key");
+        let victim = Keypair::<ZBLS>::generate(thread_rng()).public;
+        // The attacker claims a key which cancels the victim's key, so
+        // they know the secret key of the naive sum.
+        let mut attacker = Keypair::<ZBLS>::generate(thread_rng());
+        let rogue = PublicKey(attacker.public.0 - victim.0);
+        let forgery = attacker.sign(&message);
+
+        let naive = PublicKey::<ZBLS>(victim.0 + rogue.0);
+        assert!(forgery.verify(&message, &naive));
+        let bdn = BDNSignedMessage::from_aggregate(&message, &[victim, rogue], forgery);
+        assert!(!bdn.verify());
+    }
+}
@@ -6,8 +6,12 @@
 use core::borrow::{Borrow, BorrowMut};
 use core::iter::once;
 
-use ark_ec::Group;
+use std::collections::HashMap;
+
+use ark_ec::{AdditiveGroup, CurveGroup};
 use ark_ff::Zero;
+use arrayref::array_ref;
+use sha2::{Digest, Sha256};
 
 use super::single::SignedMessage;
 use super::verifiers::verify_with_distinct_messages;
@@ -58,6 +62,27 @@ pub trait SignerTable<E: EngineBLS> {
     ///
     /// Must satisfy `self.find(pk).and_then(|i| self.lookup(i)) == Some(pk)` when `pk` is present.
     fn find(&self, publickey: &PublicKey<E>) -> Option<usize>;
+
+    /// Aggregate public key of the signers set in `signers`.
+    ///
+    /// We add up every signer's public key by default, but tables
+    /// may cache partial sums, like `IndexedSignerTable` does.
+    fn aggregate_publickey(&self, signers: &[u8]) -> PublicKey<E> {
+        let mut publickey = E::PublicKeyGroup::zero();
+        for i in bitfield_indices(signers) {
+            let pop_pk = self.lookup(i).unwrap();
+            if Some(i) != self.find(&pop_pk) {
+                // unreachable due to check in add points
+                debug_assert!(
+                    false,
+                    "Incorrect SignerTable implementation with duplicate publickeys"
+                );
+                continue;
+            }
+            publickey += &pop_pk.0;
+        }
+        PublicKey(publickey)
+    }
 }
 
 /// Occupied indices bit mask for `self.signers[offset]`  
@@ -66,11 +91,11 @@ where
     E: EngineBLS,
     ST: SignerTable<E>,
 {
-    (0..8).into_iter().fold(0u8, |b, j| {
+    (0..8).fold(0u8, |b, j| {
         let i = 8 * offset + j;
         let pk = signer_table.lookup(i).filter(|pk| {
             // bb = true always due to check in add_points
-            let bb = Some(i) == signer_table.find(&pk);
+            let bb = Some(i) == signer_table.find(pk);
             debug_assert!(
                 bb,
                 "Incorrect SignerTable implementation with duplicate publickeys"
@@ -81,8 +106,123 @@ where
     })
 }
 
+/// Cached aggregate public keys of a `SignerTable`, so sums over each
+/// byte of the signers bitfield, and over the whole table.
+///
+/// We aggregate whole bytes of signers with one addition, and when
+/// most signers sign, we instead subtract the absent signers from
+/// the total.  We read only the bitfield, so merging bitfields
+/// needs no updates here.
+struct ApkCache<E: EngineBLS> {
+    occupied: Vec<u8>,
+    chunks: Vec<E::PublicKeyGroup>,
+    total: E::PublicKeyGroup,
+}
+
+impl<E: EngineBLS> Clone for ApkCache<E> {
+    fn clone(&self) -> ApkCache<E> {
+        ApkCache {
+            occupied: self.occupied.clone(),
+            chunks: self.chunks.clone(),
+            total: self.total,
+        }
+    }
+}
+
+impl<E: EngineBLS> Default for ApkCache<E> {
+    fn default() -> ApkCache<E> {
+        ApkCache {
+            occupied: Vec::new(),
+            chunks: Vec::new(),
+            total: E::PublicKeyGroup::zero(),
+        }
+    }
+}
+
+impl<E: EngineBLS> ApkCache<E> {
+    fn new<ST: SignerTable<E>>(signer_table: &ST) -> ApkCache<E> {
+        let length = signer_table.new_signers().borrow().len();
+        let mut occupied = Vec::with_capacity(length);
+        let mut chunks = Vec::with_capacity(length);
+        let mut total = E::PublicKeyGroup::zero();
+        for offset in 0..length {
+            // We skip the `find` check of `chunk_lookups`, which costs
+            // linear time for slices, because `BitSignedMessage` and
+            // `CountSignedMessage` never set bits of duplicate public keys.
+            // We cache those anyway, but they always count as absent,
+            // so we subtract any we add into `total`.
+            let mask = (0..8).fold(0u8, |b, j| match signer_table.lookup(8 * offset + j) {
+                Some(_) => b | 1u8 << j,
+                None => b,
+            });
+            let chunk = Self::sum(signer_table, offset, mask);
+            total += chunk;
+            occupied.push(mask);
+            chunks.push(chunk);
+        }
+        ApkCache {
+            occupied,
+            chunks,
+            total,
+        }
+    }
+
+    /// Sum of the public keys selected by `mask` in `signers[offset]`
+    fn sum<ST: SignerTable<E>>(signer_table: &ST, offset: usize, mask: u8) -> E::PublicKeyGroup {
+        (0..8)
+            .filter(|j| mask & (1 << j) != 0)
+            .filter_map(|j| signer_table.lookup(8 * offset + j))
+            .fold(E::PublicKeyGroup::zero(), |sum, pk| sum + pk.0)
+    }
+
+    /// Aggregate public key of `signers`, whose table must be the one
+    /// we were built from.
+    fn aggregate_publickey<ST: SignerTable<E>>(
+        &self,
+        signer_table: &ST,
+        signers: &[u8],
+    ) -> PublicKey<E> {
+        let (present, absent) =
+            signers
+                .iter()
+                .zip(&self.occupied)
+                .fold((0, 0), |(present, absent), (s, o)| {
+                    (
+                        present + (s & o).count_ones(),
+                        absent + (!s & o).count_ones(),
+                    )
+                });
+        let subtract = present > absent;
+
+        let mut publickey = if subtract {
+            self.total
+        } else {
+            E::PublicKeyGroup::zero()
+        };
+        for (offset, (s, (o, chunk))) in signers
+            .iter()
+            .zip(self.occupied.iter().zip(&self.chunks))
+            .enumerate()
+        {
+            let mask = if subtract { !s & o } else { s & o };
+            let sum = match mask {
+                0 => continue,
+                _ if mask == *o => *chunk,
+                _ => Self::sum(signer_table, offset, mask),
+            };
+            if subtract {
+                publickey -= sum;
+            } else {
+                publickey += sum;
+            }
+        }
+        PublicKey(publickey)
+    }
+}
+
 /// Avoiding duplicate keys inside a slice gets costly.  We suggest
-/// improving performance by using a customized data type.
+/// improving performance by using `IndexedSignerTable` or a
+/// customized data type.
 ///
 /// TODO: Evaluate using Deref vs Borrow in this context
 /// TODO: Use specialization here
@@ -97,7 +237,7 @@ where
 
     type Signers = Box<[u8]>;
     fn new_signers(&self) -> Self::Signers {
-        vec![0u8; (self.deref().len() + 7) / 8].into_boxed_slice()
+        vec![0u8; self.deref().len().div_ceil(8)].into_boxed_slice()
     }
 
     fn lookup(&self, index: usize) -> Option<PublicKey<E>> {
@@ -135,6 +275,8 @@ pub enum SignerTableError {
     /// Aggregation is impossible due to signers being repeated or
     /// repeated too many times in both sets or multi-sets, respectively.
     RepeatedSigners,
+    /// Attempted to decode a malformed or non-canonical wire format.
+    InvalidEncoding(&'static str),
 }
 
 impl ::core::fmt::Display for SignerTableError {
@@ -147,10 +289,12 @@ impl ::core::fmt::Display for SignerTableError {
                 "Cannot aggregate distinct messages with only a bit field."
             ),
             RepeatedSigners => write!(f, "Cannot aggregate due to duplicate signers."),
+            InvalidEncoding(s) => write!(f, "{}", s),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl ::std::error::Error for SignerTableError {
     fn description(&self) -> &str {
         use self::SignerTableError::*;
@@ -158,8 +302,229 @@ impl ::std::error::Error for SignerTableError {
             BadSignerTable(s) => s,
             MismatchedMessage => "Cannot aggregate distinct messages with only a bit field.",
             RepeatedSigners => "Cannot aggregate due to duplicate signers",
+            InvalidEncoding(s) => s,
+        }
+    }
+}
+
+/// `SignerTable` owning its public keys along with a hash index on
+/// their canonical encodings, so `find` runs in constant time.
+///
+/// We commit to the ordered public keys with a root digest, which makes
+/// `agreement` cheap, and optionally cache prepared public keys for
+/// verifying individual signers.
+pub struct IndexedSignerTable<E: EngineBLS> {
+    publickeys: Vec<PublicKey<E>>,
+    index: HashMap<Vec<u8>, usize>,
+    root: [u8; 32],
+    prepared: Option<Vec<E::PublicKeyPrepared>>,
+    apk_cache: ApkCache<E>,
+}
+
+impl<E: EngineBLS> Clone for IndexedSignerTable<E> {
+    fn clone(&self) -> IndexedSignerTable<E> {
+        IndexedSignerTable {
+            publickeys: self.publickeys.clone(),
+            index: self.index.clone(),
+            root: self.root,
+            prepared: self.prepared.clone(),
+            apk_cache: self.apk_cache.clone(),
+        }
+    }
+}
+
+impl<E: EngineBLS> IndexedSignerTable<E> {
+    /// Index the given public keys, rejecting any duplicates.
+    pub fn new(publickeys: Vec<PublicKey<E>>) -> Result<IndexedSignerTable<E>, SignerTableError> {
+        let mut index = HashMap::with_capacity(publickeys.len());
+        let mut root = Sha256::new();
+        Digest::update(&mut root, b"IndexedSignerTable");
+        Digest::update(&mut root, (publickeys.len() as u64).to_le_bytes());
+        for (i, publickey) in publickeys.iter().enumerate() {
+            let bytes = publickey.to_bytes();
+            Digest::update(&mut root, &bytes);
+            if index.insert(bytes, i).is_some() {
+                return Err(SignerTableError::RepeatedSigners);
+            }
+        }
+        let mut table = IndexedSignerTable {
+            publickeys,
+            index,
+            root: root.finalize().into(),
+            prepared: None,
+            apk_cache: ApkCache::default(),
+        };
+        // We cache sums only once `find` works.
+        table.apk_cache = ApkCache::new(&table);
+        Ok(table)
+    }
+
+    /// Cache prepared public keys for use by `verify_signer`.
+    pub fn prepare(&mut self) {
+        if self.prepared.is_none() {
+            let affine = E::PublicKeyGroup::normalize_batch(
+                &self.publickeys.iter().map(|pk| pk.0).collect::<Vec<_>>(),
+            );
+            self.prepared = Some(affine.into_iter().map(E::prepare_public_key).collect());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.publickeys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.publickeys.is_empty()
+    }
+
+    pub fn publickeys(&self) -> &[PublicKey<E>] {
+        &self.publickeys
+    }
+
+    /// Aggregate public key of the whole table.
+    pub fn total_publickey(&self) -> PublicKey<E> {
+        PublicKey(self.apk_cache.total)
+    }
+
+    /// Digest committing to the public keys and their order.
+    pub fn root(&self) -> &[u8; 32] {
+        &self.root
+    }
+
+    /// Cached prepared public key at `index`, if `prepare` was called.
+    pub fn prepared_public_key(&self, index: usize) -> Option<&E::PublicKeyPrepared> {
+        self.prepared.as_ref()?.get(index)
+    }
+
+    /// Verify an individual signature by the signer at `index`, using
+    /// the cached prepared public key if available.
+    pub fn verify_signer(&self, index: usize, message: &Message, signature: &Signature<E>) -> bool {
+        let publickey = match self.prepared_public_key(index) {
+            Some(prepared) => prepared.clone(),
+            None => match self.publickeys.get(index) {
+                Some(publickey) => E::prepare_public_key(publickey.0),
+                None => return false,
+            },
+        };
+        let message = E::prepare_signature(message.hash_to_signature_curve::<E>());
+        E::verify_prepared(E::prepare_signature(signature.0), &[(publickey, message)])
+    }
+}
+
+impl<E: EngineBLS> SignerTable<E> for IndexedSignerTable<E> {
+    fn agreement(&self, other: &Self) -> bool {
+        self.root == other.root
+    }
+
+    type Signers = Box<[u8]>;
+    fn new_signers(&self) -> Self::Signers {
+        vec![0u8; self.publickeys.len().div_ceil(8)].into_boxed_slice()
+    }
+
+    fn lookup(&self, index: usize) -> Option<PublicKey<E>> {
+        self.publickeys.get(index).cloned()
+    }
+
+    fn find(&self, publickey: &PublicKey<E>) -> Option<usize> {
+        self.index.get(&publickey.to_bytes()).copied()
+    }
+
+    fn aggregate_publickey(&self, signers: &[u8]) -> PublicKey<E> {
+        self.apk_cache.aggregate_publickey(self, signers)
+    }
+}
+
+/// Share one `IndexedSignerTable` among many aggregates.
+impl<E: EngineBLS> SignerTable<E> for &IndexedSignerTable<E> {
+    fn agreement(&self, other: &Self) -> bool {
+        ::core::ptr::eq(*self, *other) || (*self).agreement(*other)
+    }
+
+    type Signers = Box<[u8]>;
+    fn new_signers(&self) -> Self::Signers {
+        (*self).new_signers()
+    }
+
+    fn lookup(&self, index: usize) -> Option<PublicKey<E>> {
+        (*self).lookup(index)
+    }
+
+    fn find(&self, publickey: &PublicKey<E>) -> Option<usize> {
+        (*self).find(publickey)
+    }
+
+    fn aggregate_publickey(&self, signers: &[u8]) -> PublicKey<E> {
+        (*self).aggregate_publickey(signers)
+    }
+}
+
+/// `SignerTable` wrapper which caches aggregate public keys for
+/// another `SignerTable`, like a slice of public keys.
+///
+/// We aggregate signers with one addition per fully signed byte of the
+/// signers bitfield, and subtract absent signers from the table's
+/// total when most signers sign.  `IndexedSignerTable` caches these
+/// sums itself.
+pub struct ApkCachedSignerTable<E: EngineBLS, ST: SignerTable<E>> {
+    signer_table: ST,
+    apk_cache: ApkCache<E>,
+}
+
+impl<E, ST> Clone for ApkCachedSignerTable<E, ST>
+where
+    E: EngineBLS,
+    ST: SignerTable<E> + Clone,
+{
+    fn clone(&self) -> ApkCachedSignerTable<E, ST> {
+        ApkCachedSignerTable {
+            signer_table: self.signer_table.clone(),
+            apk_cache: self.apk_cache.clone(),
+        }
+    }
+}
+
+impl<E: EngineBLS, ST: SignerTable<E>> ApkCachedSignerTable<E, ST> {
+    /// Precompute aggregate public keys for `signer_table`.
+    pub fn new(signer_table: ST) -> ApkCachedSignerTable<E, ST> {
+        let apk_cache = ApkCache::new(&signer_table);
+        ApkCachedSignerTable {
+            signer_table,
+            apk_cache,
         }
     }
+
+    pub fn signer_table(&self) -> &ST {
+        &self.signer_table
+    }
+
+    /// Aggregate public key of the whole table.
+    pub fn total_publickey(&self) -> PublicKey<E> {
+        PublicKey(self.apk_cache.total)
+    }
+}
+
+impl<E: EngineBLS, ST: SignerTable<E>> SignerTable<E> for ApkCachedSignerTable<E, ST> {
+    fn agreement(&self, other: &Self) -> bool {
+        self.signer_table.agreement(&other.signer_table)
+    }
+
+    type Signers = ST::Signers;
+    fn new_signers(&self) -> Self::Signers {
+        self.signer_table.new_signers()
+    }
+
+    fn lookup(&self, index: usize) -> Option<PublicKey<E>> {
+        self.signer_table.lookup(index)
+    }
+
+    fn find(&self, publickey: &PublicKey<E>) -> Option<usize> {
+        self.signer_table.find(publickey)
+    }
+
+    fn aggregate_publickey(&self, signers: &[u8]) -> PublicKey<E> {
+        self.apk_cache
+            .aggregate_publickey(&self.signer_table, signers)
+    }
 }
 
 /// One individual message with attached aggreggate BLS signatures
@@ -169,7 +534,8 @@ impl ::std::error::Error for SignerTableError {
 /// We may aggregage only one signatures per signer here, but our
 /// serialized signature is only one 96 or or 48 bytes compressed
 /// curve point, plus the `SignerTable::Signers`, which takes
-/// about 1 bit per signer if optimized correctly.
+/// about 1 bit per signer if optimized correctly.  See `encode`
+/// and `decode` for this wire format.
 ///
 /// You must provide a `SignerTable` for this, likely by
 /// implementing it for your own data structures.
@@ -181,7 +547,7 @@ pub struct BitSignedMessage<E: EngineBLS, POP: SignerTable<E>> {
     signature: Signature<E>,
 }
 
-impl<'a, E, POP> Clone for BitSignedMessage<E, POP>
+impl<E, POP> Clone for BitSignedMessage<E, POP>
 where
     E: EngineBLS,
     POP: SignerTable<E> + Clone,
@@ -191,12 +557,12 @@ where
             proofs_of_possession: self.proofs_of_possession.clone(),
             signers: self.signers.clone(),
             message: self.message.clone(),
-            signature: self.signature.clone(),
+            signature: self.signature,
         }
     }
 }
 
-impl<'a, E, POP> Signed for &'a BitSignedMessage<E, POP>
+impl<E, POP> Signed for &BitSignedMessage<E, POP>
 where
     E: EngineBLS,
     POP: SignerTable<E>,
@@ -209,22 +575,10 @@ where
     type PKnM = ::core::iter::Once<(Message, PublicKey<E>)>;
 
     fn messages_and_publickeys(self) -> Self::PKnM {
-        let mut publickey = E::PublicKeyGroup::zero();
-        for i in 0..8 * self.signers.borrow().len() {
-            if self.signers.borrow()[i / 8] & (1 << (i % 8)) != 0 {
-                let pop_pk = self.proofs_of_possession.lookup(i).unwrap();
-                if Some(i) != self.proofs_of_possession.find(&pop_pk) {
-                    // unreachable due to check in add points
-                    debug_assert!(
-                        false,
-                        "Incorrect SignerTable implementation with duplicate publickeys"
-                    );
-                    continue;
-                }
-                publickey += &pop_pk.0;
-            }
-        }
-        once((self.message.clone(), PublicKey(publickey)))
+        let publickey = self
+            .proofs_of_possession
+            .aggregate_publickey(self.signers.borrow());
+        once((self.message.clone(), publickey))
     }
 
     fn signature(&self) -> Signature<E> {
@@ -355,7 +709,7 @@ pub struct CountSignedMessage<E: EngineBLS, POP: SignerTable<E>> {
     pub max_duplicates: usize,
 }
 
-impl<'a, E, POP> Clone for CountSignedMessage<E, POP>
+impl<E, POP> Clone for CountSignedMessage<E, POP>
 where
     E: EngineBLS,
     POP: SignerTable<E> + Clone,
@@ -365,13 +719,13 @@ where
             proofs_of_possession: self.proofs_of_possession.clone(),
             signers: self.signers.clone(),
             message: self.message.clone(),
-            signature: self.signature.clone(),
+            signature: self.signature,
             max_duplicates: self.max_duplicates,
         }
     }
 }
 
-impl<'a, E, POP> Signed for &'a CountSignedMessage<E, POP>
+impl<E, POP> Signed for &CountSignedMessage<E, POP>
 where
     E: EngineBLS,
     POP: SignerTable<E>,
@@ -387,20 +741,7 @@ where
         let mut publickey = E::PublicKeyGroup::zero();
         for signers in self.signers.iter().rev().map(|signers| signers.borrow()) {
             publickey.double_in_place();
-            for i in 0..8 * signers.len() {
-                if signers[i / 8] & (1 << (i % 8)) != 0 {
-                    let pop_pk = self.proofs_of_possession.lookup(i).unwrap();
-                    if Some(i) != self.proofs_of_possession.find(&pop_pk) {
-                        // unreachable due to check in add points
-                        debug_assert!(
-                            false,
-                            "Incorrect SignerTable implementation with duplicate publickeys"
-                        );
-                        continue;
-                    }
-                    publickey += &pop_pk.0;
-                }
-            }
+            publickey += self.proofs_of_possession.aggregate_publickey(signers).0;
         }
         once((self.message.clone(), PublicKey(publickey)))
     }
@@ -458,17 +799,15 @@ where
         }
     }
 
-    /*
-    commented out to rid of unused warning
-    TODO: add test coverage to trim and uncomment
-    fn trim(&mut self) {
+    /// Number of bitfields required by the largest count, or one if empty.
+    fn depth(&self) -> usize {
         let empty = |s: &POP::Signers| s.borrow().iter().all(|b| *b == 0u8);
-        let c = self.signers.len() - self.signers.iter().rev().take_while(|s| empty(&*s)).count();
-        self.signers.truncate(c)
-    }*/
+        let c = self.signers.len() - self.signers.iter().rev().take_while(|s| empty(s)).count();
+        c.max(1)
+    }
 
     fn test_count(&self, count: usize) -> Result<(), SignerTableError> {
-        if count >= self.max_duplicates || count >= usize::max_value() {
+        if count >= self.max_duplicates || count == usize::MAX {
             return Err(SignerTableError::RepeatedSigners);
         }
         Ok(())
@@ -545,11 +884,8 @@ where
             ));
         }
         let os = other.signers.borrow();
-        for offset in 0..self.signers[0].borrow().len() {
-            if self
-                .signers
-                .iter()
-                .fold(os[offset], |b, s| b | s.borrow()[offset])
+        for (offset, o) in os.iter().enumerate() {
+            if self.signers.iter().fold(*o, |b, s| b | s.borrow()[offset])
                 & !chunk_lookups(&self.proofs_of_possession, offset)
                 != 0u8
             {
@@ -557,7 +893,7 @@ where
             }
             for j in 0..8 {
                 let mut count = self.get_count(8 * offset + j);
-                if os[offset] & (1 << j) != 0 {
+                if *o & (1 << j) != 0 {
                     count += 1;
                 }
                 self.test_count(count)?;
@@ -611,6 +947,272 @@ where
     }
 }
 
+/// Representation of the signers in our wire formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignersEncoding {
+    /// The `SignerTable::Signers` bitfield, whose length the table fixes.
+    Bitfield,
+    /// Ascending little-endian `u32` indices, preceded by their number,
+    /// which saves space when few signers sign from a large table.
+    Sparse,
+    /// Whichever of `Bitfield` or `Sparse` is shorter.
+    Smallest,
+}
+
+const SIGNERS_BITFIELD: u8 = 0;
+const SIGNERS_SPARSE: u8 = 1;
+
+/// Split our wire format into its signature, encoding tag, and body.
+fn decode_header<E: EngineBLS>(
+    bytes: &[u8],
+) -> Result<(Signature<E>, u8, &[u8]), SignerTableError> {
+    let size = Signature::<E>::SERIALIZED_BYTES_SIZE;
+    if bytes.len() <= size {
+        return Err(SignerTableError::InvalidEncoding("Truncated signature"));
+    }
+    let signature = Signature::<E>::from_bytes(&bytes[..size])
+        .map_err(|_| SignerTableError::InvalidEncoding("Invalid signature"))?;
+    Ok((signature, bytes[size], &bytes[size + 1..]))
+}
+
+fn read_u32(bytes: &[u8]) -> usize {
+    u32::from_le_bytes(*array_ref!(bytes, 0, 4)) as usize
+}
+
+/// Read a sparse list of `width` byte entries, each starting with a
+/// strictly ascending `u32` index.
+fn decode_sparse(body: &[u8], width: usize) -> Result<Vec<&[u8]>, SignerTableError> {
+    let expected = body
+        .get(..4)
+        .and_then(|count| read_u32(count).checked_mul(width))
+        .and_then(|size| size.checked_add(4));
+    if expected != Some(body.len()) {
+        return Err(SignerTableError::InvalidEncoding("Sparse length mismatch"));
+    }
+    let entries = body[4..].chunks(width).collect::<Vec<_>>();
+    if entries.windows(2).any(|w| read_u32(w[0]) >= read_u32(w[1])) {
+        return Err(SignerTableError::InvalidEncoding(
+            "Sparse indices not ascending",
+        ));
+    }
+    Ok(entries)
+}
+
+/// Indices of the set bits in a bitfield.
+fn bitfield_indices(bitfield: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    (0..8 * bitfield.len()).filter(move |i| bitfield[i / 8] & (1 << (i % 8)) != 0)
+}
+
+/// Reject bits for indices the signer table does not occupy, including
+/// trailing bits beyond its end.
+fn check_occupied<E, ST>(signer_table: &ST, bitfield: &[u8]) -> Result<(), SignerTableError>
+where
+    E: EngineBLS,
+    ST: SignerTable<E>,
+{
+    for (offset, b) in bitfield.iter().enumerate() {
+        if *b & !chunk_lookups(signer_table, offset) != 0 {
+            return Err(SignerTableError::BadSignerTable("Absent signer"));
+        }
+    }
+    Ok(())
+}
+
+impl<E, POP> BitSignedMessage<E, POP>
+where
+    E: EngineBLS,
+    POP: SignerTable<E>,
+{
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub fn signers(&self) -> &<POP as SignerTable<E>>::Signers {
+        &self.signers
+    }
+
+    /// Canonical wire format consisting of the compressed aggregate
+    /// signature, one byte selecting the `SignersEncoding`, and the signers.
+    ///
+    /// We omit the message, which verifiers should know from context.
+    pub fn encode(&self, encoding: SignersEncoding) -> Vec<u8> {
+        let bitfield = self.signers.borrow();
+        let indices = bitfield_indices(bitfield).collect::<Vec<_>>();
+        let sparse = match encoding {
+            SignersEncoding::Bitfield => false,
+            SignersEncoding::Sparse => true,
+            SignersEncoding::Smallest => 4 + 4 * indices.len() < bitfield.len(),
+        };
+        let mut bytes = self.signature.to_bytes();
+        if sparse {
+            bytes.push(SIGNERS_SPARSE);
+            bytes.extend_from_slice(&(indices.len() as u32).to_le_bytes());
+            for index in indices {
+                bytes.extend_from_slice(&(index as u32).to_le_bytes());
+            }
+        } else {
+            bytes.push(SIGNERS_BITFIELD);
+            bytes.extend_from_slice(bitfield);
+        }
+        bytes
+    }
+
+    /// Decode our wire format against the given `SignerTable`, rejecting
+    /// non-canonical encodings and signers absent from the table.
+    pub fn decode(
+        proofs_of_possession: POP,
+        message: &Message,
+        bytes: &[u8],
+    ) -> Result<BitSignedMessage<E, POP>, SignerTableError> {
+        let (signature, tag, body) = decode_header::<E>(bytes)?;
+        let mut signers = proofs_of_possession.new_signers();
+        let bitfield = signers.borrow_mut();
+        match tag {
+            SIGNERS_BITFIELD => {
+                if body.len() != bitfield.len() {
+                    return Err(SignerTableError::InvalidEncoding(
+                        "Bitfield length mismatch",
+                    ));
+                }
+                bitfield.copy_from_slice(body);
+            }
+            SIGNERS_SPARSE => {
+                for entry in decode_sparse(body, 4)? {
+                    let index = read_u32(entry);
+                    if index >= 8 * bitfield.len() {
+                        return Err(SignerTableError::BadSignerTable("Absent signer"));
+                    }
+                    bitfield[index / 8] |= 1 << (index % 8);
+                }
+            }
+            _ => {
+                return Err(SignerTableError::InvalidEncoding(
+                    "Unknown signers encoding",
+                ))
+            }
+        }
+        check_occupied(&proofs_of_possession, signers.borrow())?;
+        Ok(BitSignedMessage {
+            proofs_of_possession,
+            signers,
+            message: message.clone(),
+            signature,
+        })
+    }
+}
+
+impl<E, POP> CountSignedMessage<E, POP>
+where
+    E: EngineBLS,
+    POP: SignerTable<E>,
+{
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// Canonical wire format consisting of the compressed aggregate
+    /// signature, one byte selecting the `SignersEncoding`, and the counts.
+    ///
+    /// `Bitfield` gives one byte for the number of bitfields, followed by
+    /// the bitfields holding successively more significant bits of each
+    /// count.  `Sparse` gives `u32` index and count pairs.
+    pub fn encode(&self, encoding: SignersEncoding) -> Vec<u8> {
+        let depth = self.depth();
+        let length = self.signers[0].borrow().len();
+        let counts = (0..8 * length)
+            .map(|index| (index, self.get_count(index)))
+            .filter(|(_, count)| *count > 0)
+            .collect::<Vec<_>>();
+        let sparse = match encoding {
+            SignersEncoding::Bitfield => false,
+            SignersEncoding::Sparse => true,
+            SignersEncoding::Smallest => 4 + 8 * counts.len() < 1 + depth * length,
+        };
+        let mut bytes = self.signature.to_bytes();
+        if sparse {
+            bytes.push(SIGNERS_SPARSE);
+            bytes.extend_from_slice(&(counts.len() as u32).to_le_bytes());
+            for (index, count) in counts {
+                bytes.extend_from_slice(&(index as u32).to_le_bytes());
+                bytes.extend_from_slice(&(count as u32).to_le_bytes());
+            }
+        } else {
+            bytes.push(SIGNERS_BITFIELD);
+            bytes.push(depth as u8);
+            for signers in self.signers[..depth].iter() {
+                bytes.extend_from_slice(signers.borrow());
+            }
+        }
+        bytes
+    }
+
+    /// Decode our wire format against the given `SignerTable`, rejecting
+    /// non-canonical encodings, signers absent from the table, and
+    /// counts of `max_duplicates` or more.
+    pub fn decode(
+        proofs_of_possession: POP,
+        message: &Message,
+        max_duplicates: usize,
+        bytes: &[u8],
+    ) -> Result<CountSignedMessage<E, POP>, SignerTableError> {
+        let (signature, tag, body) = decode_header::<E>(bytes)?;
+        let mut me = CountSignedMessage::new(proofs_of_possession, message.clone());
+        me.max_duplicates = max_duplicates;
+        me.signature = signature;
+        let length = me.signers[0].borrow().len();
+        match tag {
+            SIGNERS_BITFIELD => {
+                let depth = *body.first().ok_or(SignerTableError::InvalidEncoding(
+                    "Bitfield length mismatch",
+                ))? as usize;
+                if depth == 0 || depth > usize::BITS as usize || body.len() != 1 + depth * length {
+                    return Err(SignerTableError::InvalidEncoding(
+                        "Bitfield length mismatch",
+                    ));
+                }
+                let bitfield = |k: usize| &body[1 + k * length..1 + (k + 1) * length];
+                if depth > 1 && bitfield(depth - 1).iter().all(|b| *b == 0) {
+                    return Err(SignerTableError::InvalidEncoding(
+                        "Non-canonical count depth",
+                    ));
+                }
+                me.signers.clear();
+                for k in 0..depth {
+                    let mut signers = me.proofs_of_possession.new_signers();
+                    signers.borrow_mut().copy_from_slice(bitfield(k));
+                    check_occupied(&me.proofs_of_possession, signers.borrow())?;
+                    me.signers.push(signers);
+                }
+                for index in 0..8 * length {
+                    me.test_count(me.get_count(index))?;
+                }
+            }
+            SIGNERS_SPARSE => {
+                for entry in decode_sparse(body, 8)? {
+                    let (index, count) = (read_u32(entry), read_u32(&entry[4..]));
+                    if index >= 8 * length {
+                        return Err(SignerTableError::BadSignerTable("Absent signer"));
+                    }
+                    if count == 0 {
+                        return Err(SignerTableError::InvalidEncoding("Sparse count is zero"));
+                    }
+                    me.test_count(count)?;
+                    me.set_count(index, count);
+                }
+                for signers in me.signers.iter() {
+                    check_occupied(&me.proofs_of_possession, signers.borrow())?;
+                }
+            }
+            _ => {
+                return Err(SignerTableError::InvalidEncoding(
+                    "Unknown signers encoding",
+                ))
+            }
+        }
+        Ok(me)
+    }
+}
+
 #[cfg(all(test, feature = "std"))]
 mod tests {
     use rand::thread_rng; // Rng
@@ -623,7 +1225,7 @@ mod tests {
         let msg2 = Message::new(b"ctx", b"another message");
 
         let k = |_| Keypair::<ZBLS>::generate(thread_rng());
-        let mut keypairs = (0..4).into_iter().map(k).collect::<Vec<_>>();
+        let mut keypairs = (0..4).map(k).collect::<Vec<_>>();
         let pop = keypairs.iter().map(|k| k.public).collect::<Vec<_>>();
         let dup = keypairs[3].clone();
         keypairs.push(dup);
@@ -710,4 +1312,412 @@ mod tests {
         countsig.max_duplicates = 4;
         assert!(countsig.merge(&countpop2).is_err());
     }
+
+    fn bit_signed_message_for<E: EngineBLS>() {
+        let message = Message::new(b"ctx", b"bitfield message");
+        let mut keypairs = (0..11)
+            .map(|_| Keypair::<E>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let table = keypairs.iter().map(|k| k.public).collect::<Vec<_>>();
+        let sigs = keypairs
+            .iter_mut()
+            .map(|k| k.signed_message(&message))
+            .collect::<Vec<_>>();
+
+        let mut bitsig = BitSignedMessage::<E, _>::new(table.clone(), &message);
+        assert_eq!(bitsig.signers.len(), 2);
+        for i in [0, 3, 8, 10] {
+            bitsig.add(&sigs[i]).unwrap();
+        }
+        assert_eq!(&bitsig.signers[..], &[0b0000_1001, 0b0000_0101]);
+        assert!(bitsig.verify());
+        assert!(matches!(
+            bitsig.add(&sigs[3]),
+            Err(SignerTableError::RepeatedSigners)
+        ));
+
+        // A signature by a key outside the table cannot be added.
+        let outsider = Keypair::<E>::generate(thread_rng()).signed_message(&message);
+        assert!(matches!(
+            bitsig.add(&outsider),
+            Err(SignerTableError::BadSignerTable(_))
+        ));
+
+        // Tables must agree before merging.
+        let mut other_table = table.clone();
+        other_table.swap(1, 2);
+        let mut other = BitSignedMessage::<E, _>::new(other_table, &message);
+        other.add(&sigs[1]).unwrap();
+        assert!(matches!(
+            bitsig.merge(&other),
+            Err(SignerTableError::BadSignerTable(_))
+        ));
+
+        // Claiming a signer who did not sign fails verification.
+        let mut forged = bitsig.clone();
+        forged.signers[0] |= 0b0000_0010;
+        assert!(!forged.verify());
+        assert!(!verifiers::verify_simple(&forged));
+
+        // A signature on another message fails verification.
+        let mut wrong = BitSignedMessage::<E, _>::new(table, &message);
+        let other_message = Message::new(b"ctx", b"other message");
+        let signature = keypairs[5].sign(&other_message);
+        wrong.add_points(keypairs[5].public, signature).unwrap();
+        assert!(!wrong.verify());
+    }
+
+    #[test]
+    fn bit_signed_message() {
+        bit_signed_message_for::<ZBLS>();
+        bit_signed_message_for::<TinyBLS381>();
+    }
+
+    #[test]
+    fn count_signed_message_counts() {
+        let message = Message::new(b"ctx", b"counted message");
+        let mut keypairs = (0..3)
+            .map(|_| Keypair::<TinyBLS381>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let table = keypairs.iter().map(|k| k.public).collect::<Vec<_>>();
+        let sigs = keypairs
+            .iter_mut()
+            .map(|k| k.signed_message(&message))
+            .collect::<Vec<_>>();
+
+        let mut countsig = CountSignedMessage::<TinyBLS381, _>::new(table.clone(), message.clone());
+        countsig.max_duplicates = 6;
+        for _ in 0..5 {
+            countsig.add(&sigs[2]).unwrap();
+        }
+        countsig.add(&sigs[0]).unwrap();
+        assert_eq!(countsig.get_count(0), 1);
+        assert_eq!(countsig.get_count(1), 0);
+        assert_eq!(countsig.get_count(2), 5);
+        assert_eq!(countsig.signers.len(), 3);
+        assert!(countsig.verify());
+        assert!(matches!(
+            countsig.add(&sigs[2]),
+            Err(SignerTableError::RepeatedSigners)
+        ));
+        assert_eq!(countsig.get_count(2), 5);
+
+        let mut extra = CountSignedMessage::<TinyBLS381, _>::new(table, message);
+        extra.add(&sigs[1]).unwrap();
+        extra.add(&sigs[1]).unwrap();
+        countsig.merge(&extra).unwrap();
+        assert_eq!(countsig.get_count(1), 2);
+        assert!(countsig.verify());
+        assert!(verifiers::verify_simple(&countsig));
+
+        // Miscounting a signer fails verification.
+        countsig.set_count(1, 3);
+        assert!(!countsig.verify());
+    }
+
+    #[test]
+    fn bit_signed_message_wire_format() {
+        let message = Message::new(b"ctx", b"quorum certificate");
+        let mut keypairs = (0..11)
+            .map(|_| Keypair::<ZBLS>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let table = keypairs.iter().map(|k| k.public).collect::<Vec<_>>();
+        let mut bitsig = BitSignedMessage::<ZBLS, _>::new(table.clone(), &message);
+        for i in [1, 9, 10] {
+            bitsig.add(&keypairs[i].signed_message(&message)).unwrap();
+        }
+        let sig_size = Signature::<ZBLS>::SERIALIZED_BYTES_SIZE;
+
+        let bitfield = bitsig.encode(SignersEncoding::Bitfield);
+        let sparse = bitsig.encode(SignersEncoding::Sparse);
+        assert_eq!(bitfield.len(), sig_size + 1 + 2);
+        assert_eq!(sparse.len(), sig_size + 1 + 4 + 3 * 4);
+        assert_eq!(bitsig.encode(SignersEncoding::Smallest), bitfield);
+        for bytes in [&bitfield, &sparse] {
+            let decoded =
+                BitSignedMessage::<ZBLS, _>::decode(table.clone(), &message, bytes).unwrap();
+            assert_eq!(decoded.signers(), bitsig.signers());
+            assert!(decoded.signature == bitsig.signature);
+            assert!(decoded.verify());
+            assert_eq!(&decoded.encode(SignersEncoding::Bitfield), &bitfield);
+            assert_eq!(&decoded.encode(SignersEncoding::Sparse), &sparse);
+        }
+
+        // Sparse wins for few signers from a large table.
+        let large = (0..96)
+            .map(|_| Keypair::<ZBLS>::generate(thread_rng()).public)
+            .collect::<Vec<_>>();
+        let mut few = BitSignedMessage::<ZBLS, _>::new(large, &message);
+        few.signature = bitsig.signature;
+        few.signers[5] = 0b0001_0000;
+        assert_eq!(
+            few.encode(SignersEncoding::Smallest),
+            few.encode(SignersEncoding::Sparse)
+        );
+
+        let decode =
+            |bytes: &[u8]| BitSignedMessage::<ZBLS, _>::decode(table.clone(), &message, bytes);
+        let invalid =
+            |bytes: &[u8]| matches!(decode(bytes), Err(SignerTableError::InvalidEncoding(_)));
+        let absent =
+            |bytes: &[u8]| matches!(decode(bytes), Err(SignerTableError::BadSignerTable(_)));
+
+        // Bitfields must have exactly the length of the table.
+        assert!(invalid(&bitfield[..bitfield.len() - 1]));
+        assert!(invalid(&[&bitfield[..], &[0]].concat()));
+        assert!(invalid(&bitfield[..sig_size]));
+        assert!(invalid(&bitfield[..sig_size - 1]));
+        // Bits beyond the 11 table entries are rejected.
+        let mut trailing = bitfield.clone();
+        trailing[sig_size + 2] |= 0b0000_1000;
+        assert!(absent(&trailing));
+        // Sparse indices must ascend strictly and lie inside the table.
+        let mut unsorted = sparse.clone();
+        unsorted.swap(sig_size + 5, sig_size + 9);
+        assert!(invalid(&unsorted));
+        let mut repeated = sparse.clone();
+        repeated[sig_size + 9] = repeated[sig_size + 5];
+        assert!(invalid(&repeated));
+        let mut outside = sparse.clone();
+        outside[sig_size + 13] = 11;
+        assert!(absent(&outside));
+        let mut count = sparse.clone();
+        count[sig_size + 1] = 2;
+        assert!(invalid(&count));
+        assert!(invalid(&sparse[..sparse.len() - 1]));
+        // Unknown tags and invalid points are rejected.
+        let mut tag = bitfield.clone();
+        tag[sig_size] = 2;
+        assert!(invalid(&tag));
+        let mut point = bitfield.clone();
+        point[0] ^= 0x20;
+        point[1] ^= 0x01;
+        assert!(invalid(&point));
+    }
+
+    #[test]
+    fn count_signed_message_wire_format() {
+        let message = Message::new(b"ctx", b"counted certificate");
+        let mut keypairs = (0..9)
+            .map(|_| Keypair::<TinyBLS381>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let table = keypairs.iter().map(|k| k.public).collect::<Vec<_>>();
+        let mut countsig = CountSignedMessage::<TinyBLS381, _>::new(table.clone(), message.clone());
+        for (i, n) in [(0, 1), (4, 5), (8, 2)] {
+            let sig = keypairs[i].signed_message(&message);
+            for _ in 0..n {
+                countsig.add(&sig).unwrap();
+            }
+        }
+        let sig_size = Signature::<TinyBLS381>::SERIALIZED_BYTES_SIZE;
+        let decode = |bytes: &[u8], max| {
+            CountSignedMessage::<TinyBLS381, _>::decode(table.clone(), &message, max, bytes)
+        };
+
+        let bitfield = countsig.encode(SignersEncoding::Bitfield);
+        let sparse = countsig.encode(SignersEncoding::Sparse);
+        assert_eq!(bitfield.len(), sig_size + 2 + 3 * 2);
+        assert_eq!(sparse.len(), sig_size + 1 + 4 + 3 * 8);
+        assert_eq!(countsig.encode(SignersEncoding::Smallest), bitfield);
+        for bytes in [&bitfield, &sparse] {
+            let decoded = decode(bytes, 16).unwrap();
+            assert_eq!(decoded.get_count(4), 5);
+            assert_eq!(decoded.get_count(8), 2);
+            assert!(decoded.verify());
+            assert_eq!(&decoded.encode(SignersEncoding::Bitfield), &bitfield);
+            assert_eq!(&decoded.encode(SignersEncoding::Sparse), &sparse);
+        }
+
+        // Counts must stay below `max_duplicates`.
+        assert!(matches!(
+            decode(&bitfield, 5),
+            Err(SignerTableError::RepeatedSigners)
+        ));
+        assert!(matches!(
+            decode(&sparse, 5),
+            Err(SignerTableError::RepeatedSigners)
+        ));
+        // Empty most significant bitfields are non-canonical.
+        let mut deeper = bitfield.clone();
+        deeper[sig_size + 1] = 4;
+        deeper.extend_from_slice(&[0, 0]);
+        assert!(matches!(
+            decode(&deeper, 16),
+            Err(SignerTableError::InvalidEncoding(_))
+        ));
+        // So are zero counts, and bits beyond the table.
+        let mut zero = sparse.clone();
+        zero[sig_size + 9] = 0;
+        assert!(matches!(
+            decode(&zero, 16),
+            Err(SignerTableError::InvalidEncoding(_))
+        ));
+        let mut trailing = bitfield.clone();
+        trailing[sig_size + 3] |= 0b1000_0000;
+        assert!(matches!(
+            decode(&trailing, 16),
+            Err(SignerTableError::BadSignerTable(_))
+        ));
+        assert!(matches!(
+            decode(&bitfield[..bitfield.len() - 1], 16),
+            Err(SignerTableError::InvalidEncoding(_))
+        ));
+
+        // An empty aggregate uses one bitfield.
+        let empty = CountSignedMessage::<TinyBLS381, _>::new(table.clone(), message.clone());
+        let bytes = empty.encode(SignersEncoding::Bitfield);
+        assert_eq!(bytes.len(), sig_size + 2 + 2);
+        assert!(decode(&bytes, 16).unwrap().verify());
+    }
+
+    #[test]
+    fn indexed_signer_table() {
+        let message = Message::new(b"ctx", b"indexed message");
+        let mut keypairs = (0..20)
+            .map(|_| Keypair::<ZBLS>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let publickeys = keypairs.iter().map(|k| k.public).collect::<Vec<_>>();
+
+        let mut duplicated = publickeys.clone();
+        duplicated.push(publickeys[7]);
+        assert!(matches!(
+            IndexedSignerTable::new(duplicated),
+            Err(SignerTableError::RepeatedSigners)
+        ));
+
+        let mut table = IndexedSignerTable::new(publickeys.clone()).unwrap();
+        assert_eq!(table.len(), 20);
+        for (i, publickey) in publickeys.iter().enumerate() {
+            assert_eq!(table.find(publickey), Some(i));
+            assert!(table.lookup(i) == Some(*publickey));
+        }
+        assert!(table.lookup(20).is_none());
+        let outsider = Keypair::<ZBLS>::generate(thread_rng()).public;
+        assert_eq!(table.find(&outsider), None);
+
+        // Agreement compares the committed roots, which depend on order.
+        let same = IndexedSignerTable::new(publickeys.clone()).unwrap();
+        assert!(table.agreement(&same));
+        let mut reordered = publickeys.clone();
+        reordered.swap(0, 1);
+        let reordered = IndexedSignerTable::new(reordered).unwrap();
+        assert!(!table.agreement(&reordered));
+        assert!(table.root() != reordered.root());
+
+        // Prepared public keys verify individual signers.
+        let signatures = keypairs
+            .iter_mut()
+            .map(|k| k.sign(&message))
+            .collect::<Vec<_>>();
+        assert!(table.prepared_public_key(0).is_none());
+        assert!(table.verify_signer(3, &message, &signatures[3]));
+        table.prepare();
+        assert!(table.prepared_public_key(19).is_some());
+        assert!(table.verify_signer(3, &message, &signatures[3]));
+        assert!(!table.verify_signer(4, &message, &signatures[3]));
+        assert!(!table.verify_signer(20, &message, &signatures[3]));
+
+        // Aggregates share one table by reference.
+        let mut bitsig = BitSignedMessage::<ZBLS, _>::new(&table, &message);
+        let mut other = BitSignedMessage::<ZBLS, _>::new(&table, &message);
+        for (i, signature) in signatures.iter().enumerate() {
+            let signed = SignedMessage {
+                message: message.clone(),
+                publickey: publickeys[i],
+                signature: *signature,
+            };
+            if i % 3 == 0 {
+                other.add(&signed).unwrap();
+            } else {
+                bitsig.add(&signed).unwrap();
+            }
+        }
+        bitsig.merge(&other).unwrap();
+        assert!(bitsig.verify());
+        let bytes = bitsig.encode(SignersEncoding::Bitfield);
+        assert!(BitSignedMessage::<ZBLS, _>::decode(&same, &message, &bytes)
+            .unwrap()
+            .verify());
+        let mut foreign = BitSignedMessage::<ZBLS, _>::new(&reordered, &message);
+        let mut mixed = BitSignedMessage::<ZBLS, _>::new(&table, &message);
+        foreign.signers[0] = 1;
+        assert!(mixed.merge(&foreign).is_err());
+    }
+
+    #[test]
+    fn cached_aggregate_publickeys() {
+        use rand::Rng;
+
+        let message = Message::new(b"ctx", b"committee vote");
+        let mut keypairs = (0..21)
+            .map(|_| Keypair::<TinyBLS381>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let publickeys = keypairs.iter().map(|k| k.public).collect::<Vec<_>>();
+        let indexed = IndexedSignerTable::new(publickeys.clone()).unwrap();
+        let cached = ApkCachedSignerTable::new(publickeys.clone());
+        let total = publickeys.iter().fold(
+            <TinyBLS381 as EngineBLS>::PublicKeyGroup::zero(),
+            |t, pk| t + pk.0,
+        );
+        assert!(indexed.total_publickey().0 == total);
+        assert!(cached.total_publickey().0 == total);
+
+        // Cached sums agree with adding up signers, whether few or
+        // most sign, and whether bytes are full, partial or empty.
+        let mut rng = thread_rng();
+        let mut bitfields = vec![
+            vec![0u8; 3],
+            vec![0xff, 0xff, 0x1f],
+            vec![0xff, 0x00, 0x10],
+            vec![0xfe, 0xff, 0x1f],
+        ];
+        bitfields.extend((0..16).map(|_| vec![rng.gen(), rng.gen(), rng.gen::<u8>() & 0x1f]));
+        for bitfield in bitfields.iter() {
+            let expected = SignerTable::<TinyBLS381>::aggregate_publickey(&publickeys, bitfield);
+            assert!(indexed.aggregate_publickey(bitfield) == expected);
+            assert!(SignerTable::aggregate_publickey(&&indexed, bitfield) == expected);
+            assert!(cached.aggregate_publickey(bitfield) == expected);
+        }
+
+        // Merged bitfields stay correct.
+        let signed = keypairs
+            .iter_mut()
+            .map(|k| k.signed_message(&message))
+            .collect::<Vec<_>>();
+        let mut most = BitSignedMessage::<TinyBLS381, _>::new(cached.clone(), &message);
+        let mut rest = BitSignedMessage::<TinyBLS381, _>::new(cached.clone(), &message);
+        for (i, s) in signed.iter().enumerate() {
+            if i % 7 != 0 {
+                most.add(s).unwrap();
+            } else if i != 14 {
+                rest.add(s).unwrap();
+            }
+        }
+        assert!(most.verify() && rest.verify());
+        most.merge(&rest).unwrap();
+        assert!(most.verify());
+        let (_, apk) = most.messages_and_publickeys().next().unwrap();
+        assert!(apk.0 == total - publickeys[14].0);
+
+        let mut countsig = CountSignedMessage::<TinyBLS381, _>::new(&indexed, message.clone());
+        for s in signed.iter() {
+            countsig.add(s).unwrap();
+        }
+        countsig.add(&signed[3]).unwrap();
+        assert!(countsig.verify());
+        let (_, apk) = countsig.messages_and_publickeys().next().unwrap();
+        assert!(apk.0 == total + publickeys[3].0);
+
+        // Duplicate public keys in the table count only once.
+        let mut duplicated = publickeys[..3].to_vec();
+        duplicated.push(publickeys[0]);
+        let cached = ApkCachedSignerTable::new(duplicated);
+        let mut bitsig = BitSignedMessage::<TinyBLS381, _>::new(cached, &message);
+        for s in signed[..3].iter() {
+            bitsig.add(s).unwrap();
+        }
+        assert!(bitsig.verify());
+        let (_, apk) = bitsig.messages_and_publickeys().next().unwrap();
+        assert!(apk.0 == publickeys[0].0 + publickeys[1].0 + publickeys[2].0);
+    }
 }
@@ -1,11 +1,17 @@
 use alloc::vec::Vec;
-use ark_ec::PrimeGroup;
+
+use ark_ec::{scalar_mul::variable_base::VariableBaseMSM, CurveGroup, PrimeGroup};
 use ark_ff::field_hashers::{DefaultFieldHasher, HashToField};
+use ark_ff::Zero;
 
 use digest::{DynDigest, FixedOutputReset};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::Rng;
 
 use crate::double::{DoublePublicKeyScheme, PublicKeyInSignatureGroup};
 use crate::engine::EngineBLS;
+use crate::error::{self, BlsError};
 use crate::schnorr_pop::SchnorrProof;
 use crate::serialize::SerializableToBytes;
 use crate::single::Signature;
@@ -13,6 +19,17 @@ use crate::{Message, SecretKeyVT};
 
 pub type ChaumPedersenSignature<E> = (Signature<E>, SchnorrProof<E>);
 
+/// DLEQ proof carrying the commitments `A = k.G` and `B = k.H(m)` along
+/// with the response `s`, instead of the challenge `c`, so that
+/// verifiers may check many proofs together.
+pub type DLEQCommitmentProof<E> = (
+    <E as EngineBLS>::SignatureGroup,
+    <E as EngineBLS>::SignatureGroup,
+    <E as EngineBLS>::Scalar,
+);
+
+pub type BatchableChaumPedersenSignature<E> = (Signature<E>, DLEQCommitmentProof<E>);
+
 /// ProofOfPossion trait which should be implemented by secret
 pub trait ChaumPedersenSigner<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone> {
     /// The proof of possession generator is supposed to
@@ -20,9 +37,26 @@ pub trait ChaumPedersenSigner<E: EngineBLS, H: DynDigest + FixedOutputReset + De
     /// the secret key which it claim to possess.
     fn generate_cp_signature(&mut self, message: &Message) -> ChaumPedersenSignature<E>;
 
+    /// Produce the same signature as `generate_cp_signature`, but with
+    /// a proof carrying its commitments, for `verify_cp_signatures_batch`.
+    ///
+    /// We recompute the commitments from the compact signature by
+    /// default, which implementors may avoid by keeping them.
+    fn generate_batchable_cp_signature(
+        &mut self,
+        message: &Message,
+    ) -> BatchableChaumPedersenSignature<E>
+    where
+        Self: DoublePublicKeyScheme<E>,
+    {
+        let public_key = self.into_public_key_in_signature_group();
+        let signature_proof = self.generate_cp_signature(message);
+        to_batchable_cp_signature(message, &public_key, &signature_proof)
+    }
+
     fn generate_witness_scaler(
         &self,
-        message_point_as_bytes: &Vec<u8>,
+        message_point_as_bytes: &[u8],
     ) -> <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField;
 
     fn generate_dleq_proof(
@@ -39,6 +73,18 @@ pub trait ChaumPedersenVerifier<E: EngineBLS, H: DynDigest + FixedOutputReset +
         message: &Message,
         signature_proof: ChaumPedersenSignature<E>,
     ) -> bool;
+
+    /// Verify like `verify_cp_signature`, but explain any failure.
+    fn try_verify_cp_signature(
+        &self,
+        message: &Message,
+        signature_proof: ChaumPedersenSignature<E>,
+    ) -> Result<(), BlsError> {
+        error::ensure(
+            self.verify_cp_signature(message, signature_proof),
+            BlsError::FiatShamirMismatch,
+        )
+    }
 }
 
 impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone> ChaumPedersenSigner<E, H>
@@ -57,57 +103,28 @@ impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone> ChaumPeder
         )
     }
 
-    #[allow(non_snake_case)]
+    fn generate_batchable_cp_signature(
+        &mut self,
+        message: &Message,
+    ) -> BatchableChaumPedersenSignature<E> {
+        let bls_signature = SecretKeyVT::sign(self, message);
+        let (a_point, b_point, _, s) =
+            dleq_commit_n_respond::<E, H>(self, message, bls_signature.0);
+        (bls_signature, (a_point, b_point, s))
+    }
+
     fn generate_dleq_proof(
         &mut self,
         message: &Message,
         bls_signature: E::SignatureGroup,
     ) -> SchnorrProof<E> {
-        let signature_point = bls_signature;
-        let message_point = message.hash_to_signature_curve::<E>();
-
-        let signature_point_as_bytes = E::signature_point_to_byte(&signature_point);
-        let message_point_as_bytes = E::signature_point_to_byte(&message_point);
-        let public_key_in_signature_group_as_bytes = E::signature_point_to_byte(
-            &DoublePublicKeyScheme::<E>::into_public_key_in_signature_group(self).0,
-        );
-
-        let mut k = <SecretKeyVT<E> as ChaumPedersenSigner<E, H>>::generate_witness_scaler(
-            self,
-            &message_point_as_bytes,
-        );
-
-        let A_point = <<E as EngineBLS>::SignatureGroup as PrimeGroup>::generator() * k;
-        let B_point = message_point * k;
-
-        let A_point_as_bytes = E::signature_point_to_byte(&A_point);
-        let B_point_as_bytes = E::signature_point_to_byte(&B_point);
-
-        let proof_basis = [
-            message_point_as_bytes,
-            public_key_in_signature_group_as_bytes,
-            signature_point_as_bytes,
-            A_point_as_bytes,
-            B_point_as_bytes,
-        ]
-        .concat();
-
-        let hasher = <DefaultFieldHasher<H> as HashToField<
-            <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField,
-        >>::new(&[]);
-
-        let c = hasher.hash_to_field::<1>(proof_basis.as_slice())[0];
-
-        let s = k - c * self.0;
-
-        ::zeroize::Zeroize::zeroize(&mut k); //clear secret witness from memory
-
+        let (_, _, c, s) = dleq_commit_n_respond::<E, H>(self, message, bls_signature);
         (c, s)
     }
 
     fn generate_witness_scaler(
         &self,
-        message_point_as_bytes: &Vec<u8>,
+        message_point_as_bytes: &[u8],
     ) -> <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField {
         let secret_key_as_bytes = self.to_bytes();
 
@@ -118,11 +135,69 @@ impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone> ChaumPeder
         let hasher = <DefaultFieldHasher<H> as HashToField<
             <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField,
         >>::new(&[]);
-        let scalar_seed = [hashed_secret_key, message_point_as_bytes.clone()].concat();
+        let scalar_seed = [hashed_secret_key, message_point_as_bytes.to_vec()].concat();
         hasher.hash_to_field::<1>(scalar_seed.as_slice())[0]
     }
 }
 
+/// DLEQ commitments `A` and `B`, challenge `c` and response `s` proving
+/// that `bls_signature` and the public key in the signature group share
+/// the logarithm `secret_key`.
+#[allow(non_snake_case)]
+fn dleq_commit_n_respond<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>(
+    secret_key: &SecretKeyVT<E>,
+    message: &Message,
+    bls_signature: E::SignatureGroup,
+) -> (E::SignatureGroup, E::SignatureGroup, E::Scalar, E::Scalar) {
+    let message_point = message.hash_to_signature_curve::<E>();
+    let message_point_as_bytes = E::signature_point_to_byte(&message_point);
+
+    let mut k = <SecretKeyVT<E> as ChaumPedersenSigner<E, H>>::generate_witness_scaler(
+        secret_key,
+        &message_point_as_bytes,
+    );
+
+    let A_point = <<E as EngineBLS>::SignatureGroup as PrimeGroup>::generator() * k;
+    let B_point = message_point * k;
+
+    let c = dleq_challenge::<E, H>(
+        &message_point,
+        &DoublePublicKeyScheme::<E>::into_public_key_in_signature_group(secret_key).0,
+        &bls_signature,
+        &A_point,
+        &B_point,
+    );
+
+    let s = k - c * secret_key.0;
+
+    ::zeroize::Zeroize::zeroize(&mut k); //clear secret witness from memory
+
+    (A_point, B_point, c, s)
+}
+
+/// Fiat-Shamir challenge `c = H(H(m)|P|sigma|A|B)` of a DLEQ proof
+fn dleq_challenge<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>(
+    message_point: &E::SignatureGroup,
+    public_key_in_signature_group: &E::SignatureGroup,
+    signature_point: &E::SignatureGroup,
+    a_point: &E::SignatureGroup,
+    b_point: &E::SignatureGroup,
+) -> E::Scalar {
+    let proof_basis = [
+        E::signature_point_to_byte(message_point),
+        E::signature_point_to_byte(public_key_in_signature_group),
+        E::signature_point_to_byte(signature_point),
+        E::signature_point_to_byte(a_point),
+        E::signature_point_to_byte(b_point),
+    ]
+    .concat();
+
+    let hasher = <DefaultFieldHasher<H> as HashToField<
+        <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField,
+    >>::new(&[]);
+    hasher.hash_to_field::<1>(proof_basis.as_slice())[0]
+}
+
 /// This should be implemented by public key
 #[allow(non_snake_case)]
 impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone> ChaumPedersenVerifier<E, H>
@@ -137,32 +212,206 @@ impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone> ChaumPeder
             * signature_proof.1 .1
             + self.0 * signature_proof.1 .0;
 
-        let B_check_point = message.hash_to_signature_curve::<E>() * signature_proof.1 .1
-            + signature_proof.0 .0 * signature_proof.1 .0;
+        let message_point = message.hash_to_signature_curve::<E>();
+        let B_check_point =
+            message_point * signature_proof.1 .1 + signature_proof.0 .0 * signature_proof.1 .0;
 
-        let A_point_as_bytes = E::signature_point_to_byte(&A_check_point);
-        let B_point_as_bytes = E::signature_point_to_byte(&B_check_point);
+        let c_check = dleq_challenge::<E, H>(
+            &message_point,
+            &self.0,
+            &signature_proof.0 .0,
+            &A_check_point,
+            &B_check_point,
+        );
 
-        let signature_point_as_bytes = signature_proof.0.to_bytes();
-        let message_point_as_bytes =
-            E::signature_point_to_byte(&message.hash_to_signature_curve::<E>());
-        let public_key_in_signature_group_as_bytes = E::signature_point_to_byte(&self.0);
+        c_check == signature_proof.1 .0
+    }
 
-        let resulting_proof_basis = [
-            message_point_as_bytes,
-            public_key_in_signature_group_as_bytes,
-            signature_point_as_bytes,
-            A_point_as_bytes,
-            B_point_as_bytes,
-        ]
-        .concat();
+    fn try_verify_cp_signature(
+        &self,
+        message: &Message,
+        signature_proof: ChaumPedersenSignature<E>,
+    ) -> Result<(), BlsError> {
+        error::check_publickey(&self.0)?;
+        error::check_subgroup(&signature_proof.0 .0)?;
+        error::ensure(
+            <Self as ChaumPedersenVerifier<E, H>>::verify_cp_signature(
+                self,
+                message,
+                signature_proof,
+            ),
+            BlsError::FiatShamirMismatch,
+        )
+    }
+}
 
-        let hasher = <DefaultFieldHasher<H> as HashToField<
-            <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField,
-        >>::new(&[]);
-        let c_check: <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField =
-            hasher.hash_to_field::<1>(resulting_proof_basis.as_slice())[0];
+/// Recompute the commitments `A = s.G + c.P` and `B = s.H(m) + c.sigma`
+/// of a Chaum-Pedersen signature, so that it may join a batch.
+///
+/// We do not verify the signature, but the batch verifier recomputes
+/// the challenge from these commitments, so only valid signatures
+/// yield valid batchable signatures.
+pub fn to_batchable_cp_signature<E: EngineBLS>(
+    message: &Message,
+    public_key: &PublicKeyInSignatureGroup<E>,
+    signature_proof: &ChaumPedersenSignature<E>,
+) -> BatchableChaumPedersenSignature<E> {
+    let (signature, (c, s)) = *signature_proof;
+    let a_point =
+        <<E as EngineBLS>::SignatureGroup as PrimeGroup>::generator() * s + public_key.0 * c;
+    let b_point = message.hash_to_signature_curve::<E>() * s + signature.0 * c;
+    (signature, (a_point, b_point, s))
+}
 
-        c_check == signature_proof.1 .0
+/// Recover the compact Chaum-Pedersen signature, by computing the challenge.
+pub fn to_cp_signature<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>(
+    message: &Message,
+    public_key: &PublicKeyInSignatureGroup<E>,
+    signature_proof: &BatchableChaumPedersenSignature<E>,
+) -> ChaumPedersenSignature<E> {
+    let (signature, (a_point, b_point, s)) = *signature_proof;
+    let c = dleq_challenge::<E, H>(
+        &message.hash_to_signature_curve::<E>(),
+        &public_key.0,
+        &signature.0,
+        &a_point,
+        &b_point,
+    );
+    (signature, (c, s))
+}
+
+/// Verify many Chaum-Pedersen signatures together, by checking one
+/// random linear combination
+///
+/// `sum z_i (s_i.G + c_i.P_i - A_i) + w_i (s_i.H(m_i) + c_i.sigma_i - B_i) = 0`
+///
+/// of their DLEQ equations, with 128 bit weights `z_i` and `w_i` drawn
+/// from `rng`, using one multi-scalar multiplication over the signature
+/// group.  We hash every message to the curve only once.
+///
+/// We reject identity public keys, for which anyone can forge a proof.
+/// We say only whether all signatures verify, so callers who need the
+/// invalid ones should verify individually after a failure.
+pub fn verify_cp_signatures_batch_with_rng<'a, E, H, I, R>(signatures: I, rng: R) -> bool
+where
+    E: EngineBLS + 'a,
+    H: DynDigest + FixedOutputReset + Default + Clone,
+    I: IntoIterator<
+        Item = (
+            &'a Message,
+            &'a PublicKeyInSignatureGroup<E>,
+            &'a BatchableChaumPedersenSignature<E>,
+        ),
+    >,
+    R: Rng,
+{
+    verify_dleq_batch::<E, H, _, R>(
+        signatures
+            .into_iter()
+            .map(|(message, public_key, (signature, proof))| {
+                (message, public_key.0, signature.0, proof)
+            }),
+        rng,
+    )
+}
+
+#[cfg(feature = "std")]
+pub fn verify_cp_signatures_batch<'a, E, H, I>(signatures: I) -> bool
+where
+    E: EngineBLS + 'a,
+    H: DynDigest + FixedOutputReset + Default + Clone,
+    I: IntoIterator<
+        Item = (
+            &'a Message,
+            &'a PublicKeyInSignatureGroup<E>,
+            &'a BatchableChaumPedersenSignature<E>,
+        ),
+    >,
+{
+    verify_cp_signatures_batch_with_rng::<E, H, I, _>(signatures, thread_rng())
+}
+
+/// Batch verify DLEQ proofs given as the message, the public key in
+/// the signature group, the signature and the proof with commitments.
+pub(crate) fn verify_dleq_batch<'a, E, H, I, R>(proofs: I, mut rng: R) -> bool
+where
+    E: EngineBLS + 'a,
+    H: DynDigest + FixedOutputReset + Default + Clone,
+    I: IntoIterator<
+        Item = (
+            &'a Message,
+            E::SignatureGroup,
+            E::SignatureGroup,
+            &'a DLEQCommitmentProof<E>,
+        ),
+    >,
+    R: Rng,
+{
+    let mut generator_scalar = E::Scalar::zero();
+    let mut points = Vec::new();
+    let mut scalars = Vec::new();
+    for (message, public_key, signature, (a_point, b_point, s)) in proofs {
+        if public_key.is_zero() {
+            return false;
+        }
+        let message_point = message.hash_to_signature_curve::<E>();
+        let c = dleq_challenge::<E, H>(&message_point, &public_key, &signature, a_point, b_point);
+        let z = E::Scalar::from(rng.gen::<u128>());
+        let w = E::Scalar::from(rng.gen::<u128>());
+        generator_scalar += z * s;
+        points.extend([public_key, *a_point, message_point, signature, *b_point]);
+        scalars.extend([z * c, -z, w * s, w * c, -w]);
+    }
+    points.push(<<E as EngineBLS>::SignatureGroup as PrimeGroup>::generator());
+    scalars.push(generator_scalar);
+    let points = E::SignatureGroup::normalize_batch(&points);
+    E::SignatureGroup::msm_unchecked(&points, &scalars).is_zero()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand::thread_rng;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::single::Keypair;
+    use crate::ZBLS;
+
+    #[test]
+    fn cp_signature_batch_verification() {
+        let triples = (0..8)
+            .map(|i| {
+                let mut secret = Keypair::<ZBLS>::generate(thread_rng())
+                    .into_vartime()
+                    .secret;
+                let message = Message::new(b"ctx", &[i]);
+                let signature =
+                    ChaumPedersenSigner::<ZBLS, Sha256>::generate_batchable_cp_signature(
+                        &mut secret,
+                        &message,
+                    );
+                (
+                    message,
+                    DoublePublicKeyScheme::into_public_key_in_signature_group(&secret),
+                    signature,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut batch = triples
+            .iter()
+            .map(|(m, pk, sig)| (m, pk, sig))
+            .collect::<Vec<_>>();
+        assert!(verify_cp_signatures_batch::<ZBLS, Sha256, _>(batch.clone()));
+
+        let (message, public_key, signature) = &triples[0];
+        let compact = to_cp_signature::<ZBLS, Sha256>(message, public_key, signature);
+        assert!(ChaumPedersenVerifier::<ZBLS, Sha256>::verify_cp_signature(
+            public_key, message, compact
+        ));
+        assert!(to_batchable_cp_signature(message, public_key, &compact) == *signature);
+
+        // A signature under the wrong public key fails the whole batch.
+        batch[3].1 = &triples[4].1;
+        assert!(!verify_cp_signatures_batch::<ZBLS, Sha256, _>(batch));
     }
 }
@@ -25,20 +25,58 @@ use sha3::{
     Shake128,
 };
 
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
 use super::single::SignedMessage;
 use super::verifiers::verify_with_distinct_messages;
 use super::*;
 
+/// Delinearization key from an arbitrary byte string.
+fn delinearization_key(key: &[u8]) -> Shake128 {
+    let mut t = Shake128::default();
+    t.update(b"Delinearised BLS with key:");
+    let l = key.len() as u64;
+    t.update(&l.to_le_bytes());
+    t.update(key);
+    t
+}
+
+/// Mask for `publickey` under the delinearization `key`.
+///
+/// TODO: We only want 128 bits here, not a full scalar.  We thus
+/// need `mul_bits` exposed by the pairing crate, at which point
+/// our return type here changes.
+fn delinearization_mask<E: EngineBLS>(key: &Shake128, publickey: &PublicKey<E>) -> E::Scalar {
+    let mut t = key.clone();
+    t.update(&uncompressed_publickey(publickey));
+    let mut b = [0u8; 16];
+    t.finalize_xof().read(&mut b[..]);
+    let (x, y) = array_refs!(&b, 8, 8);
+    let mut x: <E::Scalar as PrimeField>::BigInt = u64::from_le_bytes(*x).into();
+    let y: <E::Scalar as PrimeField>::BigInt = u64::from_le_bytes(*y).into();
+    x <<= 64;
+    x.add_with_carry(&y);
+    <E::Scalar as PrimeField>::from_bigint(x).unwrap()
+}
+
+fn uncompressed_publickey<E: EngineBLS>(publickey: &PublicKey<E>) -> Vec<u8> {
+    let pk_affine = publickey.0.into_affine();
+    let mut pk_uncompressed = vec![0; pk_affine.uncompressed_size()];
+    pk_affine
+        .serialize_uncompressed(&mut pk_uncompressed[..])
+        .unwrap();
+    pk_uncompressed
+}
+
 /// Delinearized batched and aggregated BLS signatures.
 ///
 /// We caution that this type only represents one of several
 /// optimizations possible.  We believe it fits well when messages
 /// are often repeated but signers are rarely repeated.
 ///
-/// We should create another type for when repeated signers are
-/// expected, likely by keying the hash map on the pubkic key.
+/// We provide `DelinearizedRepeatedSigners` for when repeated signers
+/// are expected, which keys its hash map on the public key instead.
 /// In practice though, if signers are often repeated then you should
 /// should consider a proof-of-possession scheme, which requiees all
 /// signers register in advance.
@@ -53,7 +91,7 @@ impl<E: EngineBLS> Clone for Delinearized<E> {
         Delinearized {
             key: self.key.clone(),
             messages_n_publickeys: self.messages_n_publickeys.clone(),
-            signature: self.signature.clone(),
+            signature: self.signature,
         }
     }
 }
@@ -87,12 +125,7 @@ impl<E: EngineBLS> Delinearized<E> {
         }
     }
     pub fn new_keyed(key: &[u8]) -> Delinearized<E> {
-        let mut t = Shake128::default();
-        t.update(b"Delinearised BLS with key:");
-        let l = key.len() as u64;
-        t.update(&l.to_le_bytes());
-        t.update(key);
-        Delinearized::new(t)
+        Delinearized::new(delinearization_key(key))
     }
     pub fn new_batched_rng<R: Rng>(mut rng: R) -> Delinearized<E> {
         let r = rng.gen::<[u8; 32]>();
@@ -105,26 +138,8 @@ impl<E: EngineBLS> Delinearized<E> {
     }
 
     /// Return the mask used for a particular public key.
-    ///
-    /// TODO: We only want 128 bits here, not a full scalar.  We thus
-    /// need `mul_bits` exposed by the pairing crate, at which point
-    /// our return type here changes.
     pub fn mask(&self, publickey: &PublicKey<E>) -> E::Scalar {
-        let mut t = self.key.clone();
-        let pk_affine = publickey.0.into_affine();
-        let mut pk_uncompressed = vec![0; pk_affine.uncompressed_size()];
-        pk_affine
-            .serialize_uncompressed(&mut pk_uncompressed[..])
-            .unwrap();
-        t.update(&pk_uncompressed);
-        let mut b = [0u8; 16];
-        t.finalize_xof().read(&mut b[..]);
-        let (x, y) = array_refs!(&b, 8, 8);
-        let mut x: <E::Scalar as PrimeField>::BigInt = u64::from_le_bytes(*x).into();
-        let y: <E::Scalar as PrimeField>::BigInt = u64::from_le_bytes(*y).into();
-        x.muln(64);
-        x.add_with_carry(&y);
-        <E::Scalar as PrimeField>::from_bigint(x).unwrap()
+        delinearization_mask::<E>(&self.key, publickey)
     }
 
     /// Add only a `Signature<E>` to our internal signature,
@@ -201,16 +216,204 @@ impl<E: EngineBLS> Delinearized<E> {
     }
 }
 
-/*
-type PublicKeyUncompressed<E> = <<<E as EngineBLS>::$group as ProjectiveCurve>::Affine as AffineRepr>::Compressed;
+/// Messages signed by one signer of a `DelinearizedRepeatedSigners`.
+struct RepeatedSigner<E: EngineBLS> {
+    /// Public key multiplied by its mask
+    publickey: PublicKey<E>,
+    /// Delinearization mask of the public key
+    mask: E::Scalar,
+    messages: Vec<Message>,
+    /// Sum of all `messages` hashed to the signature curve
+    message_point: E::SignatureGroup,
+}
 
-#[derive(Clone)]
+impl<E: EngineBLS> Clone for RepeatedSigner<E> {
+    fn clone(&self) -> RepeatedSigner<E> {
+        RepeatedSigner {
+            publickey: self.publickey,
+            mask: self.mask,
+            messages: self.messages.clone(),
+            message_point: self.message_point,
+        }
+    }
+}
+
+/// Delinearized batched and aggregated BLS signatures optimized for
+/// repeated signers.
+///
+/// We key our hash map on the signers' public keys and accumulate the
+/// message points of each signer as we go, so that verification
+/// requires only one pairing per distinct signer, plus one for the
+/// signature.  We thus complement `Delinearized`, which instead
+/// collects public keys that sign the same message.
 pub struct DelinearizedRepeatedSigners<E: EngineBLS> {
     key: Shake128,
-    messages_n_publickeys: HashMap<PublicKeyUncompressed<E>,(Message,PublicKey<E>)>,
+    signers: HashMap<Vec<u8>, RepeatedSigner<E>>,
     signature: Signature<E>,
 }
-*/
+
+impl<E: EngineBLS> Clone for DelinearizedRepeatedSigners<E> {
+    fn clone(&self) -> DelinearizedRepeatedSigners<E> {
+        DelinearizedRepeatedSigners {
+            key: self.key.clone(),
+            signers: self.signers.clone(),
+            signature: self.signature,
+        }
+    }
+}
+
+impl<'a, E: EngineBLS> Signed for &'a DelinearizedRepeatedSigners<E> {
+    type E = E;
+
+    type M = &'a Message;
+    type PKG = &'a PublicKey<Self::E>;
+    type PKnM = ::alloc::vec::IntoIter<(&'a Message, &'a PublicKey<E>)>;
+
+    fn messages_and_publickeys(self) -> Self::PKnM {
+        self.signers
+            .values()
+            .flat_map(|signer| signer.messages.iter().map(move |m| (m, &signer.publickey)))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn signature(&self) -> Signature<E> {
+        self.signature
+    }
+
+    /// Verify using one pairing per distinct signer, whose message
+    /// points we already accumulated.
+    fn verify(self) -> bool {
+        let l = self.signers.len();
+        let mut publickeys = Vec::with_capacity(l);
+        let mut messages = Vec::with_capacity(l + 1);
+        for signer in self.signers.values() {
+            publickeys.push(signer.publickey.0);
+            messages.push(signer.message_point);
+        }
+        // Our public keys were masked, so normalizing them in a batch
+        // saves many inversions.
+        let publickeys = E::PublicKeyGroup::normalize_batch(&publickeys);
+        messages.push(self.signature.0);
+        let mut messages = E::SignatureGroup::normalize_batch(&messages);
+        let signature = E::prepare_signature(messages.pop().unwrap());
+        let prepared = publickeys
+            .iter()
+            .zip(messages)
+            .map(|(pk, m)| (E::prepare_public_key(*pk), E::prepare_signature(m)))
+            .collect::<Vec<(_, _)>>();
+        E::verify_prepared(signature, prepared.iter())
+    }
+}
+
+impl<E: EngineBLS> DelinearizedRepeatedSigners<E> {
+    pub fn new(key: Shake128) -> DelinearizedRepeatedSigners<E> {
+        DelinearizedRepeatedSigners {
+            key,
+            signers: HashMap::new(),
+            signature: Signature(E::SignatureGroup::zero()),
+        }
+    }
+    pub fn new_keyed(key: &[u8]) -> DelinearizedRepeatedSigners<E> {
+        DelinearizedRepeatedSigners::new(delinearization_key(key))
+    }
+    pub fn new_batched_rng<R: Rng>(mut rng: R) -> DelinearizedRepeatedSigners<E> {
+        let r = rng.gen::<[u8; 32]>();
+        DelinearizedRepeatedSigners::new_keyed(&r[..])
+    }
+
+    #[cfg(feature = "std")]
+    pub fn new_batched() -> DelinearizedRepeatedSigners<E> {
+        DelinearizedRepeatedSigners::new_batched_rng(thread_rng())
+    }
+
+    /// Return the mask used for a particular public key.
+    pub fn mask(&self, publickey: &PublicKey<E>) -> E::Scalar {
+        delinearization_mask::<E>(&self.key, publickey)
+    }
+
+    /// Number of distinct signers, and hence of pairings for the
+    /// public keys in `verify`.
+    pub fn signers(&self) -> usize {
+        self.signers.len()
+    }
+
+    /// Add only a `Signature<E>` to our internal signature,
+    /// assumes the signature was previously delinearized elsewhere.
+    ///
+    /// Useful for constructing an aggregate signature.
+    pub fn add_delinearized_signature(&mut self, signature: &Signature<E>) {
+        self.signature.0 += signature.0;
+    }
+
+    /// Add only a `Message` and `PublicKey<E>` to our internal data,
+    /// doing delinearization ourselves.
+    ///
+    /// We mask each public key only once, when we first see its signer,
+    /// and afterwards merely add the message point.
+    pub fn add_message_n_publickey(
+        &mut self,
+        message: &Message,
+        publickey: PublicKey<E>,
+    ) -> E::Scalar {
+        let message_point = message.hash_to_signature_curve::<E>();
+        match self.signers.entry(uncompressed_publickey(&publickey)) {
+            Entry::Occupied(mut signer) => {
+                let signer = signer.get_mut();
+                signer.messages.push(message.clone());
+                signer.message_point += message_point;
+                signer.mask
+            }
+            Entry::Vacant(signer) => {
+                let mask = delinearization_mask::<E>(&self.key, &publickey);
+                signer.insert(RepeatedSigner {
+                    publickey: PublicKey(publickey.0 * mask),
+                    mask,
+                    messages: vec![message.clone()],
+                    message_point,
+                });
+                mask
+            }
+        }
+    }
+
+    /// Aggregage BLS signatures from singletons using delinearization
+    pub fn add(&mut self, signed: &SignedMessage<E>) {
+        let mut signature = signed.signature;
+        let mask = self.add_message_n_publickey(&signed.message, signed.publickey);
+        signature.0 *= mask;
+        self.add_delinearized_signature(&signature);
+    }
+
+    /// Test that two `DelinearizedRepeatedSigners` use the same key.
+    ///
+    /// You should call this before calling `merge`, as otherwise
+    /// the same signer carries different masks in both.
+    pub fn agreement(&self, other: &DelinearizedRepeatedSigners<E>) -> bool {
+        let mut c = [[0u8; 16]; 2];
+        self.key.clone().finalize_xof().read(&mut c[0]);
+        other.key.clone().finalize_xof().read(&mut c[1]);
+        c[0] == c[1]
+    }
+
+    /// Merge another `DelinearizedRepeatedSigners` for simultanious
+    /// verification.
+    ///
+    /// You should only call this if `self.agreement(other)`, because
+    /// we keep only our own masked public key for shared signers.
+    pub fn merge(&mut self, other: &DelinearizedRepeatedSigners<E>) {
+        for (pk_uncompressed, signer) in other.signers.iter() {
+            self.signers
+                .entry(pk_uncompressed.clone())
+                .and_modify(|signer0| {
+                    signer0.messages.extend(signer.messages.iter().cloned());
+                    signer0.message_point += signer.message_point;
+                })
+                .or_insert_with(|| signer.clone());
+        }
+        self.signature.0 += other.signature.0;
+    }
+}
 
 #[cfg(all(test, feature = "std"))]
 mod tests {
@@ -221,7 +424,7 @@ mod tests {
         let msg1 = Message::new(b"ctx", b"some message");
 
         let k = |_| Keypair::<ZBLS>::generate(thread_rng());
-        let mut keypairs = (0..4).into_iter().map(k).collect::<Vec<_>>();
+        let mut keypairs = (0..4).map(k).collect::<Vec<_>>();
         let dup = keypairs[3].clone();
         keypairs.push(dup);
         let sigs1 = keypairs
@@ -237,12 +440,148 @@ mod tests {
         assert!(verifiers::verify_unoptimized(&dl));
         assert!(verifiers::verify_simple(&dl));
         assert!(verifiers::verify_with_distinct_messages(&dl, false));
-        // assert!( verifiers::verify_with_gaussian_elimination(&dl) );
+        assert!(verifiers::verify_with_gaussian_elimination(&dl));
 
         assert!(dl.agreement(&dl));
         let dl_too = dl.clone();
         dl.merge(&dl_too);
         assert!(dl.verify());
-        // TODO: more more
+    }
+
+    fn delinearized_messages_for<E: EngineBLS>() {
+        let messages = [
+            Message::new(b"ctx", b"first message"),
+            Message::new(b"ctx", b"second message"),
+        ];
+        let mut keypairs = (0..6)
+            .map(|_| Keypair::<E>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let sigs = keypairs
+            .iter_mut()
+            .enumerate()
+            .map(|(i, k)| k.signed_message(&messages[i % 2]))
+            .collect::<Vec<_>>();
+
+        let mut dl = Delinearized::<E>::new_keyed(b"some key");
+        let mut dl_too = Delinearized::<E>::new_keyed(b"some key");
+        for (i, sig) in sigs.iter().enumerate() {
+            if i < 4 {
+                dl.add(sig);
+            } else {
+                dl_too.add(sig);
+            }
+        }
+        assert_eq!(dl.messages_and_publickeys().len(), 2);
+        assert!(dl.verify());
+        assert!(dl.agreement(&dl_too));
+        assert!(!dl.agreement(&Delinearized::<E>::new_keyed(b"other key")));
+        dl.merge(&dl_too);
+        assert!(dl.verify());
+        assert!(verifiers::verify_simple(&dl));
+
+        // Masks depend upon the key and the public key.
+        let other = Delinearized::<E>::new_keyed(b"other key");
+        assert!(dl.mask(&keypairs[0].public) != other.mask(&keypairs[0].public));
+        assert!(dl.mask(&keypairs[0].public) != dl.mask(&keypairs[1].public));
+
+        // Signatures delinearized under another key do not verify.
+        let mut mixed = Delinearized::<E>::new_keyed(b"some key");
+        mixed.add_message_n_publickey(&sigs[0].message, sigs[0].publickey);
+        let mut signature = sigs[0].signature;
+        signature.0 *= other.mask(&sigs[0].publickey);
+        mixed.add_delinearized_signature(&signature);
+        assert!(!mixed.verify());
+
+        // Nor do signatures on the wrong message.
+        let mut wrong = Delinearized::<E>::new_batched();
+        wrong.add(&SignedMessage {
+            message: messages[1].clone(),
+            publickey: sigs[0].publickey,
+            signature: sigs[0].signature,
+        });
+        wrong.add(&sigs[1]);
+        assert!(!wrong.verify());
+    }
+
+    #[test]
+    fn delinearized_messages() {
+        delinearized_messages_for::<ZBLS>();
+        delinearized_messages_for::<TinyBLS381>();
+    }
+
+    fn delinearized_repeated_signers_for<E: EngineBLS>() {
+        let messages = (0..5)
+            .map(|i| Message::new(b"ctx", &[i]))
+            .collect::<Vec<_>>();
+        let mut keypairs = (0..3)
+            .map(|_| Keypair::<E>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let sigs = keypairs
+            .iter_mut()
+            .flat_map(|k| {
+                messages
+                    .iter()
+                    .map(|m| k.signed_message(m))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut dl = DelinearizedRepeatedSigners::<E>::new_keyed(b"some key");
+        let mut dl_too = DelinearizedRepeatedSigners::<E>::new_keyed(b"some key");
+        for (i, sig) in sigs.iter().enumerate() {
+            if i % 3 == 0 {
+                dl_too.add(sig);
+            } else {
+                dl.add(sig);
+            }
+            assert!(dl.verify());
+        }
+        assert_eq!(dl.signers(), 3);
+        assert!(dl_too.verify());
+        assert!(verifiers::verify_unoptimized(&dl));
+        assert!(verifiers::verify_simple(&dl));
+        assert!(verifiers::verify_with_distinct_messages(&dl, true));
+
+        // Masks agree with `Delinearized` under the same key.
+        let plain = Delinearized::<E>::new_keyed(b"some key");
+        assert!(dl.mask(&keypairs[0].public) == plain.mask(&keypairs[0].public));
+
+        // Repeated signers reuse the mask computed when first seen.
+        let mut masks = DelinearizedRepeatedSigners::<E>::new_keyed(b"some key");
+        let mask = masks.add_message_n_publickey(&messages[0], keypairs[1].public);
+        assert!(mask == dl.mask(&keypairs[1].public));
+        assert!(masks.add_message_n_publickey(&messages[1], keypairs[1].public) == mask);
+
+        assert!(dl.agreement(&dl_too));
+        assert!(!dl.agreement(&DelinearizedRepeatedSigners::<E>::new_keyed(b"other key")));
+        dl.merge(&dl_too);
+        assert_eq!(dl.signers(), 3);
+        assert_eq!(dl.messages_and_publickeys().len(), sigs.len());
+        assert!(dl.verify());
+        assert!(verifiers::verify_simple(&dl));
+
+        // Merging under another key leaves inconsistent masks.
+        let mut other = DelinearizedRepeatedSigners::<E>::new_keyed(b"other key");
+        other.add(&sigs[0]);
+        let mut mixed = dl_too.clone();
+        mixed.merge(&other);
+        assert!(!mixed.verify());
+
+        // Signatures on the wrong message fail.
+        let mut wrong = DelinearizedRepeatedSigners::<E>::new_batched();
+        wrong.add(&sigs[1]);
+        wrong.add(&SignedMessage {
+            message: messages[0].clone(),
+            publickey: sigs[1].publickey,
+            signature: sigs[2].signature,
+        });
+        assert!(!wrong.verify());
+        assert!(!verifiers::verify_simple(&wrong));
+    }
+
+    #[test]
+    fn delinearized_repeated_signers() {
+        delinearized_repeated_signers_for::<ZBLS>();
+        delinearized_repeated_signers_for::<TinyBLS381>();
     }
 }
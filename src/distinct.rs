@@ -18,7 +18,7 @@
 //! collected signatures.  See also:
 //! - RSA:  https://eprint.iacr.org/2018/082.pdf
 //! - Boneh-Boyen:  https://crypto.stanford.edu/~dabo/papers/bbsigs.pdf
-//!     http://sci-gems.math.bas.bg:8080/jspui/bitstream/10525/1569/1/sjc096-vol3-num3-2009.pdf
+//!   http://sci-gems.math.bas.bg:8080/jspui/bitstream/10525/1569/1/sjc096-vol3-num3-2009.pdf
 
 use ark_ff::Zero;
 use std::collections::HashMap;
@@ -70,12 +70,20 @@ impl ::std::error::Error for AttackViaDuplicateMessages {
 /// we do yet not provide any serialization scheme for the aggregate
 /// version.  Instead, you should serialize the aggregated signature
 /// seperately, and reconstruct this type using its `add_*` methods.
-#[derive(Clone)]
 pub struct DistinctMessages<E: EngineBLS> {
     messages_n_publickeys: HashMap<Message, PublicKey<E>>,
     signature: Signature<E>,
 }
 
+impl<E: EngineBLS> Clone for DistinctMessages<E> {
+    fn clone(&self) -> DistinctMessages<E> {
+        DistinctMessages {
+            messages_n_publickeys: self.messages_n_publickeys.clone(),
+            signature: self.signature,
+        }
+    }
+}
+
 impl<'a, E: EngineBLS> Signed for &'a DistinctMessages<E> {
     type E = E;
 
@@ -104,6 +112,12 @@ impl<E: EngineBLS,M: Message> SignedWithDistinctMessages for SignedMessage<E,M>
 impl<E: EngineBLS,M: Message> SignedWithDistinctMessages for DistinctMessages<E,M> {}
 */
 
+impl<E: EngineBLS> Default for DistinctMessages<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<E: EngineBLS> DistinctMessages<E> {
     pub fn new() -> DistinctMessages<E> {
         DistinctMessages {
@@ -142,6 +156,7 @@ impl<E: EngineBLS> DistinctMessages<E> {
     ///
     /// We require that duplicate message halt verification by consuming
     /// self by vaule and return it only if no duplicates occur.
+    #[allow(clippy::should_implement_trait)] // consumes self and may fail, unlike `Add::add`
     pub fn add(self, signed: &SignedMessage<E>) -> DistinctMessagesResult<E> {
         let mut me = self.add_message_n_publickey(signed.message.clone(), signed.publickey)?;
         me.add_signature(&signed.signature);
@@ -202,7 +217,7 @@ mod tests {
         ];
 
         let k = |_| Keypair::<ZBLS>::generate(thread_rng());
-        let mut keypairs = (0..4).into_iter().map(k).collect::<Vec<_>>();
+        let mut keypairs = (0..4).map(k).collect::<Vec<_>>();
         let dup = keypairs[3].clone();
         keypairs.push(dup);
 
@@ -240,7 +255,52 @@ mod tests {
         let dms2 = sigs.iter().skip(3).try_fold(dm_new(), dm_add).unwrap();
         assert!(dms1.merge(&dms2).unwrap().signature == dms.signature);
 
-        *(dms.messages_n_publickeys.get_mut(&msgs[1]).unwrap()) = keypairs[0].public.clone();
+        *(dms.messages_n_publickeys.get_mut(&msgs[1]).unwrap()) = keypairs[0].public;
         assert!(!dms.verify(), "Verification by an incorrect signer passed");
     }
+
+    #[test]
+    fn distinct_messages_reconstruction() {
+        let msgs = (0..5u8)
+            .map(|i| Message::new(b"ctx", &[i]))
+            .collect::<Vec<_>>();
+        let mut keypair = Keypair::<TinyBLS381>::generate(thread_rng());
+        let sigs = msgs
+            .iter()
+            .map(|m| keypair.signed_message(m))
+            .collect::<Vec<_>>();
+
+        // One signer may sign many distinct messages.
+        let dms = sigs
+            .iter()
+            .try_fold(DistinctMessages::new(), |dm, sig| dm.add(sig))
+            .unwrap();
+        assert!(dms.verify());
+
+        // Verifiers rebuild the aggregate from the messages and keys.
+        let signature = (&dms).signature();
+        let mut rebuilt = msgs
+            .iter()
+            .try_fold(DistinctMessages::<TinyBLS381>::default(), |dm, m| {
+                dm.add_message_n_publickey(m.clone(), keypair.public)
+            })
+            .unwrap();
+        rebuilt.add_signature(&signature);
+        assert!(rebuilt.verify());
+        assert!(rebuilt
+            .clone()
+            .add_message_n_publickey(msgs[0].clone(), keypair.public)
+            .is_err());
+
+        // Dropping one message breaks verification.
+        let partial = msgs[1..]
+            .iter()
+            .try_fold(DistinctMessages::<TinyBLS381>::new(), |dm, m| {
+                dm.add_message_n_publickey(m.clone(), keypair.public)
+            })
+            .unwrap();
+        let mut partial = partial;
+        partial.add_signature(&signature);
+        assert!(!partial.verify());
+    }
 }
@@ -0,0 +1,882 @@
+//! ## Dealerless distributed key generation
+//!
+//! We implement the joint Feldman distributed key generation by
+//! Pedersen, with complaints as in
+//! [Gennaro, Jarecki, Krawczyk and Rabin](https://link.springer.com/article/10.1007/s00145-006-0347-3),
+//! among participants at indices `1..=n`.  Each participant acts as a
+//! `threshold::Dealer` for a random secret, broadcasts the Feldman
+//! commitments to its polynomial, and sends every other participant
+//! their share privately.  Recipients broadcast complaints against
+//! dealers whose shares do not match their commitments, and accused
+//! dealers answer by broadcasting the disputed shares.  A dealer is
+//! disqualified if its commitments are missing or malformed, or if it
+//! fails to answer any complaint with a valid share.  As this depends
+//! only upon broadcast messages, all honest participants agree upon
+//! the qualified dealers.  Each participant's final share is the sum
+//! of its shares from qualified dealers, and the group `PublicKey` is
+//! the sum of their commitments to their constant terms.
+//!
+//! An adversary could bias the distribution of the group public key
+//! by choosing whom to disqualify after seeing honest commitments,
+//! which Gennaro et al. show does not harm threshold signatures.
+//!
+//! `Participant` is a pure state machine, so callers provide the
+//! transport:  We expect that `DkgMessage::Share` messages travel over
+//! authenticated and encrypted channels, that all other messages travel
+//! over a reliable broadcast channel, and that callers invoke
+//! `Participant::advance` once all messages of a round arrived or its
+//! deadline passed.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use ark_ff::Zero;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
+use rand::Rng;
+
+use crate::single::{PublicKey, SecretKeyVT};
+use crate::threshold::{Dealer, SecretShare, VerificationVector};
+use crate::EngineBLS;
+
+/// Error type for distributed key generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DkgError {
+    /// The threshold must lie between one and the number of participants.
+    InvalidParameters,
+    /// Participant indices lie in `1..=n`.
+    UnknownParticipant(u32),
+    /// A participant sent the same kind of message twice.
+    DuplicateMessage(u32),
+    /// A private share was delivered to the wrong participant.
+    MisdirectedMessage(u32),
+    /// The message does not belong to the current round.
+    WrongPhase,
+    /// Every dealer was disqualified.
+    NoQualifiedDealers,
+    /// Fewer dealers than the old threshold qualified during resharing.
+    TooFewQualifiedDealers(usize),
+    /// Our existing share does not match the existing verification vector.
+    InvalidShare,
+    /// We hold no share in the committee being dealt to.
+    NotARecipient,
+}
+
+impl ::core::fmt::Display for DkgError {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        use self::DkgError::*;
+        match self {
+            InvalidParameters => write!(f, "The threshold must be between 1 and n."),
+            UnknownParticipant(i) => write!(f, "Unknown participant {}.", i),
+            DuplicateMessage(i) => write!(f, "Duplicate message from participant {}.", i),
+            MisdirectedMessage(i) => write!(f, "Share for participant {} delivered here.", i),
+            WrongPhase => write!(f, "Message does not belong to the current round."),
+            NoQualifiedDealers => write!(f, "Every dealer was disqualified."),
+            TooFewQualifiedDealers(n) => write!(f, "Only {} dealers qualified.", n),
+            InvalidShare => write!(f, "Share does not match the verification vector."),
+            NotARecipient => write!(f, "We are not a member of the new committee."),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for DkgError {}
+
+/// Public parameters shared by all participants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DkgParameters {
+    /// Number of shares required to sign.
+    pub threshold: u32,
+    /// Number of participants, who use indices `1..=participants`.
+    pub participants: u32,
+}
+
+impl DkgParameters {
+    pub fn new(threshold: u32, participants: u32) -> Result<Self, DkgError> {
+        if threshold == 0 || threshold > participants {
+            return Err(DkgError::InvalidParameters);
+        }
+        Ok(DkgParameters {
+            threshold,
+            participants,
+        })
+    }
+
+    fn check_index(&self, index: u32) -> Result<(), DkgError> {
+        if index == 0 || index > self.participants {
+            return Err(DkgError::UnknownParticipant(index));
+        }
+        Ok(())
+    }
+}
+
+/// Broadcast commitments to a dealer's polynomial.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct DealMessage<E: EngineBLS> {
+    pub dealer: u32,
+    pub commitments: VerificationVector<E>,
+}
+
+/// Private share from a dealer, which must travel encrypted.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct ShareMessage<E: EngineBLS> {
+    pub dealer: u32,
+    pub recipient: u32,
+    pub share: E::Scalar,
+}
+
+/// Broadcast list of the dealers whose shares failed verification.
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ComplaintMessage {
+    pub complainer: u32,
+    pub dealers: Vec<u32>,
+}
+
+/// Broadcast answer of a dealer to complaints against it, revealing
+/// the shares of the complainers.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct JustificationMessage<E: EngineBLS> {
+    pub dealer: u32,
+    pub shares: Vec<(u32, E::Scalar)>,
+}
+
+/// Any message of the protocol.
+pub enum DkgMessage<E: EngineBLS> {
+    Deal(DealMessage<E>),
+    Share(ShareMessage<E>),
+    Complaint(ComplaintMessage),
+    Justification(JustificationMessage<E>),
+}
+
+impl<E: EngineBLS> DkgMessage<E> {
+    /// Index of the participant who sent this message.
+    pub fn sender(&self) -> u32 {
+        match self {
+            DkgMessage::Deal(m) => m.dealer,
+            DkgMessage::Share(m) => m.dealer,
+            DkgMessage::Complaint(m) => m.complainer,
+            DkgMessage::Justification(m) => m.dealer,
+        }
+    }
+
+    /// Index of the recipient for private messages, or `None` for broadcasts.
+    pub fn recipient(&self) -> Option<u32> {
+        match self {
+            DkgMessage::Share(m) => Some(m.recipient),
+            _ => None,
+        }
+    }
+}
+
+const DEAL_TAG: u8 = 0;
+const SHARE_TAG: u8 = 1;
+const COMPLAINT_TAG: u8 = 2;
+const JUSTIFICATION_TAG: u8 = 3;
+
+impl<E: EngineBLS> CanonicalSerialize for DkgMessage<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match self {
+            DkgMessage::Deal(m) => {
+                DEAL_TAG.serialize_with_mode(&mut writer, compress)?;
+                m.serialize_with_mode(writer, compress)
+            }
+            DkgMessage::Share(m) => {
+                SHARE_TAG.serialize_with_mode(&mut writer, compress)?;
+                m.serialize_with_mode(writer, compress)
+            }
+            DkgMessage::Complaint(m) => {
+                COMPLAINT_TAG.serialize_with_mode(&mut writer, compress)?;
+                m.serialize_with_mode(writer, compress)
+            }
+            DkgMessage::Justification(m) => {
+                JUSTIFICATION_TAG.serialize_with_mode(&mut writer, compress)?;
+                m.serialize_with_mode(writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + match self {
+            DkgMessage::Deal(m) => m.serialized_size(compress),
+            DkgMessage::Share(m) => m.serialized_size(compress),
+            DkgMessage::Complaint(m) => m.serialized_size(compress),
+            DkgMessage::Justification(m) => m.serialized_size(compress),
+        }
+    }
+}
+
+impl<E: EngineBLS> Valid for DkgMessage<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            DkgMessage::Deal(m) => m.check(),
+            DkgMessage::Share(m) => m.check(),
+            DkgMessage::Complaint(m) => m.check(),
+            DkgMessage::Justification(m) => m.check(),
+        }
+    }
+}
+
+impl<E: EngineBLS> CanonicalDeserialize for DkgMessage<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+            DEAL_TAG => Ok(DkgMessage::Deal(DealMessage::deserialize_with_mode(
+                reader, compress, validate,
+            )?)),
+            SHARE_TAG => Ok(DkgMessage::Share(ShareMessage::deserialize_with_mode(
+                reader, compress, validate,
+            )?)),
+            COMPLAINT_TAG => Ok(DkgMessage::Complaint(
+                ComplaintMessage::deserialize_with_mode(reader, compress, validate)?,
+            )),
+            JUSTIFICATION_TAG => Ok(DkgMessage::Justification(
+                JustificationMessage::deserialize_with_mode(reader, compress, validate)?,
+            )),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// Result of a successful key generation for one participant.
+pub struct DkgOutput<E: EngineBLS> {
+    /// Our share of the group secret key, for `threshold` partial signing.
+    pub share: SecretShare<E>,
+    /// Verification vector of the group secret, giving all public shares.
+    pub verification_vector: VerificationVector<E>,
+    /// Sorted indices of the qualified dealers.
+    pub qualified: Vec<u32>,
+}
+
+impl<E: EngineBLS> DkgOutput<E> {
+    /// The public key under which combined signatures verify.
+    pub fn group_public_key(&self) -> PublicKey<E> {
+        self.verification_vector.group_public_key()
+    }
+}
+
+/// Round of the protocol, advanced by `Participant::advance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DkgPhase {
+    /// Exchanging commitments and private shares.
+    Dealing,
+    /// Exchanging complaints.
+    Complaining,
+    /// Exchanging justifications.
+    Justifying,
+    /// Ready to `finalize`.
+    Finished,
+}
+
+/// Whether a secret share matches the commitments of its dealer.
+fn share_is_valid<E: EngineBLS>(
+    commitments: &VerificationVector<E>,
+    recipient: u32,
+    share: &E::Scalar,
+) -> bool {
+    commitments.verify_share(&SecretShare {
+        index: recipient,
+        secret: SecretKeyVT(*share),
+    })
+}
+
+/// Messages of one run of verifiable secret sharing, by dealers at
+/// indices `1..=dealers` to recipients at indices `1..=recipients`,
+/// along with the rules for qualifying dealers.
+///
+/// In key generation both index ranges coincide, but resharing moves
+/// shares from one committee to another.
+pub(crate) struct Transcript<E: EngineBLS> {
+    dealers: u32,
+    recipients: u32,
+    threshold: u32,
+    /// Our index as a dealer, if any.
+    dealer: Option<u32>,
+    /// Our index as a recipient, if any.
+    recipient: Option<u32>,
+    phase: DkgPhase,
+    commitments: BTreeMap<u32, VerificationVector<E>>,
+    shares: BTreeMap<u32, E::Scalar>,
+    complainers: BTreeSet<u32>,
+    complaints: BTreeMap<u32, BTreeSet<u32>>,
+    justifications: BTreeMap<u32, BTreeMap<u32, E::Scalar>>,
+}
+
+impl<E: EngineBLS> Transcript<E> {
+    /// Dealers must share polynomials with `threshold` coefficients.
+    pub(crate) fn new(
+        dealers: u32,
+        recipients: u32,
+        threshold: u32,
+        dealer: Option<u32>,
+        recipient: Option<u32>,
+    ) -> Self {
+        Transcript {
+            dealers,
+            recipients,
+            threshold,
+            dealer,
+            recipient,
+            phase: DkgPhase::Dealing,
+            commitments: BTreeMap::new(),
+            shares: BTreeMap::new(),
+            complainers: BTreeSet::new(),
+            complaints: BTreeMap::new(),
+            justifications: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn phase(&self) -> DkgPhase {
+        self.phase
+    }
+
+    pub(crate) fn recipient(&self) -> Option<u32> {
+        self.recipient
+    }
+
+    fn check_dealer(&self, index: u32) -> Result<(), DkgError> {
+        if index == 0 || index > self.dealers {
+            return Err(DkgError::UnknownParticipant(index));
+        }
+        Ok(())
+    }
+
+    fn check_recipient(&self, index: u32) -> Result<(), DkgError> {
+        if index == 0 || index > self.recipients {
+            return Err(DkgError::UnknownParticipant(index));
+        }
+        Ok(())
+    }
+
+    fn expect_phase(&self, phase: DkgPhase) -> Result<(), DkgError> {
+        if self.phase != phase {
+            return Err(DkgError::WrongPhase);
+        }
+        Ok(())
+    }
+
+    /// Our commitments and one private share for every other recipient.
+    pub(crate) fn deal(&mut self, dealer: &Dealer<E>) -> Result<Vec<DkgMessage<E>>, DkgError> {
+        let index = self.dealer.ok_or(DkgError::WrongPhase)?;
+        if self.phase != DkgPhase::Dealing || self.commitments.contains_key(&index) {
+            return Err(DkgError::WrongPhase);
+        }
+        let commitments = dealer.verification_vector();
+        self.commitments.insert(index, commitments.clone());
+        let mut messages = Vec::with_capacity(self.recipients as usize + 1);
+        messages.push(DkgMessage::Deal(DealMessage {
+            dealer: index,
+            commitments,
+        }));
+        for recipient in 1..=self.recipients {
+            let share = dealer.share(recipient).expect("index is non-zero").secret.0;
+            if Some(recipient) == self.recipient {
+                self.shares.insert(index, share);
+            } else {
+                messages.push(DkgMessage::Share(ShareMessage {
+                    dealer: index,
+                    recipient,
+                    share,
+                }));
+            }
+        }
+        Ok(messages)
+    }
+
+    pub(crate) fn handle(&mut self, message: DkgMessage<E>) -> Result<(), DkgError> {
+        match message {
+            DkgMessage::Deal(m) => {
+                self.expect_phase(DkgPhase::Dealing)?;
+                self.check_dealer(m.dealer)?;
+                if self.commitments.contains_key(&m.dealer) {
+                    return Err(DkgError::DuplicateMessage(m.dealer));
+                }
+                self.commitments.insert(m.dealer, m.commitments);
+            }
+            DkgMessage::Share(m) => {
+                self.expect_phase(DkgPhase::Dealing)?;
+                self.check_dealer(m.dealer)?;
+                if Some(m.recipient) != self.recipient {
+                    return Err(DkgError::MisdirectedMessage(m.recipient));
+                }
+                if self.shares.contains_key(&m.dealer) {
+                    return Err(DkgError::DuplicateMessage(m.dealer));
+                }
+                self.shares.insert(m.dealer, m.share);
+            }
+            DkgMessage::Complaint(m) => {
+                self.expect_phase(DkgPhase::Complaining)?;
+                self.check_recipient(m.complainer)?;
+                for dealer in m.dealers.iter() {
+                    self.check_dealer(*dealer)?;
+                }
+                if !self.complainers.insert(m.complainer) {
+                    return Err(DkgError::DuplicateMessage(m.complainer));
+                }
+                self.record_complaints(m.complainer, &m.dealers);
+            }
+            DkgMessage::Justification(m) => {
+                self.expect_phase(DkgPhase::Justifying)?;
+                self.check_dealer(m.dealer)?;
+                if self.justifications.contains_key(&m.dealer) {
+                    return Err(DkgError::DuplicateMessage(m.dealer));
+                }
+                // We ignore shares nobody asked for, as otherwise a dealer
+                // could replace the shares of participants who did not complain.
+                let complainers = self.complaints.get(&m.dealer);
+                let shares = m
+                    .shares
+                    .into_iter()
+                    .filter(|(recipient, _)| complainers.is_some_and(|c| c.contains(recipient)))
+                    .collect();
+                self.justifications.insert(m.dealer, shares);
+            }
+        }
+        Ok(())
+    }
+
+    /// Close the current round and return our messages for the next one.
+    ///
+    /// Recipients always broadcast complaints and dealers always
+    /// broadcast justifications, even if empty.
+    pub(crate) fn advance(
+        &mut self,
+        dealer: Option<&Dealer<E>>,
+    ) -> Result<Vec<DkgMessage<E>>, DkgError> {
+        let mut messages = Vec::new();
+        match self.phase {
+            DkgPhase::Dealing => {
+                if let Some(index) = self.dealer {
+                    if !self.commitments.contains_key(&index) {
+                        return Err(DkgError::WrongPhase);
+                    }
+                }
+                if let Some(index) = self.recipient {
+                    let dealers = (1..=self.dealers)
+                        .filter(|dealer| match self.commitments.get(dealer) {
+                            // Everyone disqualifies dealers without commitments.
+                            None => false,
+                            Some(commitments) => match self.shares.get(dealer) {
+                                None => true,
+                                Some(share) => !share_is_valid(commitments, index, share),
+                            },
+                        })
+                        .collect::<Vec<_>>();
+                    self.complainers.insert(index);
+                    self.record_complaints(index, &dealers);
+                    messages.push(DkgMessage::Complaint(ComplaintMessage {
+                        complainer: index,
+                        dealers,
+                    }));
+                }
+                self.phase = DkgPhase::Complaining;
+            }
+            DkgPhase::Complaining => {
+                if let (Some(index), Some(dealer)) = (self.dealer, dealer) {
+                    let shares = self
+                        .complaints
+                        .get(&index)
+                        .into_iter()
+                        .flatten()
+                        .map(|complainer| {
+                            let share = dealer.share(*complainer).expect("index is non-zero");
+                            (*complainer, share.secret.0)
+                        })
+                        .collect::<Vec<_>>();
+                    self.justifications
+                        .insert(index, shares.iter().cloned().collect());
+                    messages.push(DkgMessage::Justification(JustificationMessage {
+                        dealer: index,
+                        shares,
+                    }));
+                }
+                self.phase = DkgPhase::Justifying;
+            }
+            DkgPhase::Justifying => self.phase = DkgPhase::Finished,
+            DkgPhase::Finished => return Err(DkgError::WrongPhase),
+        }
+        Ok(messages)
+    }
+
+    /// Sorted indices of the dealers who followed the protocol, which
+    /// all honest participants agree upon once the protocol finished.
+    pub(crate) fn qualified(&self) -> Result<Vec<u32>, DkgError> {
+        self.expect_phase(DkgPhase::Finished)?;
+        Ok((1..=self.dealers)
+            .filter(|dealer| self.is_qualified(*dealer))
+            .collect())
+    }
+
+    /// Commitments of a qualified dealer.
+    pub(crate) fn commitments(&self, dealer: u32) -> &VerificationVector<E> {
+        &self.commitments[&dealer]
+    }
+
+    /// Our valid share from a qualified dealer, preferring a justified
+    /// share if it matches the dealer's commitments.
+    pub(crate) fn share_from(&self, dealer: u32) -> Option<E::Scalar> {
+        let recipient = self.recipient?;
+        let commitments = self.commitments.get(&dealer)?;
+        self.justifications
+            .get(&dealer)
+            .and_then(|shares| shares.get(&recipient))
+            .filter(|share| share_is_valid(commitments, recipient, share))
+            .or_else(|| self.shares.get(&dealer))
+            .copied()
+    }
+
+    fn record_complaints(&mut self, complainer: u32, dealers: &[u32]) {
+        for dealer in dealers {
+            self.complaints
+                .entry(*dealer)
+                .or_default()
+                .insert(complainer);
+        }
+    }
+
+    fn is_qualified(&self, dealer: u32) -> bool {
+        let commitments = match self.commitments.get(&dealer) {
+            Some(commitments) if commitments.threshold() == self.threshold as usize => commitments,
+            _ => return false,
+        };
+        let empty = BTreeMap::new();
+        let justification = self.justifications.get(&dealer).unwrap_or(&empty);
+        self.complaints
+            .get(&dealer)
+            .into_iter()
+            .flatten()
+            .all(|complainer| match justification.get(complainer) {
+                Some(share) => share_is_valid(commitments, *complainer, share),
+                None => false,
+            })
+    }
+}
+
+/// One participant of the distributed key generation.
+pub struct Participant<E: EngineBLS> {
+    parameters: DkgParameters,
+    index: u32,
+    dealer: Dealer<E>,
+    transcript: Transcript<E>,
+}
+
+impl<E: EngineBLS> Participant<E> {
+    /// Create the participant at `index`, sampling its polynomial from `rng`.
+    pub fn new<R: Rng>(
+        parameters: DkgParameters,
+        index: u32,
+        mut rng: R,
+    ) -> Result<Self, DkgError> {
+        let parameters = DkgParameters::new(parameters.threshold, parameters.participants)?;
+        parameters.check_index(index)?;
+        let secret = SecretKeyVT::generate(&mut rng);
+        let dealer = Dealer::new(&secret, parameters.threshold as usize, &mut rng)
+            .map_err(|_| DkgError::InvalidParameters)?;
+        let transcript = Transcript::new(
+            parameters.participants,
+            parameters.participants,
+            parameters.threshold,
+            Some(index),
+            Some(index),
+        );
+        Ok(Participant {
+            parameters,
+            index,
+            dealer,
+            transcript,
+        })
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn phase(&self) -> DkgPhase {
+        self.transcript.phase()
+    }
+
+    /// Our first round messages, namely our broadcast commitments and
+    /// one private share for every other participant.
+    pub fn deal(&mut self) -> Result<Vec<DkgMessage<E>>, DkgError> {
+        self.transcript.deal(&self.dealer)
+    }
+
+    /// Process a message from another participant.
+    pub fn handle(&mut self, message: DkgMessage<E>) -> Result<(), DkgError> {
+        self.transcript.handle(message)
+    }
+
+    /// Close the current round and return our messages for the next one.
+    ///
+    /// Closing dealing yields our complaints, and closing complaints
+    /// yields our justification, both of which we always broadcast,
+    /// even if empty.  Closing justifications yields nothing.
+    pub fn advance(&mut self) -> Result<Vec<DkgMessage<E>>, DkgError> {
+        self.transcript.advance(Some(&self.dealer))
+    }
+
+    /// Sorted indices of the dealers who followed the protocol, which
+    /// all honest participants agree upon once the protocol finished.
+    pub fn qualified(&self) -> Result<Vec<u32>, DkgError> {
+        self.transcript.qualified()
+    }
+
+    /// Our share of the group secret key and the group verification vector.
+    pub fn finalize(&self) -> Result<DkgOutput<E>, DkgError> {
+        let qualified = self.qualified()?;
+        if qualified.is_empty() {
+            return Err(DkgError::NoQualifiedDealers);
+        }
+        let mut secret = E::Scalar::zero();
+        let mut verification_vector =
+            alloc::vec![E::PublicKeyGroup::zero(); self.parameters.threshold as usize];
+        for dealer in qualified.iter() {
+            secret += self
+                .transcript
+                .share_from(*dealer)
+                .expect("qualified dealers gave us a share");
+            for (sum, c) in verification_vector
+                .iter_mut()
+                .zip(self.transcript.commitments(*dealer).0.iter())
+            {
+                *sum += c;
+            }
+        }
+        Ok(DkgOutput {
+            share: SecretShare {
+                index: self.index,
+                secret: SecretKeyVT(secret),
+            },
+            verification_vector: VerificationVector(verification_vector),
+            qualified,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::threshold::{combine_partial_signatures, reconstruct_secret};
+    use crate::{Message, TinyBLS381, BLS377, ZBLS};
+
+    /// Round trip a message through its serialization, as a network would.
+    fn transmit<E: EngineBLS>(message: &DkgMessage<E>) -> DkgMessage<E> {
+        let mut bytes = Vec::new();
+        message.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), message.compressed_size());
+        DkgMessage::deserialize_compressed(&bytes[..]).unwrap()
+    }
+
+    /// Deliver messages to their recipients, or to everyone else for
+    /// broadcasts, after `adversary` possibly tampered with or dropped them.
+    fn deliver<E: EngineBLS>(
+        parties: &mut [Participant<E>],
+        messages: Vec<DkgMessage<E>>,
+        adversary: &mut impl FnMut(&mut DkgMessage<E>) -> bool,
+    ) {
+        for mut message in messages {
+            if !adversary(&mut message) {
+                continue;
+            }
+            for party in parties.iter_mut() {
+                let addressed = match message.recipient() {
+                    Some(recipient) => recipient == party.index(),
+                    None => message.sender() != party.index(),
+                };
+                if addressed {
+                    party.handle(transmit(&message)).unwrap();
+                }
+            }
+        }
+    }
+
+    /// Run the protocol in-process among `n` parties.
+    fn simulate<E: EngineBLS>(
+        t: u32,
+        n: u32,
+        mut adversary: impl FnMut(&mut DkgMessage<E>) -> bool,
+    ) -> Vec<Participant<E>> {
+        let parameters = DkgParameters::new(t, n).unwrap();
+        let mut parties = (1..=n)
+            .map(|i| Participant::<E>::new(parameters, i, thread_rng()).unwrap())
+            .collect::<Vec<_>>();
+        let messages = parties
+            .iter_mut()
+            .flat_map(|p| p.deal().unwrap())
+            .collect::<Vec<_>>();
+        deliver(&mut parties, messages, &mut adversary);
+        for _ in 0..3 {
+            let messages = parties
+                .iter_mut()
+                .flat_map(|p| p.advance().unwrap())
+                .collect::<Vec<_>>();
+            deliver(&mut parties, messages, &mut adversary);
+        }
+        assert!(parties.iter().all(|p| p.phase() == DkgPhase::Finished));
+        parties
+    }
+
+    /// Check that honest outputs agree and support threshold signing.
+    fn check_outputs<E: EngineBLS>(outputs: &[DkgOutput<E>], t: usize, qualified: &[u32]) {
+        for output in outputs {
+            assert_eq!(output.qualified, qualified);
+            assert!(output.verification_vector == outputs[0].verification_vector);
+            assert!(output.verification_vector.verify_share(&output.share));
+        }
+        let group_public_key = outputs[0].group_public_key();
+
+        let shares = outputs.iter().map(|o| o.share.clone()).collect::<Vec<_>>();
+        let secret = reconstruct_secret(&shares[..t]).unwrap();
+        assert!(secret.into_public() == group_public_key);
+
+        let message = Message::new(b"ctx", b"dkg message");
+        let partials = shares
+            .iter()
+            .rev()
+            .take(t)
+            .map(|s| s.sign(&message))
+            .collect::<Vec<_>>();
+        let signature = combine_partial_signatures(&partials).unwrap();
+        assert!(signature.verify(&message, &group_public_key));
+    }
+
+    fn honest_run<E: EngineBLS>(t: u32, n: u32) {
+        let parties = simulate::<E>(t, n, |_| true);
+        let outputs = parties
+            .iter()
+            .map(|p| p.finalize().unwrap())
+            .collect::<Vec<_>>();
+        check_outputs(&outputs, t as usize, &(1..=n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn honest_run_for_all_engines() {
+        honest_run::<ZBLS>(3, 5);
+        honest_run::<BLS377>(1, 1);
+        honest_run::<TinyBLS381>(4, 4);
+    }
+
+    #[test]
+    fn justified_complaint_keeps_dealer_qualified() {
+        // Corrupt dealer 2's share for participant 3 in transit, so
+        // dealer 2 reveals the correct share when 3 complains.
+        let parties = simulate::<ZBLS>(2, 4, |message| {
+            if let DkgMessage::Share(m) = message {
+                if m.dealer == 2 && m.recipient == 3 {
+                    m.share += m.share;
+                }
+            }
+            true
+        });
+        assert_eq!(parties[1].transcript.justifications[&2].len(), 1);
+        let outputs = parties
+            .iter()
+            .map(|p| p.finalize().unwrap())
+            .collect::<Vec<_>>();
+        check_outputs(&outputs, 2, &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn unsolicited_justifications_are_ignored() {
+        // Dealer 2 justifies a bad share for participant 3, who never
+        // complained, while participant 4 complains about a bad share.
+        let parties = simulate::<ZBLS>(2, 4, |message| {
+            match message {
+                DkgMessage::Share(m) if m.dealer == 2 && m.recipient == 4 => {
+                    m.share += m.share;
+                }
+                DkgMessage::Justification(m) if m.dealer == 2 => {
+                    m.shares.push((3, <ZBLS as EngineBLS>::Scalar::from(7u64)));
+                }
+                _ => {}
+            }
+            true
+        });
+        assert!(!parties[2].transcript.justifications[&2].contains_key(&3));
+        let outputs = parties
+            .iter()
+            .map(|p| p.finalize().unwrap())
+            .collect::<Vec<_>>();
+        assert!(outputs[2]
+            .verification_vector
+            .verify_share(&outputs[2].share));
+        check_outputs(&outputs, 2, &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn misbehaving_dealers_are_disqualified() {
+        // Dealer 1 sends a bad share and never answers the complaint,
+        // dealer 2 answers with a bad share, and dealer 5 never deals.
+        let parties = simulate::<TinyBLS381>(3, 6, |message| match message {
+            DkgMessage::Deal(m) => m.dealer != 5,
+            DkgMessage::Share(m) => {
+                if (m.dealer == 1 || m.dealer == 2) && m.recipient == 4 {
+                    m.share += m.share;
+                }
+                true
+            }
+            DkgMessage::Justification(m) => {
+                for (_, share) in m.shares.iter_mut() {
+                    *share += *share;
+                }
+                m.dealer != 1
+            }
+            DkgMessage::Complaint(_) => true,
+        });
+        // Misbehaving dealers believe themselves qualified, so we only
+        // check the outputs of honest participants.
+        let outputs = parties
+            .iter()
+            .filter(|p| [3, 4, 6].contains(&p.index()))
+            .map(|p| p.finalize().unwrap())
+            .collect::<Vec<_>>();
+        check_outputs(&outputs, 3, &[3, 4, 6]);
+    }
+
+    #[test]
+    fn protocol_violations_are_rejected() {
+        let parameters = DkgParameters::new(2, 3).unwrap();
+        assert_eq!(DkgParameters::new(0, 3), Err(DkgError::InvalidParameters));
+        assert_eq!(DkgParameters::new(4, 3), Err(DkgError::InvalidParameters));
+        assert!(matches!(
+            Participant::<ZBLS>::new(parameters, 4, thread_rng()),
+            Err(DkgError::UnknownParticipant(4))
+        ));
+
+        let mut p1 = Participant::<ZBLS>::new(parameters, 1, thread_rng()).unwrap();
+        let mut p2 = Participant::<ZBLS>::new(parameters, 2, thread_rng()).unwrap();
+        assert!(matches!(p1.advance(), Err(DkgError::WrongPhase)));
+        let messages = p2.deal().unwrap();
+        assert!(matches!(p2.deal(), Err(DkgError::WrongPhase)));
+        assert!(matches!(p1.finalize(), Err(DkgError::WrongPhase)));
+
+        let deal = transmit(&messages[0]);
+        p1.handle(deal).unwrap();
+        assert!(matches!(
+            p1.handle(transmit(&messages[0])),
+            Err(DkgError::DuplicateMessage(2))
+        ));
+        let share_for_3 = messages.iter().find(|m| m.recipient() == Some(3)).unwrap();
+        assert!(matches!(
+            p1.handle(transmit(share_for_3)),
+            Err(DkgError::MisdirectedMessage(3))
+        ));
+        assert!(matches!(
+            p1.handle(DkgMessage::Complaint(ComplaintMessage {
+                complainer: 2,
+                dealers: Vec::new(),
+            })),
+            Err(DkgError::WrongPhase)
+        ));
+    }
+}
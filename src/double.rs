@@ -15,14 +15,23 @@ use core::iter::once;
 
 use ark_ec::{AffineRepr, CurveGroup};
 
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
 
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::Rng;
 use sha2::Sha256;
 
 use crate::broken_derives;
-use crate::chaum_pedersen_signature::{ChaumPedersenSigner, ChaumPedersenVerifier};
+use crate::chaum_pedersen_signature::{
+    self, ChaumPedersenSigner, ChaumPedersenVerifier, DLEQCommitmentProof,
+};
+use crate::error::{self, BlsError};
 use crate::schnorr_pop::SchnorrProof;
-use crate::serialize::SerializableToBytes;
+use crate::serialize::{
+    point_from_zcash_bytes, point_to_zcash_bytes, zcash_point_size, SerializableToBytes,
+    SerializableWithEncoding,
+};
 use crate::single::{Keypair, KeypairVT, PublicKey, SecretKeyVT, Signature};
 use crate::{EngineBLS, Message, Signed};
 
@@ -40,6 +49,20 @@ impl<E: EngineBLS> DoublePublicKey<E> {
     pub fn verify(&self, message: &Message, signature: &DoubleSignature<E>) -> bool {
         signature.verify(message, self)
     }
+
+    pub fn try_verify(
+        &self,
+        message: &Message,
+        signature: &DoubleSignature<E>,
+    ) -> Result<(), BlsError> {
+        signature.try_verify(message, self)
+    }
+
+    /// Validate both points like `PublicKey::validate`.
+    pub fn validate(&self) -> Result<(), BlsError> {
+        error::check_publickey(&self.0)?;
+        error::check_publickey(&self.1)
+    }
 }
 
 /// Serialization for DoublePublickey
@@ -48,12 +71,51 @@ impl<E: EngineBLS> SerializableToBytes for DoublePublicKey<E> {
         E::SIGNATURE_SERIALIZED_SIZE + E::PUBLICKEY_SERIALIZED_SIZE;
 }
 
+impl<E: EngineBLS> SerializableWithEncoding for DoublePublicKey<E> {
+    fn to_zcash_bytes(&self) -> Vec<u8> {
+        [point_to_zcash_bytes(&self.0), point_to_zcash_bytes(&self.1)].concat()
+    }
+
+    fn from_zcash_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let split = zcash_point_size::<E::SignatureGroup>();
+        if bytes.len() < split {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(DoublePublicKey(
+            point_from_zcash_bytes(&bytes[..split])?,
+            point_from_zcash_bytes(&bytes[split..])?,
+        ))
+    }
+}
+
+/// Serialization for PublicKeyInSignatureGroup
+impl<E: EngineBLS> SerializableToBytes for PublicKeyInSignatureGroup<E> {
+    const SERIALIZED_BYTES_SIZE: usize = E::SIGNATURE_SERIALIZED_SIZE;
+}
+
+impl<E: EngineBLS> SerializableWithEncoding for PublicKeyInSignatureGroup<E> {
+    fn to_zcash_bytes(&self) -> Vec<u8> {
+        point_to_zcash_bytes(&self.0)
+    }
+
+    fn from_zcash_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        point_from_zcash_bytes(bytes).map(PublicKeyInSignatureGroup)
+    }
+}
+
+#[allow(clippy::wrong_self_convention)]
 pub trait DoublePublicKeyScheme<E: EngineBLS> {
     fn into_public_key_in_signature_group(&self) -> PublicKeyInSignatureGroup<E>;
 
     /// Return a double public object containing public keys both in G1 and G2
     fn into_double_public_key(&self) -> DoublePublicKey<E>;
     fn sign(&mut self, message: &Message) -> DoubleSignature<E>;
+
+    /// Sign like `sign`, but with a DLEQ proof carrying its commitments
+    fn sign_batchable(&mut self, message: &Message) -> BatchableDoubleSignature<E> {
+        let publickey = self.into_double_public_key();
+        self.sign(message).to_batchable(message, &publickey)
+    }
 }
 
 impl<E: EngineBLS> DoublePublicKeyScheme<E> for SecretKeyVT<E> {
@@ -73,9 +135,15 @@ impl<E: EngineBLS> DoublePublicKeyScheme<E> for SecretKeyVT<E> {
     /// Sign a message using a Seedabale RNG created from a seed derived from the message and key
     fn sign(&mut self, message: &Message) -> DoubleSignature<E> {
         let chaum_pedersen_signature =
-            ChaumPedersenSigner::<E, Sha256>::generate_cp_signature(self, &message);
+            ChaumPedersenSigner::<E, Sha256>::generate_cp_signature(self, message);
         DoubleSignature(chaum_pedersen_signature.0 .0, chaum_pedersen_signature.1)
     }
+
+    fn sign_batchable(&mut self, message: &Message) -> BatchableDoubleSignature<E> {
+        let chaum_pedersen_signature =
+            ChaumPedersenSigner::<E, Sha256>::generate_batchable_cp_signature(self, message);
+        BatchableDoubleSignature(chaum_pedersen_signature.0 .0, chaum_pedersen_signature.1)
+    }
 }
 
 impl<E: EngineBLS> DoublePublicKeyScheme<E> for KeypairVT<E> {
@@ -91,6 +159,10 @@ impl<E: EngineBLS> DoublePublicKeyScheme<E> for KeypairVT<E> {
     fn sign(&mut self, message: &Message) -> DoubleSignature<E> {
         DoublePublicKeyScheme::sign(&mut self.secret, message)
     }
+
+    fn sign_batchable(&mut self, message: &Message) -> BatchableDoubleSignature<E> {
+        DoublePublicKeyScheme::sign_batchable(&mut self.secret, message)
+    }
 }
 
 impl<E: EngineBLS> DoublePublicKeyScheme<E> for Keypair<E> {
@@ -106,6 +178,10 @@ impl<E: EngineBLS> DoublePublicKeyScheme<E> for Keypair<E> {
     fn sign(&mut self, message: &Message) -> DoubleSignature<E> {
         DoublePublicKeyScheme::sign(&mut self.into_vartime(), message)
     }
+
+    fn sign_batchable(&mut self, message: &Message) -> BatchableDoubleSignature<E> {
+        DoublePublicKeyScheme::sign_batchable(&mut self.into_vartime(), message)
+    }
 }
 
 /// Detached BLS Signature containing DLEQ
@@ -119,10 +195,114 @@ impl<E: EngineBLS> DoubleSignature<E> {
     pub fn verify(&self, message: &Message, publickey: &DoublePublicKey<E>) -> bool {
         <PublicKeyInSignatureGroup<E> as ChaumPedersenVerifier<E, Sha256>>::verify_cp_signature(
             &PublicKeyInSignatureGroup(publickey.0),
-            &message,
+            message,
             (Signature(self.0), self.1),
         )
     }
+
+    /// Verify a single BLS signature using DLEQ proof, explaining any failure.
+    pub fn try_verify(
+        &self,
+        message: &Message,
+        publickey: &DoublePublicKey<E>,
+    ) -> Result<(), BlsError> {
+        <PublicKeyInSignatureGroup<E> as ChaumPedersenVerifier<E, Sha256>>::try_verify_cp_signature(
+            &PublicKeyInSignatureGroup(publickey.0),
+            message,
+            (Signature(self.0), self.1),
+        )
+    }
+
+    /// Recompute the DLEQ commitments so this signature may join a batch.
+    pub fn to_batchable(
+        &self,
+        message: &Message,
+        publickey: &DoublePublicKey<E>,
+    ) -> BatchableDoubleSignature<E> {
+        let (signature, proof) = chaum_pedersen_signature::to_batchable_cp_signature::<E>(
+            message,
+            &PublicKeyInSignatureGroup(publickey.0),
+            &(Signature(self.0), self.1),
+        );
+        BatchableDoubleSignature(signature.0, proof)
+    }
+}
+
+/// Detached BLS Signature containing a DLEQ proof with its commitments,
+/// which verifiers may check many at once with `verify_batch`.
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BatchableDoubleSignature<E: EngineBLS>(pub E::SignatureGroup, DLEQCommitmentProof<E>);
+
+impl<E: EngineBLS> Clone for BatchableDoubleSignature<E> {
+    fn clone(&self) -> Self {
+        BatchableDoubleSignature(self.0, self.1)
+    }
+}
+
+impl<E: EngineBLS> BatchableDoubleSignature<E> {
+    /// Verify a single BLS signature using DLEQ proof
+    pub fn verify(&self, message: &Message, publickey: &DoublePublicKey<E>) -> bool {
+        self.to_double_signature(message, publickey)
+            .verify(message, publickey)
+    }
+
+    /// Recover the compact `DoubleSignature`, by computing the challenge.
+    pub fn to_double_signature(
+        &self,
+        message: &Message,
+        publickey: &DoublePublicKey<E>,
+    ) -> DoubleSignature<E> {
+        let (signature, proof) = chaum_pedersen_signature::to_cp_signature::<E, Sha256>(
+            message,
+            &PublicKeyInSignatureGroup(publickey.0),
+            &(Signature(self.0), self.1),
+        );
+        DoubleSignature(signature.0, proof)
+    }
+
+    /// Verify many signatures together using one multi-scalar
+    /// multiplication, like `chaum_pedersen_signature::verify_cp_signatures_batch`.
+    pub fn verify_batch_with_rng<'a, I, R>(signatures: I, rng: R) -> bool
+    where
+        E: 'a,
+        I: IntoIterator<
+            Item = (
+                &'a Message,
+                &'a DoublePublicKey<E>,
+                &'a BatchableDoubleSignature<E>,
+            ),
+        >,
+        R: Rng,
+    {
+        chaum_pedersen_signature::verify_dleq_batch::<E, Sha256, _, R>(
+            signatures
+                .into_iter()
+                .map(|(message, publickey, signature)| {
+                    (message, publickey.0, signature.0, &signature.1)
+                }),
+            rng,
+        )
+    }
+
+    #[cfg(feature = "std")]
+    pub fn verify_batch<'a, I>(signatures: I) -> bool
+    where
+        E: 'a,
+        I: IntoIterator<
+            Item = (
+                &'a Message,
+                &'a DoublePublicKey<E>,
+                &'a BatchableDoubleSignature<E>,
+            ),
+        >,
+    {
+        Self::verify_batch_with_rng(signatures, thread_rng())
+    }
+}
+
+/// Serialization for BatchableDoubleSignature
+impl<E: EngineBLS> SerializableToBytes for BatchableDoubleSignature<E> {
+    const SERIALIZED_BYTES_SIZE: usize = 3 * E::SIGNATURE_SERIALIZED_SIZE + E::SECRET_KEY_SIZE;
 }
 
 /// Message with attached BLS signature
@@ -144,7 +324,7 @@ impl<E: EngineBLS> PartialEq<Self> for DoubleSignedMessage<E> {
     }
 }
 
-impl<'a, E: EngineBLS> Signed for &'a DoubleSignedMessage<E> {
+impl<E: EngineBLS> Signed for &DoubleSignedMessage<E> {
     type E = E;
 
     type M = Message;
@@ -168,6 +348,10 @@ impl<'a, E: EngineBLS> Signed for &'a DoubleSignedMessage<E> {
             (Signature(self.signature.0), self.signature.1),
         )
     }
+
+    fn try_verify(self) -> Result<(), BlsError> {
+        self.signature.try_verify(&self.message, &self.publickey)
+    }
 }
 
 /// Serialization for DoubleSignature
@@ -188,6 +372,7 @@ mod tests {
     use ark_ec::hashing::map_to_curve_hasher::MapToCurve;
     use ark_ec::pairing::Pairing as PairingEngine;
 
+    use crate::serialize::Encoding;
     use crate::{EngineBLS, Message, TinyBLS};
 
     fn double_public_serialization_test<
@@ -229,7 +414,7 @@ mod tests {
         let good = Message::new(b"ctx", b"test message");
 
         let mut keypair = Keypair::<EB>::generate(thread_rng());
-        let public_key = DoublePublicKeyScheme::into_double_public_key(&mut keypair);
+        let public_key = DoublePublicKeyScheme::into_double_public_key(&keypair);
         let good_sig = DoublePublicKeyScheme::sign(&mut keypair, &good);
 
         assert!(
@@ -272,7 +457,7 @@ mod tests {
         let good_sig0 = DoublePublicKeyScheme::sign(&mut keypair, &message);
 
         let signed_message = DoubleSignedMessage {
-            message: message,
+            message,
             publickey: DoublePublicKey(
                 keypair.into_public_key_in_signature_group().0,
                 keypair.public.0,
@@ -305,7 +490,7 @@ mod tests {
         let good_sig0 = DoublePublicKeyScheme::sign(&mut keypair, &message);
 
         let signed_message = DoubleSignedMessage {
-            message: message,
+            message,
             publickey: DoublePublicKey(
                 keypair.into_public_key_in_signature_group().0,
                 keypair.public.0,
@@ -347,4 +532,171 @@ mod tests {
             ark_bls12_381::Config,
         >();
     }
+
+    fn zcash_encoding_roundtrip<E: EngineBLS>() {
+        let keypair = KeypairVT::<E>::generate(thread_rng());
+        let double_public_key = keypair.into_double_public_key();
+        let bytes = double_public_key.to_bytes_with_encoding(Encoding::ZCash);
+        assert_eq!(bytes.len(), DoublePublicKey::<E>::SERIALIZED_BYTES_SIZE);
+        let decoded = DoublePublicKey::<E>::from_zcash_bytes(&bytes).unwrap();
+        assert_eq!(decoded.0, double_public_key.0);
+        assert_eq!(decoded.1, double_public_key.1);
+        assert!(DoublePublicKey::<E>::from_zcash_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let public_key_in_signature_group = keypair.into_public_key_in_signature_group();
+        let bytes = public_key_in_signature_group.to_zcash_bytes();
+        assert_eq!(bytes, double_public_key.to_zcash_bytes()[..bytes.len()]);
+        assert!(
+            PublicKeyInSignatureGroup::<E>::from_bytes_with_encoding(&bytes, Encoding::ZCash)
+                .unwrap()
+                == public_key_in_signature_group
+        );
+        assert!(
+            PublicKeyInSignatureGroup::<E>::from_bytes(
+                &public_key_in_signature_group.to_bytes_with_encoding(Encoding::Arkworks)
+            )
+            .unwrap()
+                == public_key_in_signature_group
+        );
+    }
+
+    #[test]
+    fn zcash_encoding_for_all_engines() {
+        zcash_encoding_roundtrip::<crate::ZBLS>();
+        zcash_encoding_roundtrip::<crate::BLS377>();
+        zcash_encoding_roundtrip::<crate::TinyBLS381>();
+        zcash_encoding_roundtrip::<crate::TinyBLS377>();
+    }
+
+    fn batch_verification<E: EngineBLS>() {
+        use ark_ff::Zero;
+
+        let messages = (0..8)
+            .map(|i| Message::new(b"ctx", &[i as u8]))
+            .collect::<Vec<_>>();
+        let mut keypairs = (0..8)
+            .map(|_| Keypair::<E>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let mut publickeys = keypairs
+            .iter()
+            .map(DoublePublicKeyScheme::into_double_public_key)
+            .collect::<Vec<_>>();
+        let signatures = keypairs
+            .iter_mut()
+            .zip(&messages)
+            .map(|(keypair, message)| DoublePublicKeyScheme::sign_batchable(keypair, message))
+            .collect::<Vec<_>>();
+        let batch = |messages: &[Message],
+                     publickeys: &[DoublePublicKey<E>],
+                     signatures: &[BatchableDoubleSignature<E>]| {
+            BatchableDoubleSignature::verify_batch(
+                messages
+                    .iter()
+                    .zip(publickeys)
+                    .zip(signatures)
+                    .map(|((m, pk), sig)| (m, pk, sig)),
+            )
+        };
+        assert!(batch(&messages, &publickeys, &signatures));
+        assert!(batch(&[], &[], &[]));
+
+        // Both forms agree and verify alone.
+        let compact = DoublePublicKeyScheme::sign(&mut keypairs[0], &messages[0]);
+        let converted = signatures[0].to_double_signature(&messages[0], &publickeys[0]);
+        assert_eq!(converted.0, compact.0);
+        assert_eq!(converted.1, compact.1);
+        let recommitted = compact.to_batchable(&messages[0], &publickeys[0]);
+        assert_eq!(recommitted.1, signatures[0].1);
+        assert!(signatures[0].verify(&messages[0], &publickeys[0]));
+        assert!(!signatures[0].verify(&messages[1], &publickeys[0]));
+
+        // Wrong messages, tampered proofs and identity keys fail.
+        let mut swapped = messages.clone();
+        swapped.swap(2, 3);
+        assert!(!batch(&swapped, &publickeys, &signatures));
+        let mut tampered = signatures.clone();
+        tampered[4].1 .2 += E::Scalar::from(1u64);
+        assert!(!batch(&messages, &publickeys, &tampered));
+        publickeys[5].0 = E::SignatureGroup::zero();
+        assert!(!batch(&messages, &publickeys, &signatures));
+    }
+
+    #[test]
+    fn batch_verification_for_all_engines() {
+        batch_verification::<crate::ZBLS>();
+        batch_verification::<crate::TinyBLS381>();
+    }
+
+    /// Signer relying upon the default batchable signing methods.
+    struct DefaultSigner(SecretKeyVT<crate::ZBLS>);
+
+    impl DoublePublicKeyScheme<crate::ZBLS> for DefaultSigner {
+        fn into_public_key_in_signature_group(&self) -> PublicKeyInSignatureGroup<crate::ZBLS> {
+            self.0.into_public_key_in_signature_group()
+        }
+
+        fn into_double_public_key(&self) -> DoublePublicKey<crate::ZBLS> {
+            self.0.into_double_public_key()
+        }
+
+        fn sign(&mut self, message: &Message) -> DoubleSignature<crate::ZBLS> {
+            DoublePublicKeyScheme::sign(&mut self.0, message)
+        }
+    }
+
+    impl ChaumPedersenSigner<crate::ZBLS, Sha256> for DefaultSigner {
+        fn generate_cp_signature(
+            &mut self,
+            message: &Message,
+        ) -> chaum_pedersen_signature::ChaumPedersenSignature<crate::ZBLS> {
+            ChaumPedersenSigner::<crate::ZBLS, Sha256>::generate_cp_signature(&mut self.0, message)
+        }
+
+        fn generate_witness_scaler(
+            &self,
+            message_point_as_bytes: &[u8],
+        ) -> <crate::ZBLS as EngineBLS>::Scalar {
+            ChaumPedersenSigner::<crate::ZBLS, Sha256>::generate_witness_scaler(
+                &self.0,
+                message_point_as_bytes,
+            )
+        }
+
+        fn generate_dleq_proof(
+            &mut self,
+            message: &Message,
+            bls_signature: <crate::ZBLS as EngineBLS>::SignatureGroup,
+        ) -> crate::schnorr_pop::SchnorrProof<crate::ZBLS> {
+            ChaumPedersenSigner::<crate::ZBLS, Sha256>::generate_dleq_proof(
+                &mut self.0,
+                message,
+                bls_signature,
+            )
+        }
+    }
+
+    #[test]
+    fn default_batchable_signing() {
+        let message = Message::new(b"ctx", b"test message");
+        let mut secret = SecretKeyVT::<crate::ZBLS>::generate(thread_rng());
+        let mut signer = DefaultSigner(secret.clone());
+        let publickey = secret.into_double_public_key();
+
+        let expected = DoublePublicKeyScheme::sign_batchable(&mut secret, &message);
+        let signature = DoublePublicKeyScheme::sign_batchable(&mut signer, &message);
+        assert_eq!(signature.0, expected.0);
+        assert_eq!(signature.1, expected.1);
+        assert!(signature.verify(&message, &publickey));
+
+        let expected = ChaumPedersenSigner::<crate::ZBLS, Sha256>::generate_batchable_cp_signature(
+            &mut secret,
+            &message,
+        );
+        let signature = ChaumPedersenSigner::<crate::ZBLS, Sha256>::generate_batchable_cp_signature(
+            &mut signer,
+            &message,
+        );
+        assert!(signature.0 == expected.0);
+        assert_eq!(signature.1, expected.1);
+    }
 }
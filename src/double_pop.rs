@@ -4,6 +4,7 @@
 //! [draft-irtf-cfrg-bls-signature-05](https://www.ietf.org/archive/id/draft-irtf-cfrg-bls-signature-05.html)
 
 use crate::engine::EngineBLS;
+use crate::error::{self, BlsError};
 use crate::{DoubleSignature, Message, ProofOfPossession, ProofOfPossessionGenerator};
 
 use crate::double::{DoublePublicKey, DoublePublicKeyScheme};
@@ -12,9 +13,13 @@ use crate::single::{Keypair, PublicKey};
 
 use alloc::vec::Vec;
 use digest::{DynDigest, FixedOutputReset};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::Rng;
 
-use ark_ec::PrimeGroup;
+use ark_ec::{CurveGroup, PrimeGroup};
 use ark_ff::field_hashers::{DefaultFieldHasher, HashToField};
+use ark_ff::Zero;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 
 /// Proof Of Possession of the secret key as the secret scaler genarting both public
@@ -48,49 +53,154 @@ impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>
     /// we verifying by calling the verify_prepared ⎈function from the
     /// engine.
     fn verify(&self, public_key_of_prover: &DoublePublicKey<E>) -> bool {
-        //First we need to generate our randomness in a way that
-        //prover is unable to predict. We assume g1 and g2 are fixed.
-
-        let public_key_as_bytes =
-            <E as EngineBLS>::public_key_point_to_byte(&public_key_of_prover.1);
-        let public_key_in_signature_group = public_key_of_prover.0;
-        let public_key_in_signature_group_as_bytes =
-            E::signature_point_to_byte(&public_key_in_signature_group);
-
-        let public_key_hashed_to_signature_group =
-            Message::new_pop_message(b"", &public_key_as_bytes).hash_to_signature_curve::<E>();
-        let public_key_hashed_to_signature_group_as_bytes =
-            E::signature_point_to_byte(&public_key_hashed_to_signature_group);
-        let random_oracle_seed = [
-            public_key_hashed_to_signature_group_as_bytes,
-            public_key_as_bytes,
-            public_key_in_signature_group_as_bytes,
-            E::signature_point_to_byte(&self.0),
-        ]
-        .concat();
-
-        let hasher = <DefaultFieldHasher<H> as HashToField<
-            <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField,
-        >>::new(&[]);
-
-        let randomization_coefficient: E::Scalar =
-            hasher.hash_to_field::<1>(random_oracle_seed.as_slice())[0];
-
-        let mut randomized_pub_in_g1 = public_key_in_signature_group;
-        randomized_pub_in_g1 *= randomization_coefficient;
-        let signature = E::prepare_signature(self.0 + randomized_pub_in_g1);
-        let prepared_public_key = E::prepare_public_key(public_key_of_prover.1);
-        let prepared = [
-            (
-                prepared_public_key.clone(),
-                E::prepare_signature(public_key_hashed_to_signature_group),
-            ),
-            (
-                prepared_public_key.clone(),
-                E::prepare_signature(E::generator_of_signature_group() * randomization_coefficient),
+        let (signature, message) = nugget_equation::<E, H>(self, public_key_of_prover);
+        let prepared = [(
+            E::prepare_public_key(public_key_of_prover.1),
+            E::prepare_signature(message),
+        )];
+        E::verify_prepared(E::prepare_signature(signature), prepared.iter())
+    }
+
+    fn try_verify(&self, public_key_of_prover: &DoublePublicKey<E>) -> Result<(), BlsError> {
+        error::check_publickey(&public_key_of_prover.0)?;
+        error::check_publickey(&public_key_of_prover.1)?;
+        error::check_subgroup(&self.0)?;
+        error::ensure(
+            <Self as ProofOfPossession<E, H, DoublePublicKey<E>>>::verify(
+                self,
+                public_key_of_prover,
             ),
-        ];
-        E::verify_prepared(signature, prepared.iter())
+            BlsError::PairingMismatch,
+        )
+    }
+}
+
+/// Both sides of the nugget PoP pairing equation
+/// e(H_pop(pk_2) + t.g_1, pk_2) = e(sign(H_pop(pk_2))+ t.pk_1, g_2),
+/// so `sign(H_pop(pk_2))+ t.pk_1` and `H_pop(pk_2) + t.g_1`, where we
+/// merge the two pairings with `pk_2` into one.
+fn nugget_equation<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>(
+    pop: &NuggetBLSPoP<E>,
+    public_key_of_prover: &DoublePublicKey<E>,
+) -> (E::SignatureGroup, E::SignatureGroup) {
+    //First we need to generate our randomness in a way that
+    //prover is unable to predict. We assume g1 and g2 are fixed.
+
+    let public_key_as_bytes = <E as EngineBLS>::public_key_point_to_byte(&public_key_of_prover.1);
+    let public_key_in_signature_group = public_key_of_prover.0;
+    let public_key_in_signature_group_as_bytes =
+        E::signature_point_to_byte(&public_key_in_signature_group);
+
+    let public_key_hashed_to_signature_group =
+        Message::new_pop_message(b"", &public_key_as_bytes).hash_to_signature_curve::<E>();
+    let public_key_hashed_to_signature_group_as_bytes =
+        E::signature_point_to_byte(&public_key_hashed_to_signature_group);
+    let random_oracle_seed = [
+        public_key_hashed_to_signature_group_as_bytes,
+        public_key_as_bytes,
+        public_key_in_signature_group_as_bytes,
+        E::signature_point_to_byte(&pop.0),
+    ]
+    .concat();
+
+    let hasher = <DefaultFieldHasher<H> as HashToField<
+        <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField,
+    >>::new(&[]);
+
+    let randomization_coefficient: E::Scalar =
+        hasher.hash_to_field::<1>(random_oracle_seed.as_slice())[0];
+
+    let mut randomized_pub_in_g1 = public_key_in_signature_group;
+    randomized_pub_in_g1 *= randomization_coefficient;
+    (
+        pop.0 + randomized_pub_in_g1,
+        public_key_hashed_to_signature_group
+            + E::generator_of_signature_group() * randomization_coefficient,
+    )
+}
+
+impl<E: EngineBLS> NuggetBLSPoP<E> {
+    /// Verify the proofs of possession for `publickeys` together in one
+    /// multi-Miller loop and one final exponentiation, by checking
+    ///
+    /// e(sum z_i (sign(H_pop(pk_2i)) + t_i.pk_1i), g_2) = prod_i e(H_pop(pk_2i) + t_i.g_1, z_i.pk_2i)
+    ///
+    /// with 128 bit coefficients `z_i` drawn from `rng`.
+    ///
+    /// If the batch fails then we verify every proof alone, and return
+    /// the indices of the failing keys.  We always fail identity or
+    /// non-subgroup keys, and any keys or proofs lacking a counterpart.
+    pub fn verify_batch_with_rng<H, R>(
+        publickeys: &[DoublePublicKey<E>],
+        proofs: &[NuggetBLSPoP<E>],
+        mut rng: R,
+    ) -> Result<(), Vec<usize>>
+    where
+        H: DynDigest + FixedOutputReset + Default + Clone,
+        R: Rng,
+    {
+        let mut failed = Vec::new();
+        let mut signature = E::SignatureGroup::zero();
+        let mut weighted_publickeys = Vec::with_capacity(proofs.len());
+        let mut messages = Vec::with_capacity(proofs.len());
+        for (i, (publickey, pop)) in publickeys.iter().zip(proofs).enumerate() {
+            if publickey.validate().is_err() {
+                failed.push(i);
+                continue;
+            }
+            let z = E::Scalar::from(rng.gen::<u128>());
+            let (sig, message) = nugget_equation::<E, H>(pop, publickey);
+            signature += sig * z;
+            weighted_publickeys.push(publickey.1 * z);
+            messages.push(message);
+        }
+        failed.extend(publickeys.len().min(proofs.len())..publickeys.len().max(proofs.len()));
+
+        let publickeys_affine = E::PublicKeyGroup::normalize_batch(&weighted_publickeys);
+        let messages_affine = E::SignatureGroup::normalize_batch(&messages);
+        let prepared = publickeys_affine
+            .into_iter()
+            .zip(messages_affine)
+            .map(|(publickey, message)| {
+                (
+                    E::prepare_public_key(publickey),
+                    E::prepare_signature(message),
+                )
+            })
+            .collect::<Vec<_>>();
+        if E::verify_prepared(E::prepare_signature(signature), prepared.iter()) {
+            return if failed.is_empty() {
+                Ok(())
+            } else {
+                Err(failed)
+            };
+        }
+
+        // Some valid key's proof fails, so find it.
+        failed.extend(
+            publickeys
+                .iter()
+                .zip(proofs)
+                .enumerate()
+                .filter(|(i, (publickey, pop))| {
+                    !failed.contains(i)
+                        && !<Self as ProofOfPossession<E, H, DoublePublicKey<E>>>::verify(
+                            pop, publickey,
+                        )
+                })
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>(),
+        );
+        failed.sort_unstable();
+        Err(failed)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn verify_batch<H: DynDigest + FixedOutputReset + Default + Clone>(
+        publickeys: &[DoublePublicKey<E>],
+        proofs: &[NuggetBLSPoP<E>],
+    ) -> Result<(), Vec<usize>> {
+        Self::verify_batch_with_rng::<H, _>(publickeys, proofs, thread_rng())
     }
 }
 
@@ -109,7 +219,7 @@ impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>
         let public_key_as_bytes = self.public.to_bytes();
         let sigma_pop = DoublePublicKeyScheme::<E>::sign(
             self,
-            &Message::new_pop_message(b"", &public_key_as_bytes.as_slice()),
+            &Message::new_pop_message(b"", public_key_as_bytes.as_slice()),
         );
 
         NuggetBLSnCPPoP::<E>(sigma_pop)
@@ -136,7 +246,21 @@ impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>
             &NuggetBLSPoP::<E>(self.0 .0),
             public_key_of_prover,
         ) && public_key_of_prover.verify(
-            &Message::new_pop_message(b"", &public_key_in_public_key_group_as_bytes.as_slice()),
+            &Message::new_pop_message(b"", public_key_in_public_key_group_as_bytes.as_slice()),
+            &self.0,
+        )
+    }
+
+    /// Report the first of the nugget BLS and the CP checks which fails.
+    fn try_verify(&self, public_key_of_prover: &DoublePublicKey<E>) -> Result<(), BlsError> {
+        let public_key_in_public_key_group_as_bytes =
+            PublicKey::<E>(public_key_of_prover.1).to_bytes();
+        <NuggetBLSPoP<E> as ProofOfPossession<E, H, DoublePublicKey<E>>>::try_verify(
+            &NuggetBLSPoP::<E>(self.0 .0),
+            public_key_of_prover,
+        )?;
+        public_key_of_prover.try_verify(
+            &Message::new_pop_message(b"", public_key_in_public_key_group_as_bytes.as_slice()),
             &self.0,
         )
     }
@@ -290,4 +414,60 @@ mod tests {
             NuggetBLSnCPPoP<TinyBLS381>,
         >();
     }
+
+    #[test]
+    fn nugget_bls_pop_batch_verification() {
+        use ark_ff::Zero;
+
+        let mut keypairs = (0..8)
+            .map(|_| Keypair::<TinyBLS381>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let mut publickeys = keypairs
+            .iter()
+            .map(DoublePublicKeyScheme::into_double_public_key)
+            .collect::<Vec<DoublePublicKey<TinyBLS381>>>();
+        let mut proofs = keypairs
+            .iter_mut()
+            .map(|keypair| {
+                ProofOfPossessionGenerator::<
+                    TinyBLS381,
+                    Sha256,
+                    DoublePublicKey<TinyBLS381>,
+                    NuggetBLSPoP<TinyBLS381>,
+                >::generate_pok(keypair)
+            })
+            .collect::<Vec<_>>();
+        assert!(NuggetBLSPoP::verify_batch::<Sha256>(&publickeys, &proofs).is_ok());
+        assert!(NuggetBLSPoP::<TinyBLS381>::verify_batch::<Sha256>(&[], &[]).is_ok());
+        assert_eq!(
+            NuggetBLSPoP::verify_batch::<Sha256>(&publickeys[..6], &proofs),
+            Err(vec![6, 7])
+        );
+
+        // Failing proofs get reported, even when they cancel out.
+        let delta = proofs[1].0;
+        proofs[2].0 += delta;
+        proofs[5].0 -= delta;
+        assert_eq!(
+            NuggetBLSPoP::verify_batch::<Sha256>(&publickeys, &proofs),
+            Err(vec![2, 5])
+        );
+
+        // Anyone can prove possession for the identity.
+        publickeys[2] = DoublePublicKey(Zero::zero(), Zero::zero());
+        proofs[2] = NuggetBLSPoP(Zero::zero());
+        assert!(ProofOfPossession::<TinyBLS381, Sha256, _>::verify(
+            &proofs[2],
+            &publickeys[2]
+        ));
+        assert_eq!(
+            NuggetBLSPoP::verify_batch::<Sha256>(&publickeys, &proofs),
+            Err(vec![2, 5])
+        );
+        proofs[5].0 += delta;
+        assert_eq!(
+            NuggetBLSPoP::verify_batch::<Sha256>(&publickeys, &proofs),
+            Err(vec![2])
+        );
+    }
 }
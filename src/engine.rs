@@ -129,16 +129,20 @@ pub trait EngineBLS {
         Self::Scalar::rand(rng)
     }
 
-    /// getter function for the hash to curve map
-    fn hash_to_curve_map() -> MapToCurveBasedHasher<
+    /// getter function for the hash to curve map, keyed by the
+    /// domain separation tag `dst` as in the IETF hash-to-curve draft.
+    fn hash_to_curve_map(
+        dst: &[u8],
+    ) -> MapToCurveBasedHasher<
         Self::SignatureGroup,
         Self::HashToSignatureField,
         Self::MapToSignatureCurve,
     >;
 
-    /// Hash one message to the signature curve.
-    fn hash_to_signature_curve<M: Borrow<[u8]>>(message: M) -> Self::SignatureGroup {
-        Self::hash_to_curve_map()
+    /// Hash one message to the signature curve under the domain
+    /// separation tag `dst`, which is normally the ciphersuite id.
+    fn hash_to_signature_curve<M: Borrow<[u8]>>(message: M, dst: &[u8]) -> Self::SignatureGroup {
+        Self::hash_to_curve_map(dst)
             .hash(message.borrow())
             .unwrap()
             .into_group()
@@ -185,6 +189,7 @@ pub trait EngineBLS {
     /// properties like message distinctness.  It exists purely to
     /// simplify replacing mid-level routines with optimized variants,
     /// like versions that cache public key preperation or use fewer pairings.
+    #[allow(clippy::map_identity)] // the identity map narrows `'a` to the lifetime of `lhs`
     fn verify_prepared<'a, I>(signature: Self::SignaturePrepared, inputs: I) -> bool
     where
         Self::PublicKeyPrepared: 'a,
@@ -250,7 +255,7 @@ pub trait EngineBLS {
 pub type ZBLS = UsualBLS<ark_bls12_381::Bls12_381, ark_bls12_381::Config>;
 pub type BLS377 = UsualBLS<ark_bls12_377::Bls12_377, ark_bls12_377::Config>;
 
-/// Usual aggregate BLS signature scheme on ZCash's BLS12-381 curve.
+// Usual aggregate BLS signature scheme on ZCash's BLS12-381 curve.
 // pub const Z_BLS : ZBLS = UsualBLS(::zexe_algebra::bls12_381::Bls12_381{});
 
 /// Usual BLS variant with tiny 48 byte public keys and 96 byte signatures.
@@ -323,7 +328,9 @@ where
         )
     }
 
-    fn hash_to_curve_map() -> MapToCurveBasedHasher<
+    fn hash_to_curve_map(
+        dst: &[u8],
+    ) -> MapToCurveBasedHasher<
         Self::SignatureGroup,
         Self::HashToSignatureField,
         Self::MapToSignatureCurve,
@@ -332,7 +339,7 @@ where
             Self::SignatureGroup,
             DefaultFieldHasher<Sha256, 128>,
             WBMap<P::G2Config>,
-        >::new(&[1])
+        >::new(dst)
         .unwrap()
     }
 }
@@ -414,7 +421,9 @@ where
         )
     }
 
-    fn hash_to_curve_map() -> MapToCurveBasedHasher<
+    fn hash_to_curve_map(
+        dst: &[u8],
+    ) -> MapToCurveBasedHasher<
         Self::SignatureGroup,
         Self::HashToSignatureField,
         Self::MapToSignatureCurve,
@@ -423,7 +432,7 @@ where
             Self::SignatureGroup,
             DefaultFieldHasher<Sha256, 128>,
             WBMap<P::G1Config>,
-        >::new(&[1])
+        >::new(dst)
         .unwrap()
     }
 }
@@ -0,0 +1,314 @@
+//! ## Verification errors
+//!
+//! Our verification routines return `bool`, but most have a `try_`
+//! variant that explains why verification failed, so that callers
+//! can log or report the reason for rejecting a signature.
+
+use ark_ec::CurveGroup;
+
+/// Reasons why a signature, aggregate or proof fails verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum BlsError {
+    /// Some public key is the identity, for which anyone can sign.
+    IdentityPublicKey,
+    /// A signature is the identity, which strict validation rejects.
+    IdentitySignature,
+    /// Some point lies outside the prime order subgroup.
+    NotInSubgroup,
+    /// An aggregate contains no messages or public keys.
+    EmptyAggregate,
+    /// The pairing equation does not hold.
+    PairingMismatch,
+    /// A Schnorr or Chaum-Pedersen proof does not reproduce its
+    /// Fiat-Shamir challenge.
+    FiatShamirMismatch,
+    /// A proof-of-possession failed, for reasons its type does not report.
+    InvalidProofOfPossession,
+    /// Auxiliary public keys are missing for some message of an
+    /// aggregate, or given for messages it does not contain.
+    AuxiliaryPublicKeyMismatch,
+    /// A public key was revoked, so we refuse to register it again.
+    RevokedPublicKey,
+    /// Some signature or aggregate is on messages other than those of
+    /// the aggregate it should be combined with.
+    MessageMismatch,
+}
+
+impl ::core::fmt::Display for BlsError {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        use self::BlsError::*;
+        match self {
+            IdentityPublicKey => write!(f, "Public key is the identity point."),
+            IdentitySignature => write!(f, "Signature is the identity point."),
+            NotInSubgroup => write!(f, "Point is not in the prime order subgroup."),
+            EmptyAggregate => write!(f, "Aggregate contains no messages or public keys."),
+            PairingMismatch => write!(f, "Pairing equation does not hold."),
+            FiatShamirMismatch => write!(f, "Proof does not match its Fiat-Shamir challenge."),
+            InvalidProofOfPossession => write!(f, "Invalid proof-of-possession."),
+            AuxiliaryPublicKeyMismatch => write!(
+                f,
+                "Auxiliary public keys do not match the aggregate's messages."
+            ),
+            RevokedPublicKey => write!(f, "Public key was revoked."),
+            MessageMismatch => write!(f, "Messages do not match the aggregate's messages."),
+        }
+    }
+}
+
+/// Check that `point` lies on the curve and in the prime order subgroup,
+/// as when deserializing with validation.
+pub(crate) fn check_subgroup<G: CurveGroup>(point: &G) -> Result<(), BlsError> {
+    if point.check().is_ok() {
+        Ok(())
+    } else {
+        Err(BlsError::NotInSubgroup)
+    }
+}
+
+/// Check that the public key `point` is neither the identity nor
+/// outside the prime order subgroup.
+pub(crate) fn check_publickey<G: CurveGroup>(point: &G) -> Result<(), BlsError> {
+    if point.is_zero() {
+        return Err(BlsError::IdentityPublicKey);
+    }
+    check_subgroup(point)
+}
+
+/// Check that the signature `point` is neither the identity nor
+/// outside the prime order subgroup.
+pub(crate) fn check_signature<G: CurveGroup>(point: &G) -> Result<(), BlsError> {
+    if point.is_zero() {
+        return Err(BlsError::IdentitySignature);
+    }
+    check_subgroup(point)
+}
+
+/// Report `failure` unless `ok`.
+pub(crate) fn ensure(ok: bool, failure: BlsError) -> Result<(), BlsError> {
+    if ok {
+        Ok(())
+    } else {
+        Err(failure)
+    }
+}
+
+/// Input validation shared by our aggregators, which reject invalid
+/// inputs in strict mode, as the IETF BLS signature draft requires.
+///
+/// We skip invalid inputs in strict mode, but remember the first
+/// failure so that verification fails with it.  Otherwise we accept
+/// every input, and leave invalid points to fail verification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StrictInputs {
+    strict: bool,
+    invalid: Option<BlsError>,
+}
+
+impl StrictInputs {
+    /// Validate every input.
+    pub fn strict() -> StrictInputs {
+        StrictInputs {
+            strict: true,
+            invalid: None,
+        }
+    }
+
+    /// Returns true if we validate inputs.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// First input rejected in strict mode, if any.
+    pub fn invalid(&self) -> Option<BlsError> {
+        self.invalid
+    }
+
+    /// Report the first input rejected in strict mode, if any.
+    pub fn check(&self) -> Result<(), BlsError> {
+        self.invalid.map_or(Ok(()), Err)
+    }
+
+    /// Returns true if `check` passed or we are not strict, and
+    /// otherwise remembers its failure.
+    pub fn accept(&mut self, check: impl FnOnce() -> Result<(), BlsError>) -> bool {
+        if !self.strict {
+            return true;
+        }
+        match check() {
+            Ok(()) => true,
+            Err(e) => {
+                self.invalid.get_or_insert(e);
+                false
+            }
+        }
+    }
+
+    /// Run `check` in strict mode, but report its failure immediately.
+    ///
+    /// We use this when removing inputs, because we refuse to remove
+    /// invalid points in strict mode, as we never added those.
+    pub fn require(&self, check: impl FnOnce() -> Result<(), BlsError>) -> Result<(), BlsError> {
+        if self.strict {
+            check()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Inherit the first invalid input of another aggregate we merge.
+    ///
+    /// Returns true if we must validate `other`'s points ourselves,
+    /// because we are strict but `other` is not.
+    pub fn merge(&mut self, other: &StrictInputs) -> bool {
+        if let Some(e) = other.invalid {
+            self.invalid.get_or_insert(e);
+        }
+        self.strict && !other.strict
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use ark_ec::{short_weierstrass::Projective, AffineRepr};
+    use ark_ff::{Field, One, Zero};
+
+    use super::*;
+    use crate::{PublicKey, ZBLS};
+
+    #[test]
+    fn strict_inputs() {
+        let mut lax = StrictInputs::default();
+        assert!(lax.accept(|| Err(BlsError::NotInSubgroup)));
+        assert!(lax.require(|| Err(BlsError::NotInSubgroup)).is_ok());
+        assert!(lax.check().is_ok());
+
+        let mut strict = StrictInputs::strict();
+        assert!(strict.accept(|| Ok(())));
+        assert!(!strict.accept(|| Err(BlsError::IdentityPublicKey)));
+        assert!(!strict.accept(|| Err(BlsError::NotInSubgroup)));
+        assert!(strict.invalid() == Some(BlsError::IdentityPublicKey));
+        assert!(strict.check() == Err(BlsError::IdentityPublicKey));
+        assert!(strict.require(|| Err(BlsError::NotInSubgroup)) == Err(BlsError::NotInSubgroup));
+
+        // Strict inputs validate lax ones they merge, and inherit failures.
+        let mut merged = StrictInputs::strict();
+        assert!(merged.merge(&lax));
+        assert!(!merged.merge(&strict));
+        assert!(merged.invalid() == Some(BlsError::IdentityPublicKey));
+        assert!(!lax.merge(&merged));
+        assert!(lax.invalid() == Some(BlsError::IdentityPublicKey));
+    }
+
+    #[test]
+    fn point_checks() {
+        type G1 = <ZBLS as crate::EngineBLS>::PublicKeyGroup;
+        assert!(check_publickey(&G1::zero()) == Err(BlsError::IdentityPublicKey));
+        assert!(check_subgroup(&G1::zero()).is_ok());
+        let pk = PublicKey::<ZBLS>(G1::from(
+            <G1 as CurveGroup>::Affine::generator()
+                * <ZBLS as crate::EngineBLS>::Scalar::from(7u64),
+        ));
+        assert!(check_publickey(&pk.0).is_ok());
+
+        // Find a point on the curve but outside the subgroup.
+        let mut x = <G1 as CurveGroup>::BaseField::one();
+        let off = loop {
+            if let Some(p) = <G1 as CurveGroup>::Affine::get_point_from_x_unchecked(x, false) {
+                if !p.is_in_correct_subgroup_assuming_on_curve() {
+                    break p;
+                }
+            }
+            x += <G1 as CurveGroup>::BaseField::ONE;
+        };
+        let off: Projective<_> = off.into();
+        assert!(check_publickey(&off) == Err(BlsError::NotInSubgroup));
+        assert_eq!(
+            BlsError::PairingMismatch.to_string(),
+            "Pairing equation does not hold."
+        );
+    }
+
+    #[test]
+    fn verification_reasons() {
+        use rand::thread_rng;
+        use sha2::Sha256;
+
+        use crate::double::{DoublePublicKeyScheme, DoubleSignature};
+        use crate::multi_pop_aggregator::MultiMessageSignatureAggregatorAssumingPoP;
+        use crate::schnorr_pop::SchnorrPoP;
+        use crate::single::{Keypair, Signature};
+        use crate::{
+            DoublePublicKey, Message, NuggetBLSPoP, ProofOfPossession, ProofOfPossessionGenerator,
+            Signed, TinyBLS381,
+        };
+
+        let message = Message::new(b"ctx", b"vote");
+        let other = Message::new(b"ctx", b"other vote");
+        let mut keypair = Keypair::<ZBLS>::generate(thread_rng());
+        let signed = keypair.signed_message(&message);
+        assert!(signed
+            .signature
+            .try_verify(&message, &keypair.public)
+            .is_ok());
+        assert!((&signed).try_verify().is_ok());
+        assert!(
+            signed.signature.try_verify(&other, &keypair.public) == Err(BlsError::PairingMismatch)
+        );
+        let identity = PublicKey::<ZBLS>(Zero::zero());
+        assert!(
+            Signature::<ZBLS>(Zero::zero()).try_verify(&message, &identity)
+                == Err(BlsError::IdentityPublicKey)
+        );
+
+        let mut aggregate = MultiMessageSignatureAggregatorAssumingPoP::<ZBLS>::new();
+        assert!((&aggregate).verify());
+        assert!((&aggregate).try_verify() == Err(BlsError::EmptyAggregate));
+        aggregate.aggregate(&signed);
+        assert!((&aggregate).try_verify().is_ok());
+        aggregate.add_message_n_publickey(&other, &keypair.public);
+        assert!((&aggregate).try_verify() == Err(BlsError::PairingMismatch));
+
+        let pop: SchnorrPoP<ZBLS> = ProofOfPossessionGenerator::<
+            ZBLS,
+            Sha256,
+            PublicKey<ZBLS>,
+            SchnorrPoP<ZBLS>,
+        >::generate_pok(&mut keypair);
+        let stranger = Keypair::<ZBLS>::generate(thread_rng()).public;
+        assert!(ProofOfPossession::<ZBLS, Sha256, _>::try_verify(&pop, &keypair.public).is_ok());
+        assert!(
+            ProofOfPossession::<ZBLS, Sha256, _>::try_verify(&pop, &stranger)
+                == Err(BlsError::FiatShamirMismatch)
+        );
+        assert!(
+            ProofOfPossession::<ZBLS, Sha256, _>::try_verify(&pop, &identity)
+                == Err(BlsError::IdentityPublicKey)
+        );
+
+        let mut keypair = Keypair::<TinyBLS381>::generate(thread_rng());
+        let double_publickey: DoublePublicKey<TinyBLS381> =
+            DoublePublicKeyScheme::into_double_public_key(&keypair);
+        let pop: NuggetBLSPoP<TinyBLS381> = ProofOfPossessionGenerator::<
+            TinyBLS381,
+            Sha256,
+            DoublePublicKey<TinyBLS381>,
+            NuggetBLSPoP<TinyBLS381>,
+        >::generate_pok(&mut keypair);
+        assert!(
+            ProofOfPossession::<TinyBLS381, Sha256, _>::try_verify(&pop, &double_publickey).is_ok()
+        );
+        let wrong = NuggetBLSPoP::<TinyBLS381>(pop.0 + pop.0);
+        assert!(
+            ProofOfPossession::<TinyBLS381, Sha256, _>::try_verify(&wrong, &double_publickey)
+                == Err(BlsError::PairingMismatch)
+        );
+
+        let signature: DoubleSignature<TinyBLS381> =
+            DoublePublicKeyScheme::sign(&mut keypair, &message);
+        assert!(double_publickey.try_verify(&message, &signature).is_ok());
+        assert!(
+            double_publickey.try_verify(&other, &signature) == Err(BlsError::FiatShamirMismatch)
+        );
+    }
+}
@@ -105,25 +105,34 @@ extern crate serde;
 use core::borrow::Borrow;
 use digest::DynDigest;
 
+pub mod batch;
+pub mod bdn;
 pub mod chaum_pedersen_signature;
+pub mod dkg;
 pub mod double;
 pub mod double_pop;
 pub mod engine;
+pub mod error;
+pub mod multi_pop_aggregator;
+pub mod pop_registry;
+pub mod reshare;
 pub mod schnorr_pop;
 pub mod serialize;
 pub mod single;
+pub mod single_pop_aggregator;
+pub mod threshold;
 pub mod verifiers;
 
-#[cfg(feature = "std")]
-pub mod multi_pop_aggregator;
-#[cfg(feature = "std")]
-pub mod single_pop_aggregator;
+#[cfg(feature = "scale-codec")]
+mod scale_support;
+#[cfg(feature = "serde")]
+mod serde_support;
 
-#[cfg(feature = "experimental")]
+#[cfg(feature = "std")]
 pub mod bit;
-#[cfg(feature = "experimental")]
+#[cfg(feature = "std")]
 pub mod delinear;
-#[cfg(feature = "experimental")]
+#[cfg(feature = "std")]
 pub mod distinct;
 
 pub use engine::*;
@@ -132,8 +141,10 @@ pub use double::{
     DoublePublicKey, DoublePublicKeyScheme, DoubleSignature, PublicKeyInSignatureGroup,
 };
 pub use double_pop::{NuggetBLSPoP, NuggetBLSnCPPoP};
+pub use error::BlsError;
+pub use pop_registry::{PopRegistry, VerifiedPublicKey};
 pub use schnorr_pop::SchnorrProof;
-pub use serialize::SerializableToBytes;
+pub use serialize::{Encoding, SerializableToBytes, SerializableWithEncoding};
 pub use single::{Keypair, KeypairVT, PublicKey, SecretKey, SecretKeyVT, Signature, SignedMessage};
 
 use alloc::vec::Vec;
@@ -145,18 +156,26 @@ use alloc::vec::Vec;
 const MESSAGE_SIZE: usize = 32;
 
 /// Ciphersuite standards from BLS signature draft IETF proposal
-const PROOF_OF_POSSESSION_ID: &'static [u8] = b"BLS_POP_";
-const NORMAL_MESSAGE_SIGNATURE_ID: &'static [u8] = b"BLS_SIG_";
+const PROOF_OF_POSSESSION_ID: &[u8] = b"BLS_POP_";
+const NORMAL_MESSAGE_SIGNATURE_ID: &[u8] = b"BLS_SIG_";
 
-const NORMAL_MESSAGE_SIGNATURE_ASSUMING_POP: &'static [u8] = b"POP_";
-const NORMAL_MESSAGE_SIGNATURE_BASIC: &'static [u8] = b"NUL_";
-const POP_MESSAGE: &'static [u8] = b"POP_";
+const NORMAL_MESSAGE_SIGNATURE_ASSUMING_POP: &[u8] = b"POP_";
+const NORMAL_MESSAGE_SIGNATURE_BASIC: &[u8] = b"NUL_";
+const POP_MESSAGE: &[u8] = b"POP_";
 
 type MessageDigest = [u8; MESSAGE_SIZE];
 /// Internal message hash type.  Short for frequent rehashing
 /// by `HashMap`, etc.
+///
+/// We remember the context length, so serialization can recompute the
+/// digest from the context and message, which we hash separately.
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Message(pub MessageDigest, pub alloc::vec::Vec<u8>, MessageType);
+pub struct Message(
+    pub MessageDigest,
+    pub alloc::vec::Vec<u8>,
+    MessageType,
+    usize,
+);
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 enum MessageType {
@@ -172,6 +191,7 @@ impl Message {
             msg_hash,
             [context, message].concat(),
             MessageType::NormalBasic,
+            context.len(),
         )
     }
 
@@ -181,6 +201,7 @@ impl Message {
             msg_hash,
             [context, message].concat(),
             MessageType::NormalAssumingPoP,
+            context.len(),
         )
     }
 
@@ -190,6 +211,7 @@ impl Message {
             msg_hash,
             [context, message].concat(),
             MessageType::ProofOfPossession,
+            context.len(),
         )
     }
 
@@ -234,12 +256,14 @@ impl Message {
         [id, &h2c_suite_id[..], sc_tag].concat()
     }
 
+    /// Hash to the signature curve using our ciphersuite as the
+    /// domain separation tag, as in the BLS signature IETF draft.
     pub fn hash_to_signature_curve<E: EngineBLS>(&self) -> E::SignatureGroup {
-        E::hash_to_signature_curve(&[&self.cipher_suite::<E>()[..], &self.1[..]].concat()[..])
+        E::hash_to_signature_curve(&self.1[..], &self.cipher_suite::<E>()[..])
     }
 }
 
-impl<'a> From<&'a [u8]> for Message {
+impl From<&[u8]> for Message {
     fn from(x: &[u8]) -> Message {
         Message::new(b"", x)
     }
@@ -287,6 +311,15 @@ pub trait Signed: Sized {
     fn verify(self) -> bool {
         verifiers::verify_simple(self)
     }
+
+    /// Verify like `verify`, but explain any failure.
+    ///
+    /// We use `try_verify_simple` as a default implementation, which
+    /// also rejects empty aggregates, identity public keys and points
+    /// outside the prime order subgroup.
+    fn try_verify(self) -> Result<(), BlsError> {
+        verifiers::try_verify_simple(self)
+    }
 }
 
 pub trait ProofOfPossession<E, H, PV>
@@ -295,6 +328,14 @@ where
     H: DynDigest + Default + Clone,
 {
     fn verify(&self, public_key_of_prover: &PV) -> bool;
+
+    /// Verify like `verify`, but explain any failure.
+    fn try_verify(&self, public_key_of_prover: &PV) -> Result<(), BlsError> {
+        error::ensure(
+            self.verify(public_key_of_prover),
+            BlsError::InvalidProofOfPossession,
+        )
+    }
 }
 
 /// ProofOfPossion trait which should be implemented by secret
@@ -34,14 +34,19 @@
 // Aside about proof-of-possession in the DLOG setting
 // https://twitter.com/btcVeg/status/1085490561082183681
 
+use alloc::collections::BTreeMap;
 use core::borrow::Borrow; // BorrowMut
-use std::collections::HashMap;
 
 use ark_ff::Zero;
 
-use super::verifiers::verify_with_distinct_messages;
+use super::verifiers::{
+    try_verify_simple, try_verify_strict, try_verify_using_aggregated_auxiliary_public_keys,
+    verify_using_aggregated_auxiliary_public_keys, verify_with_distinct_messages,
+};
 use super::*;
 
+use digest::{DynDigest, FixedOutputReset};
+
 /// Batch or aggregate BLS signatures with attached messages and
 /// signers, for whom we previously checked proofs-of-possession.
 ///
@@ -59,6 +64,9 @@ use super::*;
 /// Distinct message aggregation is always optimal for checking
 /// proofs-of-possession.  Please see the module level doumentation
 /// for additional discussion and notes on security.
+/// We therefore recommend `VerifiedMultiMessageSignatureAggregator`,
+/// which accepts only `VerifiedPublicKey`s, perhaps taken from a
+/// `PopRegistry`.
 ///
 /// We foresee this type primarily being used to batch several
 /// `BitPoPSignedMessage`s into one verification.  We do not track
@@ -78,29 +86,67 @@ use super::*;
 /// but this sounds complex or worse fragile.
 ///
 // TODO: Implement gaussian elimination verification scheme.
-use single::PublicKey;
+use double::PublicKeyInSignatureGroup;
+use error::StrictInputs;
+use pop_registry::VerifiedPublicKey;
+use single::{PublicKey, SignedMessage};
 /// ProofOfPossion trait which should be implemented by secret
-
-#[derive(Clone)]
 pub struct MultiMessageSignatureAggregatorAssumingPoP<E: EngineBLS> {
-    messages_n_publickeys: HashMap<Message, PublicKey<E>>,
+    messages_n_publickeys: BTreeMap<Message, PublicKey<E>>,
     signature: Signature<E>,
+    auxiliary_publickeys: BTreeMap<Message, PublicKeyInSignatureGroup<E>>,
+    inputs: StrictInputs,
+}
+
+impl<E: EngineBLS> Clone for MultiMessageSignatureAggregatorAssumingPoP<E> {
+    fn clone(&self) -> MultiMessageSignatureAggregatorAssumingPoP<E> {
+        MultiMessageSignatureAggregatorAssumingPoP {
+            messages_n_publickeys: self.messages_n_publickeys.clone(),
+            signature: self.signature,
+            auxiliary_publickeys: self.auxiliary_publickeys.clone(),
+            inputs: self.inputs,
+        }
+    }
+}
+
+impl<E: EngineBLS> Default for MultiMessageSignatureAggregatorAssumingPoP<E> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<E: EngineBLS> MultiMessageSignatureAggregatorAssumingPoP<E> {
     pub fn new() -> MultiMessageSignatureAggregatorAssumingPoP<E> {
         MultiMessageSignatureAggregatorAssumingPoP {
-            messages_n_publickeys: HashMap::new(),
+            messages_n_publickeys: BTreeMap::new(),
             signature: Signature(E::SignatureGroup::zero()),
+            auxiliary_publickeys: BTreeMap::new(),
+            inputs: StrictInputs::default(),
         }
     }
 
+    /// Validate every signature and public key we add, see `StrictInputs`.
+    pub fn strict(mut self) -> MultiMessageSignatureAggregatorAssumingPoP<E> {
+        self.inputs = StrictInputs::strict();
+        self
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.inputs.is_strict()
+    }
+
+    pub fn invalid(&self) -> Option<BlsError> {
+        self.inputs.invalid()
+    }
+
     /// Add only a `Signature<E>` to our internal signature.
     ///
     /// Useful for constructing an aggregate signature, but we
     /// recommend instead using a custom types like `BitPoPSignedMessage`.
     pub fn add_signature(&mut self, signature: &Signature<E>) {
-        self.signature.0 += &signature.0;
+        if self.inputs.accept(|| signature.validate()) {
+            self.signature.0 += &signature.0;
+        }
     }
 
     /// Add only a `Message` and `PublicKey<E>` to our internal data.
@@ -108,6 +154,23 @@ impl<E: EngineBLS> MultiMessageSignatureAggregatorAssumingPoP<E> {
     /// Useful for constructing an aggregate signature, but we
     /// recommend instead using a custom types like `BitPoPSignedMessage`.
     pub fn add_message_n_publickey(&mut self, message: &Message, publickey: &PublicKey<E>) {
+        if !self.inputs.accept(|| publickey.validate()) {
+            return;
+        }
+        self.messages_n_publickeys
+            .entry(message.clone())
+            .and_modify(|pk0| pk0.0 += &publickey.0)
+            .or_insert(*publickey);
+    }
+
+    /// Add a `Message` and a `PublicKey<E>` whose proof-of-possession
+    /// we checked.
+    fn add_verified_message_n_publickey(
+        &mut self,
+        message: &Message,
+        publickey: &VerifiedPublicKey<E>,
+    ) {
+        let publickey = publickey.publickey();
         self.messages_n_publickeys
             .entry(message.clone())
             .and_modify(|pk0| pk0.0 += &publickey.0)
@@ -126,6 +189,182 @@ impl<E: EngineBLS> MultiMessageSignatureAggregatorAssumingPoP<E> {
         }
         self.add_signature(&signature);
     }
+
+    /// Merge another partial aggregate into ours.
+    ///
+    /// We validate `other`'s points as `StrictInputs::merge` requires.
+    pub fn merge(&mut self, other: &MultiMessageSignatureAggregatorAssumingPoP<E>) {
+        let strict = self.inputs.merge(&other.inputs);
+        for (message, publickey) in other.messages_n_publickeys.iter() {
+            if strict && !self.inputs.accept(|| error::check_subgroup(&publickey.0)) {
+                continue;
+            }
+            self.messages_n_publickeys
+                .entry(message.clone())
+                .and_modify(|pk0| pk0.0 += &publickey.0)
+                .or_insert(*publickey);
+        }
+        for (message, aux) in other.auxiliary_publickeys.iter() {
+            if strict && !self.inputs.accept(|| error::check_subgroup(&aux.0)) {
+                continue;
+            }
+            self.auxiliary_publickeys
+                .entry(message.clone())
+                .and_modify(|aux0| aux0.0 += &aux.0)
+                .or_insert(*aux);
+        }
+        if !strict
+            || self
+                .inputs
+                .accept(|| error::check_subgroup(&other.signature.0))
+        {
+            self.signature.0 += &other.signature.0;
+        }
+    }
+
+    /// Subtract `publickey` from `message`'s public key, forgetting
+    /// both `message` and its auxiliary public key if none remain.
+    fn subtract_publickey(&mut self, message: &Message, publickey: &E::PublicKeyGroup) {
+        if let Some(pk0) = self.messages_n_publickeys.get_mut(message) {
+            pk0.0 -= publickey;
+            if pk0.0.is_zero() {
+                self.messages_n_publickeys.remove(message);
+                self.auxiliary_publickeys.remove(message);
+            }
+        }
+    }
+
+    /// Remove a signer's contribution, so both their signature and
+    /// public keys, like when we learn they equivocated.
+    ///
+    /// We refuse to remove signatures on messages we do not contain,
+    /// and invalid points as in `StrictInputs::require`.  We cannot know if `signed` was really aggregated here, so callers
+    /// should remove only what they previously added.
+    ///
+    /// We also subtract the signer's `auxiliary_publickey` once for each
+    /// of their messages in `signed`, which callers must supply if they
+    /// added it, or else verification using auxiliary public keys fails
+    /// afterwards.  We forget auxiliary public keys of messages nobody
+    /// signs anymore regardless.
+    pub fn remove_signed<'a, S>(
+        &mut self,
+        signed: &'a S,
+        auxiliary_publickey: Option<&PublicKeyInSignatureGroup<E>>,
+    ) -> Result<(), BlsError>
+    where
+        &'a S: Signed<E = E>,
+    {
+        let signature = signed.signature();
+        let mut removed = BTreeMap::new();
+        for (message, publickey) in signed.messages_and_publickeys() {
+            let message = message.borrow();
+            error::ensure(
+                self.messages_n_publickeys.contains_key(message),
+                BlsError::MessageMismatch,
+            )?;
+            self.inputs.require(|| publickey.borrow().validate())?;
+            *removed
+                .entry(message.clone())
+                .or_insert_with(E::PublicKeyGroup::zero) += publickey.borrow().0;
+        }
+        self.inputs.require(|| signature.validate())?;
+        if let Some(aux) = auxiliary_publickey {
+            self.inputs.require(|| error::check_publickey(&aux.0))?;
+            for message in removed.keys() {
+                self.auxiliary_publickeys
+                    .entry(message.clone())
+                    .and_modify(|aux0| aux0.0 -= &aux.0)
+                    .or_insert(PublicKeyInSignatureGroup(-aux.0));
+            }
+        }
+        for (message, publickey) in removed.iter() {
+            self.subtract_publickey(message, publickey);
+        }
+        self.signature.0 -= signature.0;
+        Ok(())
+    }
+
+    /// Split off a partial aggregate `other` of ours, returning the
+    /// aggregate of the remaining signers.
+    ///
+    /// We require that `other` verifies, so that the difference verifies
+    /// if and only if we do, and subtracting `other` could never turn
+    /// an invalid aggregate of ours into a valid one.  We also require
+    /// that we contain all messages of `other`.
+    pub fn difference(
+        &self,
+        other: &MultiMessageSignatureAggregatorAssumingPoP<E>,
+    ) -> Result<MultiMessageSignatureAggregatorAssumingPoP<E>, BlsError> {
+        error::ensure(
+            other
+                .messages_n_publickeys
+                .keys()
+                .chain(other.auxiliary_publickeys.keys())
+                .all(|message| self.messages_n_publickeys.contains_key(message)),
+            BlsError::MessageMismatch,
+        )?;
+        other.try_verify()?;
+        let mut difference = self.clone();
+        for (message, aux) in other.auxiliary_publickeys.iter() {
+            difference
+                .auxiliary_publickeys
+                .entry(message.clone())
+                .and_modify(|aux0| aux0.0 -= &aux.0)
+                .or_insert(PublicKeyInSignatureGroup(-aux.0));
+        }
+        for (message, publickey) in other.messages_n_publickeys.iter() {
+            difference.subtract_publickey(message, &publickey.0);
+        }
+        difference.signature.0 -= &other.signature.0;
+        Ok(difference)
+    }
+
+    /// Aggregate an auxiliary public key in the signature group for
+    /// `message`, for verification using auxiliary public keys.
+    ///
+    /// Every message needs auxiliary public keys for all its signers.
+    pub fn add_auxiliary_public_key(
+        &mut self,
+        message: &Message,
+        publickey_in_signature_group: &PublicKeyInSignatureGroup<E>,
+    ) {
+        if !self
+            .inputs
+            .accept(|| error::check_publickey(&publickey_in_signature_group.0))
+        {
+            return;
+        }
+        self.auxiliary_publickeys
+            .entry(message.clone())
+            .and_modify(|aux0| aux0.0 += &publickey_in_signature_group.0)
+            .or_insert(*publickey_in_signature_group);
+    }
+
+    /// Verify using the aggregated auxiliary public keys of every
+    /// message, with only one pairing per distinct message.
+    pub fn verify_using_aggregated_auxiliary_public_keys<
+        RandomOracle: DynDigest + FixedOutputReset + Default + Clone,
+    >(
+        &self,
+    ) -> bool {
+        self.inputs.invalid().is_none()
+            && verify_using_aggregated_auxiliary_public_keys::<_, RandomOracle, _>(
+                self,
+                self.auxiliary_publickeys.iter(),
+            )
+    }
+
+    pub fn try_verify_using_aggregated_auxiliary_public_keys<
+        RandomOracle: DynDigest + FixedOutputReset + Default + Clone,
+    >(
+        &self,
+    ) -> Result<(), BlsError> {
+        self.inputs.check()?;
+        try_verify_using_aggregated_auxiliary_public_keys::<_, RandomOracle, _>(
+            self,
+            self.auxiliary_publickeys.iter(),
+        )
+    }
 }
 
 impl<'a, E: EngineBLS> Signed for &'a MultiMessageSignatureAggregatorAssumingPoP<E> {
@@ -133,7 +372,7 @@ impl<'a, E: EngineBLS> Signed for &'a MultiMessageSignatureAggregatorAssumingPoP
 
     type M = &'a Message;
     type PKG = &'a PublicKey<Self::E>;
-    type PKnM = ::std::collections::hash_map::Iter<'a, Message, PublicKey<E>>;
+    type PKnM = ::alloc::collections::btree_map::Iter<'a, Message, PublicKey<E>>;
 
     fn messages_and_publickeys(self) -> Self::PKnM {
         self.messages_n_publickeys.iter()
@@ -149,18 +388,189 @@ impl<'a, E: EngineBLS> Signed for &'a MultiMessageSignatureAggregatorAssumingPoP
         // except the public keys might not be normalized here.
         // We foresee verification via gaussian elimination being faster,
         // but requires affine keys or normalization.
-        verify_with_distinct_messages(self, true)
+        self.inputs.invalid().is_none() && verify_with_distinct_messages(self, true)
         // TODO: verify_with_gaussian_elimination(self)
     }
+
+    fn try_verify(self) -> Result<(), BlsError> {
+        self.inputs.check()?;
+        if self.inputs.is_strict() {
+            try_verify_strict(self)
+        } else {
+            try_verify_simple(self)
+        }
+    }
+}
+
+/// Batch or aggregate BLS signatures by signers whose
+/// proofs-of-possession we checked.
+///
+/// We wrap a strict `MultiMessageSignatureAggregatorAssumingPoP`, but
+/// accept public keys only as `VerifiedPublicKey`s, so we never
+/// aggregate rogue keys.
+///
+/// ```compile_fail
+/// use rand::thread_rng;
+/// use tnt_bls::multi_pop_aggregator::VerifiedMultiMessageSignatureAggregator;
+/// use tnt_bls::{Keypair, Message, ZBLS};
+///
+/// let message = Message::new(b"ctx", b"vote");
+/// let mut keypair = Keypair::<ZBLS>::generate(thread_rng());
+/// let mut aggregator = VerifiedMultiMessageSignatureAggregator::<ZBLS>::new();
+/// aggregator.aggregate(&keypair.signed_message(&message));
+/// ```
+pub struct VerifiedMultiMessageSignatureAggregator<E: EngineBLS>(
+    MultiMessageSignatureAggregatorAssumingPoP<E>,
+);
+
+impl<E: EngineBLS> Clone for VerifiedMultiMessageSignatureAggregator<E> {
+    fn clone(&self) -> VerifiedMultiMessageSignatureAggregator<E> {
+        VerifiedMultiMessageSignatureAggregator(self.0.clone())
+    }
+}
+
+impl<E: EngineBLS> Default for VerifiedMultiMessageSignatureAggregator<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: EngineBLS> VerifiedMultiMessageSignatureAggregator<E> {
+    pub fn new() -> VerifiedMultiMessageSignatureAggregator<E> {
+        VerifiedMultiMessageSignatureAggregator(
+            MultiMessageSignatureAggregatorAssumingPoP::new().strict(),
+        )
+    }
+
+    /// Aggregator by the given public keys, along with the messages
+    /// they sign.
+    pub fn from_verified_publickeys<'a, I>(
+        messages_n_publickeys: I,
+    ) -> VerifiedMultiMessageSignatureAggregator<E>
+    where
+        E: 'a,
+        I: IntoIterator<Item = (&'a Message, &'a VerifiedPublicKey<E>)>,
+    {
+        let mut aggregator = VerifiedMultiMessageSignatureAggregator::new();
+        for (message, publickey) in messages_n_publickeys {
+            aggregator.add_verified_message_n_publickey(message, publickey);
+        }
+        aggregator
+    }
+
+    /// First input rejected, if any.
+    pub fn invalid(&self) -> Option<BlsError> {
+        self.0.invalid()
+    }
+
+    /// See `MultiMessageSignatureAggregatorAssumingPoP::add_signature`.
+    pub fn add_signature(&mut self, signature: &Signature<E>) {
+        self.0.add_signature(signature)
+    }
+
+    pub fn add_verified_message_n_publickey(
+        &mut self,
+        message: &Message,
+        publickey: &VerifiedPublicKey<E>,
+    ) {
+        self.0.add_verified_message_n_publickey(message, publickey)
+    }
+
+    /// See `MultiMessageSignatureAggregatorAssumingPoP::add_auxiliary_public_key`.
+    pub fn add_auxiliary_public_key(
+        &mut self,
+        message: &Message,
+        publickey_in_signature_group: &PublicKeyInSignatureGroup<E>,
+    ) {
+        self.0
+            .add_auxiliary_public_key(message, publickey_in_signature_group)
+    }
+
+    /// See `MultiMessageSignatureAggregatorAssumingPoP::merge`.
+    pub fn merge(&mut self, other: &VerifiedMultiMessageSignatureAggregator<E>) {
+        self.0.merge(&other.0)
+    }
+
+    /// Remove the contribution of the signer with `publickey` on
+    /// `message`, see `MultiMessageSignatureAggregatorAssumingPoP::remove_signed`.
+    pub fn remove_signer(
+        &mut self,
+        message: &Message,
+        publickey: &VerifiedPublicKey<E>,
+        signature: &Signature<E>,
+        auxiliary_publickey: Option<&PublicKeyInSignatureGroup<E>>,
+    ) -> Result<(), BlsError> {
+        let signed = SignedMessage {
+            message: message.clone(),
+            publickey: *publickey.publickey(),
+            signature: *signature,
+        };
+        self.0.remove_signed(&signed, auxiliary_publickey)
+    }
+
+    /// See `MultiMessageSignatureAggregatorAssumingPoP::difference`.
+    pub fn difference(
+        &self,
+        other: &VerifiedMultiMessageSignatureAggregator<E>,
+    ) -> Result<VerifiedMultiMessageSignatureAggregator<E>, BlsError> {
+        self.0
+            .difference(&other.0)
+            .map(VerifiedMultiMessageSignatureAggregator)
+    }
+
+    pub fn verify_using_aggregated_auxiliary_public_keys<
+        RandomOracle: DynDigest + FixedOutputReset + Default + Clone,
+    >(
+        &self,
+    ) -> bool {
+        self.0
+            .verify_using_aggregated_auxiliary_public_keys::<RandomOracle>()
+    }
+
+    pub fn try_verify_using_aggregated_auxiliary_public_keys<
+        RandomOracle: DynDigest + FixedOutputReset + Default + Clone,
+    >(
+        &self,
+    ) -> Result<(), BlsError> {
+        self.0
+            .try_verify_using_aggregated_auxiliary_public_keys::<RandomOracle>()
+    }
+}
+
+impl<'a, E: EngineBLS> Signed for &'a VerifiedMultiMessageSignatureAggregator<E> {
+    type E = E;
+
+    type M = &'a Message;
+    type PKG = &'a PublicKey<Self::E>;
+    type PKnM = ::alloc::collections::btree_map::Iter<'a, Message, PublicKey<E>>;
+
+    fn messages_and_publickeys(self) -> Self::PKnM {
+        (&self.0).messages_and_publickeys()
+    }
+
+    fn signature(&self) -> Signature<E> {
+        self.0.signature
+    }
+
+    fn verify(self) -> bool {
+        (&self.0).verify()
+    }
+
+    fn try_verify(self) -> Result<(), BlsError> {
+        (&self.0).try_verify()
+    }
 }
 
 #[cfg(all(test, feature = "std"))]
 mod tests {
 
+    use crate::double::DoublePublicKeyScheme;
     use crate::Keypair;
     use crate::Message;
     use crate::UsualBLS;
+    use crate::{TinyBLS377, ZBLS};
     use rand::thread_rng;
+    use sha2::Sha256;
 
     use ark_bls12_381::Bls12_381;
 
@@ -198,7 +608,7 @@ mod tests {
         aggregated_sigs.add_message_n_publickey(&good, &keypair1.public);
 
         assert!(
-            aggregated_sigs.verify() == true,
+            aggregated_sigs.verify(),
             "good aggregated signature of a single message with multiple key does not verify"
         );
     }
@@ -224,7 +634,7 @@ mod tests {
         aggregated_sigs.add_message_n_publickey(&good1, &keypair.public);
 
         assert!(
-            aggregated_sigs.verify() == true,
+            aggregated_sigs.verify(),
             "good aggregated signature of multiple messages with a single key does not verify"
         );
     }
@@ -252,7 +662,7 @@ mod tests {
         aggregated_sigs.add_message_n_publickey(&good1, &keypair1.public);
 
         assert!(
-            aggregated_sigs.verify() == true,
+            aggregated_sigs.verify(),
             "good aggregated signature of multiple messages with multiple keys does not verify"
         );
     }
@@ -275,7 +685,7 @@ mod tests {
         aggregated_sigs.add_message_n_publickey(&good, &keypair.public);
 
         assert!(
-            aggregated_sigs.verify() == true,
+            aggregated_sigs.verify(),
             "good aggregate of a repetitive signature does not verify"
         );
     }
@@ -303,8 +713,187 @@ mod tests {
         aggregated_sigs.add_message_n_publickey(&good0, &keypair1.public);
 
         assert!(
-            aggregated_sigs.verify() == false,
+            !aggregated_sigs.verify(),
             "aggregated signature of a wrong message should not verify"
         );
     }
+
+    #[test]
+    fn strict_aggregation_rejects_identity_signatures() {
+        type E = UsualBLS<Bls12_381, ark_bls12_381::Config>;
+        let message = Message::new(b"ctx", b"test message");
+        let mut keypair = Keypair::<E>::generate(thread_rng());
+
+        let mut strict = MultiMessageSignatureAggregatorAssumingPoP::<E>::new().strict();
+        strict.aggregate(&keypair.signed_message(&message));
+        assert!(strict.verify());
+        assert!(strict.try_verify().is_ok());
+        strict.add_signature(&Signature(Zero::zero()));
+        assert!(strict.invalid() == Some(crate::BlsError::IdentitySignature));
+        assert!(!strict.verify());
+        assert!(strict.try_verify() == Err(crate::BlsError::IdentitySignature));
+    }
+
+    fn check_multi_message_auxiliary_public_keys<E: EngineBLS>() {
+        let messages = [
+            Message::new(b"ctx", b"first"),
+            Message::new(b"ctx", b"second"),
+            Message::new(b"ctx", b"third"),
+        ];
+        let mut keypairs: Vec<_> = (0..5)
+            .map(|_| Keypair::<E>::generate(thread_rng()))
+            .collect();
+
+        let mut aggregator = MultiMessageSignatureAggregatorAssumingPoP::<E>::new().strict();
+        for (i, k) in keypairs.iter_mut().enumerate() {
+            let message = &messages[i % messages.len()];
+            aggregator.aggregate(&k.signed_message(message));
+            aggregator.add_auxiliary_public_key(message, &k.into_public_key_in_signature_group());
+        }
+        assert!(aggregator.verify());
+        assert!(aggregator.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        assert!(aggregator
+            .try_verify_using_aggregated_auxiliary_public_keys::<Sha256>()
+            .is_ok());
+
+        // An auxiliary public key for the wrong message fails.
+        let mut swapped = aggregator.clone();
+        let aux = keypairs[0].into_public_key_in_signature_group();
+        swapped.add_auxiliary_public_key(&messages[0], &PublicKeyInSignatureGroup(-aux.0));
+        swapped.add_auxiliary_public_key(&messages[1], &aux);
+        assert!(!swapped.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        assert!(
+            swapped.try_verify_using_aggregated_auxiliary_public_keys::<Sha256>()
+                == Err(crate::BlsError::PairingMismatch)
+        );
+
+        // A message without auxiliary public keys fails.
+        let mut missing = aggregator.clone();
+        let extra = Message::new(b"ctx", b"fourth");
+        missing.aggregate(&keypairs[0].signed_message(&extra));
+        assert!(missing.verify());
+        assert!(!missing.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        assert!(
+            missing.try_verify_using_aggregated_auxiliary_public_keys::<Sha256>()
+                == Err(crate::BlsError::AuxiliaryPublicKeyMismatch)
+        );
+
+        // An auxiliary public key for an unsigned message fails.
+        let mut stray = aggregator.clone();
+        stray.add_auxiliary_public_key(&extra, &aux);
+        assert!(!stray.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+    }
+
+    #[test]
+    fn verify_multi_message_aggregate_using_auxiliary_public_keys() {
+        check_multi_message_auxiliary_public_keys::<TinyBLS377>();
+        check_multi_message_auxiliary_public_keys::<ZBLS>();
+    }
+
+    #[test]
+    fn aggregation_order_does_not_matter() {
+        type E = UsualBLS<Bls12_381, ark_bls12_381::Config>;
+        let signed: Vec<_> = (0u8..4)
+            .map(|i| {
+                let message = Message::new(b"ctx", &[i]);
+                Keypair::<E>::generate(thread_rng()).signed_message(&message)
+            })
+            .collect();
+
+        let mut forward = MultiMessageSignatureAggregatorAssumingPoP::<E>::new();
+        let mut backward = MultiMessageSignatureAggregatorAssumingPoP::<E>::new();
+        for s in signed.iter() {
+            forward.aggregate(s);
+        }
+        for s in signed.iter().rev() {
+            backward.aggregate(s);
+        }
+        let forward_messages: Vec<_> = forward.messages_and_publickeys().map(|(m, _)| m).collect();
+        let backward_messages: Vec<_> =
+            backward.messages_and_publickeys().map(|(m, _)| m).collect();
+        assert_eq!(forward_messages, backward_messages);
+        assert!(forward_messages.windows(2).all(|w| w[0] < w[1]));
+        assert!(forward.verify() && backward.verify());
+    }
+
+    #[test]
+    fn merge_remove_and_split_aggregates() {
+        let messages = [
+            Message::new(b"ctx", b"first"),
+            Message::new(b"ctx", b"second"),
+        ];
+        let mut keypairs: Vec<_> = (0..4)
+            .map(|_| Keypair::<TinyBLS377>::generate(thread_rng()))
+            .collect();
+        let signed: Vec<_> = keypairs
+            .iter_mut()
+            .enumerate()
+            .map(|(i, k)| k.signed_message(&messages[i % 2]))
+            .collect();
+
+        let mut left = MultiMessageSignatureAggregatorAssumingPoP::<TinyBLS377>::new().strict();
+        let mut right = MultiMessageSignatureAggregatorAssumingPoP::<TinyBLS377>::new();
+        for (i, s) in signed.iter().enumerate() {
+            let partial = if i < 3 { &mut left } else { &mut right };
+            partial.aggregate(s);
+            partial.add_auxiliary_public_key(
+                &s.message,
+                &keypairs[i].into_public_key_in_signature_group(),
+            );
+        }
+
+        let mut merged = left.clone();
+        merged.merge(&right);
+        assert!(merged.verify());
+        assert!(merged.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        assert!(merged.invalid().is_none());
+
+        // Splitting off `right` recovers `left`.
+        let split = merged.difference(&right).unwrap();
+        assert!(split.verify());
+        assert!(split.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        assert!(split
+            .messages_and_publickeys()
+            .eq(left.messages_and_publickeys()));
+
+        // Removing the only signer of a message forgets the message.
+        let mut removed = left.clone();
+        removed.remove_signed(&signed[1], None).unwrap();
+        assert!(removed.verify());
+        assert_eq!(removed.messages_and_publickeys().len(), 1);
+        assert!(removed.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        assert!(removed.remove_signed(&signed[1], None) == Err(crate::BlsError::MessageMismatch));
+
+        // Removing signers on a remaining message leaves a valid aggregate,
+        // provided we remove their auxiliary public keys too.
+        let mut removed = merged.clone();
+        removed
+            .remove_signed(
+                &signed[0],
+                Some(&keypairs[0].into_public_key_in_signature_group()),
+            )
+            .unwrap();
+        assert!(removed.verify());
+        assert!(removed.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        let mut forgetful = merged.clone();
+        forgetful.remove_signed(&signed[0], None).unwrap();
+        assert!(forgetful.verify());
+        assert!(!forgetful.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        assert!(removed
+            .remove_signed(
+                &keypairs[0].signed_message(&Message::new(b"ctx", b"third")),
+                None
+            )
+            .is_err());
+
+        // We refuse to subtract aggregates which do not verify, or
+        // contain messages we lack.
+        let mut bogus = MultiMessageSignatureAggregatorAssumingPoP::<TinyBLS377>::new();
+        bogus.add_message_n_publickey(&messages[0], &signed[0].publickey);
+        bogus.add_signature(&signed[1].signature);
+        assert!(merged.difference(&bogus).err() == Some(crate::BlsError::PairingMismatch));
+        let mut stranger = MultiMessageSignatureAggregatorAssumingPoP::<TinyBLS377>::new();
+        stranger.aggregate(&keypairs[0].signed_message(&Message::new(b"ctx", b"third")));
+        assert!(merged.difference(&stranger).err() == Some(crate::BlsError::MessageMismatch));
+    }
 }
@@ -0,0 +1,435 @@
+//! ## Public keys with checked proofs-of-possession
+//!
+//! Aggregation assuming proofs-of-possession stays secure against rogue
+//! key attacks only if verifiers checked a proof-of-possession for every
+//! public key they aggregate.  We enforce this in the type system here:
+//! A `VerifiedPublicKey` could only be constructed by verifying some
+//! `SchnorrPoP`, `BatchableSchnorrPoP`, `NuggetBLSPoP` or `NuggetBLSnCPPoP`,
+//! and our `VerifiedSignatureAggregator` and
+//! `VerifiedMultiMessageSignatureAggregator` accept only such keys.
+//!
+//! We provide a `PopRegistry` too, which caches verified keys, so that
+//! verifiers check each proof-of-possession only once, and which
+//! permanently revokes keys, like when their owners misbehave.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use digest::{DynDigest, FixedOutputReset};
+
+use crate::double::DoublePublicKey;
+use crate::double_pop::{NuggetBLSPoP, NuggetBLSnCPPoP};
+use crate::engine::EngineBLS;
+use crate::error::BlsError;
+use crate::schnorr_pop::{BatchableSchnorrPoP, SchnorrPoP};
+use crate::serialize::SerializableToBytes;
+use crate::single::PublicKey;
+use crate::ProofOfPossession;
+
+/// BLS public key whose proof-of-possession we checked.
+///
+/// We have no public constructor besides verifying proofs-of-possession,
+/// and no deserialization, so holding a `VerifiedPublicKey` proves some
+/// proof-of-possession was checked for this key.
+#[derive(Debug)]
+pub struct VerifiedPublicKey<E: EngineBLS>(PublicKey<E>);
+
+impl<E: EngineBLS> Clone for VerifiedPublicKey<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E: EngineBLS> Copy for VerifiedPublicKey<E> {}
+
+impl<E: EngineBLS> PartialEq<Self> for VerifiedPublicKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq(&other.0)
+    }
+}
+
+impl<E: EngineBLS> Eq for VerifiedPublicKey<E> {}
+
+impl<E: EngineBLS> VerifiedPublicKey<E> {
+    /// Verify a Schnorr proof-of-possession for `publickey`.
+    pub fn from_schnorr_pop<H>(
+        publickey: &PublicKey<E>,
+        pop: &SchnorrPoP<E>,
+    ) -> Result<VerifiedPublicKey<E>, BlsError>
+    where
+        H: DynDigest + FixedOutputReset + Default + Clone,
+    {
+        <SchnorrPoP<E> as ProofOfPossession<E, H, PublicKey<E>>>::try_verify(pop, publickey)?;
+        Ok(VerifiedPublicKey(*publickey))
+    }
+
+    /// Verify a batchable Schnorr proof-of-possession for `publickey`.
+    pub fn from_batchable_schnorr_pop<H>(
+        publickey: &PublicKey<E>,
+        pop: &BatchableSchnorrPoP<E>,
+    ) -> Result<VerifiedPublicKey<E>, BlsError>
+    where
+        H: DynDigest + FixedOutputReset + Default + Clone,
+    {
+        <BatchableSchnorrPoP<E> as ProofOfPossession<E, H, PublicKey<E>>>::try_verify(
+            pop, publickey,
+        )?;
+        Ok(VerifiedPublicKey(*publickey))
+    }
+
+    /// Verify a Nugget BLS proof-of-possession for `publickey`, and
+    /// return its public key in the public key group.
+    pub fn from_nugget_bls_pop<H>(
+        publickey: &DoublePublicKey<E>,
+        pop: &NuggetBLSPoP<E>,
+    ) -> Result<VerifiedPublicKey<E>, BlsError>
+    where
+        H: DynDigest + FixedOutputReset + Default + Clone,
+    {
+        <NuggetBLSPoP<E> as ProofOfPossession<E, H, DoublePublicKey<E>>>::try_verify(
+            pop, publickey,
+        )?;
+        Ok(VerifiedPublicKey(PublicKey(publickey.1)))
+    }
+
+    /// Verify a Nugget BLS and Chaum-Pedersen proof-of-possession for
+    /// `publickey`, and return its public key in the public key group.
+    pub fn from_nugget_bls_n_cp_pop<H>(
+        publickey: &DoublePublicKey<E>,
+        pop: &NuggetBLSnCPPoP<E>,
+    ) -> Result<VerifiedPublicKey<E>, BlsError>
+    where
+        H: DynDigest + FixedOutputReset + Default + Clone,
+    {
+        <NuggetBLSnCPPoP<E> as ProofOfPossession<E, H, DoublePublicKey<E>>>::try_verify(
+            pop, publickey,
+        )?;
+        Ok(VerifiedPublicKey(PublicKey(publickey.1)))
+    }
+
+    /// Returns the underlying public key.
+    pub fn publickey(&self) -> &PublicKey<E> {
+        &self.0
+    }
+}
+
+/// Cache of public keys with checked proofs-of-possession.
+///
+/// We index keys by their compressed serialization, and never forget
+/// revoked keys, so later proofs-of-possession cannot register them
+/// again.  We cannot recall `VerifiedPublicKey`s handed out before
+/// revocation though, so callers should fetch keys from the registry
+/// whenever they build aggregators.
+pub struct PopRegistry<E: EngineBLS> {
+    verified: BTreeMap<Vec<u8>, VerifiedPublicKey<E>>,
+    revoked: BTreeSet<Vec<u8>>,
+}
+
+impl<E: EngineBLS> Clone for PopRegistry<E> {
+    fn clone(&self) -> PopRegistry<E> {
+        PopRegistry {
+            verified: self.verified.clone(),
+            revoked: self.revoked.clone(),
+        }
+    }
+}
+
+impl<E: EngineBLS> Default for PopRegistry<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: EngineBLS> PopRegistry<E> {
+    pub fn new() -> PopRegistry<E> {
+        PopRegistry {
+            verified: BTreeMap::new(),
+            revoked: BTreeSet::new(),
+        }
+    }
+
+    /// Register `publickey` if `verify` succeeds, unless we already
+    /// know it, in which case we skip verification.
+    fn register_with(
+        &mut self,
+        publickey: &PublicKey<E>,
+        verify: impl FnOnce() -> Result<VerifiedPublicKey<E>, BlsError>,
+    ) -> Result<VerifiedPublicKey<E>, BlsError> {
+        let index = publickey.to_bytes();
+        if self.revoked.contains(&index) {
+            return Err(BlsError::RevokedPublicKey);
+        }
+        if let Some(verified) = self.verified.get(&index) {
+            return Ok(*verified);
+        }
+        let verified = verify()?;
+        self.verified.insert(index, verified);
+        Ok(verified)
+    }
+
+    /// Check a Schnorr proof-of-possession and register `publickey`.
+    pub fn register_schnorr_pop<H>(
+        &mut self,
+        publickey: &PublicKey<E>,
+        pop: &SchnorrPoP<E>,
+    ) -> Result<VerifiedPublicKey<E>, BlsError>
+    where
+        H: DynDigest + FixedOutputReset + Default + Clone,
+    {
+        self.register_with(publickey, || {
+            VerifiedPublicKey::from_schnorr_pop::<H>(publickey, pop)
+        })
+    }
+
+    /// Check a batchable Schnorr proof-of-possession and register `publickey`.
+    pub fn register_batchable_schnorr_pop<H>(
+        &mut self,
+        publickey: &PublicKey<E>,
+        pop: &BatchableSchnorrPoP<E>,
+    ) -> Result<VerifiedPublicKey<E>, BlsError>
+    where
+        H: DynDigest + FixedOutputReset + Default + Clone,
+    {
+        self.register_with(publickey, || {
+            VerifiedPublicKey::from_batchable_schnorr_pop::<H>(publickey, pop)
+        })
+    }
+
+    /// Check a Nugget BLS proof-of-possession and register the public
+    /// key group half of `publickey`.
+    pub fn register_nugget_bls_pop<H>(
+        &mut self,
+        publickey: &DoublePublicKey<E>,
+        pop: &NuggetBLSPoP<E>,
+    ) -> Result<VerifiedPublicKey<E>, BlsError>
+    where
+        H: DynDigest + FixedOutputReset + Default + Clone,
+    {
+        self.register_with(&PublicKey(publickey.1), || {
+            VerifiedPublicKey::from_nugget_bls_pop::<H>(publickey, pop)
+        })
+    }
+
+    /// Check a Nugget BLS and Chaum-Pedersen proof-of-possession and
+    /// register the public key group half of `publickey`.
+    pub fn register_nugget_bls_n_cp_pop<H>(
+        &mut self,
+        publickey: &DoublePublicKey<E>,
+        pop: &NuggetBLSnCPPoP<E>,
+    ) -> Result<VerifiedPublicKey<E>, BlsError>
+    where
+        H: DynDigest + FixedOutputReset + Default + Clone,
+    {
+        self.register_with(&PublicKey(publickey.1), || {
+            VerifiedPublicKey::from_nugget_bls_n_cp_pop::<H>(publickey, pop)
+        })
+    }
+
+    /// Register a key whose proof-of-possession was checked elsewhere,
+    /// unless it was revoked.
+    pub fn insert(&mut self, verified: VerifiedPublicKey<E>) -> Result<(), BlsError> {
+        self.register_with(verified.publickey(), || Ok(verified))
+            .map(|_| ())
+    }
+
+    /// Returns `publickey` as verified, if registered and not revoked.
+    pub fn get(&self, publickey: &PublicKey<E>) -> Option<VerifiedPublicKey<E>> {
+        self.verified.get(&publickey.to_bytes()).copied()
+    }
+
+    /// Returns true if `publickey` is registered and not revoked.
+    pub fn contains(&self, publickey: &PublicKey<E>) -> bool {
+        self.verified.contains_key(&publickey.to_bytes())
+    }
+
+    /// Revoke `publickey` permanently, returning true if it was registered.
+    pub fn revoke(&mut self, publickey: &PublicKey<E>) -> bool {
+        let index = publickey.to_bytes();
+        let registered = self.verified.remove(&index).is_some();
+        self.revoked.insert(index);
+        registered
+    }
+
+    /// Returns true if `publickey` was revoked.
+    pub fn is_revoked(&self, publickey: &PublicKey<E>) -> bool {
+        self.revoked.contains(&publickey.to_bytes())
+    }
+
+    /// Number of registered keys, excluding revoked ones.
+    pub fn len(&self) -> usize {
+        self.verified.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.verified.is_empty()
+    }
+
+    /// Iterate over registered keys, ordered by their serialization.
+    pub fn iter(&self) -> impl Iterator<Item = &VerifiedPublicKey<E>> {
+        self.verified.values()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand::thread_rng;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::double::DoublePublicKeyScheme;
+    use crate::multi_pop_aggregator::VerifiedMultiMessageSignatureAggregator;
+    use crate::single::Keypair;
+    use crate::single_pop_aggregator::VerifiedSignatureAggregator;
+    use crate::{Message, ProofOfPossessionGenerator, Signed, TinyBLS381};
+
+    type E = TinyBLS381;
+
+    fn schnorr_pop(keypair: &mut Keypair<E>) -> SchnorrPoP<E> {
+        ProofOfPossessionGenerator::<E, Sha256, PublicKey<E>, SchnorrPoP<E>>::generate_pok(keypair)
+    }
+
+    #[test]
+    fn verified_public_keys_require_valid_pops() {
+        let mut keypair = Keypair::<E>::generate(thread_rng());
+        let mut other = Keypair::<E>::generate(thread_rng());
+
+        let pop = schnorr_pop(&mut keypair);
+        let verified =
+            VerifiedPublicKey::from_schnorr_pop::<Sha256>(&keypair.public, &pop).unwrap();
+        assert!(verified.publickey() == &keypair.public);
+        assert!(VerifiedPublicKey::from_schnorr_pop::<Sha256>(&other.public, &pop).is_err());
+
+        let pop: BatchableSchnorrPoP<E> = ProofOfPossessionGenerator::<
+            E,
+            Sha256,
+            PublicKey<E>,
+            BatchableSchnorrPoP<E>,
+        >::generate_pok(&mut keypair);
+        assert!(
+            VerifiedPublicKey::from_batchable_schnorr_pop::<Sha256>(&keypair.public, &pop).is_ok()
+        );
+
+        let double: DoublePublicKey<E> = keypair.into_double_public_key();
+        let other_double: DoublePublicKey<E> = other.into_double_public_key();
+        let pop: NuggetBLSPoP<E> = ProofOfPossessionGenerator::<
+            E,
+            Sha256,
+            DoublePublicKey<E>,
+            NuggetBLSPoP<E>,
+        >::generate_pok(&mut keypair);
+        assert!(
+            VerifiedPublicKey::from_nugget_bls_pop::<Sha256>(&double, &pop).unwrap() == verified
+        );
+        assert!(VerifiedPublicKey::from_nugget_bls_pop::<Sha256>(&other_double, &pop).is_err());
+
+        let pop: NuggetBLSnCPPoP<E> = ProofOfPossessionGenerator::<
+            E,
+            Sha256,
+            DoublePublicKey<E>,
+            NuggetBLSnCPPoP<E>,
+        >::generate_pok(&mut other);
+        assert!(VerifiedPublicKey::from_nugget_bls_n_cp_pop::<Sha256>(&other_double, &pop).is_ok());
+        assert!(VerifiedPublicKey::from_nugget_bls_n_cp_pop::<Sha256>(&double, &pop).is_err());
+    }
+
+    #[test]
+    fn registry_caches_and_revokes() {
+        let mut keypair = Keypair::<E>::generate(thread_rng());
+        let mut other = Keypair::<E>::generate(thread_rng());
+        let pop = schnorr_pop(&mut keypair);
+
+        let mut registry = PopRegistry::<E>::new();
+        assert!(registry.is_empty());
+        assert!(registry
+            .register_schnorr_pop::<Sha256>(&other.public, &pop)
+            .is_err());
+        assert!(!registry.contains(&other.public));
+
+        let verified = registry
+            .register_schnorr_pop::<Sha256>(&keypair.public, &pop)
+            .unwrap();
+        assert!(registry.get(&keypair.public) == Some(verified));
+        // We skip verification for registered keys.
+        let wrong_pop = schnorr_pop(&mut other);
+        assert!(registry
+            .register_schnorr_pop::<Sha256>(&keypair.public, &wrong_pop)
+            .is_ok());
+        assert_eq!(registry.len(), 1);
+
+        let double: DoublePublicKey<E> = other.into_double_public_key();
+        let pop: NuggetBLSPoP<E> = ProofOfPossessionGenerator::<
+            E,
+            Sha256,
+            DoublePublicKey<E>,
+            NuggetBLSPoP<E>,
+        >::generate_pok(&mut other);
+        registry
+            .register_nugget_bls_pop::<Sha256>(&double, &pop)
+            .unwrap();
+        assert!(registry.contains(&other.public));
+        assert_eq!(registry.iter().count(), 2);
+
+        assert!(registry.revoke(&keypair.public));
+        assert!(!registry.revoke(&keypair.public));
+        assert!(registry.is_revoked(&keypair.public));
+        assert!(registry.get(&keypair.public).is_none());
+        let pop = schnorr_pop(&mut keypair);
+        assert!(
+            registry.register_schnorr_pop::<Sha256>(&keypair.public, &pop)
+                == Err(BlsError::RevokedPublicKey)
+        );
+        assert!(registry.insert(verified) == Err(BlsError::RevokedPublicKey));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn aggregators_from_verified_public_keys() {
+        let message = Message::new(b"ctx", b"vote");
+        let mut keypairs: Vec<_> = (0..3)
+            .map(|_| Keypair::<E>::generate(thread_rng()))
+            .collect();
+        let mut registry = PopRegistry::<E>::new();
+        for k in keypairs.iter_mut() {
+            let pop = schnorr_pop(k);
+            registry
+                .register_schnorr_pop::<Sha256>(&k.public, &pop)
+                .unwrap();
+        }
+
+        let mut single = VerifiedSignatureAggregator::<E>::from_verified_publickeys(
+            message.clone(),
+            registry.iter(),
+        );
+        let mut multi = VerifiedMultiMessageSignatureAggregator::<E>::from_verified_publickeys(
+            registry.iter().map(|pk| (&message, pk)),
+        );
+        for k in keypairs.iter_mut() {
+            let signature = k.sign(&message);
+            single.add_signature(&signature);
+            multi.add_signature(&signature);
+        }
+        assert!(single.verify());
+        assert!(multi.verify());
+        assert!(single.invalid().is_none() && multi.invalid().is_none());
+
+        let mut extra = Keypair::<E>::generate(thread_rng());
+        let pop = schnorr_pop(&mut extra);
+        let verified = VerifiedPublicKey::from_schnorr_pop::<Sha256>(&extra.public, &pop).unwrap();
+        single.add_verified_publickey(&verified);
+        multi.add_verified_message_n_publickey(&message, &verified);
+        assert!(!single.verify());
+        assert!(!multi.verify());
+        let signature = extra.sign(&message);
+        single.add_signature(&signature);
+        multi.add_signature(&signature);
+        assert!(single.verify());
+        assert!(multi.verify());
+
+        single.remove_signer(&verified, &signature, None).unwrap();
+        multi
+            .remove_signer(&message, &verified, &signature, None)
+            .unwrap();
+        assert!(single.verify());
+        assert!(multi.verify());
+    }
+}
@@ -0,0 +1,536 @@
+//! ## Resharing and refreshing threshold keys
+//!
+//! Resharing moves a shared secret from an old `(t, n)` committee to a
+//! new `(t', n')` committee without changing the group `PublicKey`,
+//! following [Desmedt and Jajodia](https://www.researchgate.net/publication/2737691).
+//! Every old shareholder `i` deals its share `s_i` to the new committee
+//! using the `dkg` messages, but with a polynomial whose constant term
+//! is `s_i`, which anyone checks against the old public share `s_i g`.
+//! New shareholders then interpolate the shares dealt by the qualified
+//! old shareholders, of whom there must be at least `t`, so the new
+//! shares lie on a fresh polynomial with the same constant term.
+//!
+//! Refreshing re-randomizes the shares of an unchanged committee, as in
+//! [Herzberg et al.](https://link.springer.com/chapter/10.1007/3-540-44750-4_27),
+//! by having every shareholder deal a polynomial with constant term zero
+//! and adding the qualified dealings to the existing shares.  Shares
+//! from before and after a refresh cannot be combined, so an adversary
+//! must corrupt `t` shareholders between two refreshes.
+//!
+//! We run complaints and justifications exactly like `dkg::Participant`,
+//! and make the same assumptions about channels.  As old and new
+//! committees use separate indices, callers route `DkgMessage::Share`
+//! messages by their recipient's index in the new committee, and
+//! broadcast all other messages to both committees.
+
+use alloc::vec::Vec;
+
+use ark_ff::Zero;
+use rand::Rng;
+
+use crate::dkg::{DkgError, DkgMessage, DkgOutput, DkgParameters, DkgPhase, Transcript};
+use crate::single::SecretKeyVT;
+use crate::threshold::{lagrange_coefficient_at_zero, Dealer, SecretShare, VerificationVector};
+use crate::EngineBLS;
+
+/// One participant of a resharing or refresh, who belongs to the old
+/// committee, the new committee, or both.
+pub struct Resharing<E: EngineBLS> {
+    old: DkgParameters,
+    new: DkgParameters,
+    verification_vector: VerificationVector<E>,
+    /// Our existing share, which a refresh updates.
+    share: Option<SecretShare<E>>,
+    refresh: bool,
+    dealer: Option<Dealer<E>>,
+    transcript: Transcript<E>,
+}
+
+impl<E: EngineBLS> Resharing<E> {
+    /// Reshare the secret behind `verification_vector` from the `old`
+    /// committee to the `new` committee.
+    ///
+    /// Members of the old committee provide their `share`, and members
+    /// of the new committee provide their `new_index`.
+    pub fn new<R: Rng>(
+        old: DkgParameters,
+        verification_vector: VerificationVector<E>,
+        share: Option<SecretShare<E>>,
+        new: DkgParameters,
+        new_index: Option<u32>,
+        mut rng: R,
+    ) -> Result<Self, DkgError> {
+        let old = DkgParameters::new(old.threshold, old.participants)?;
+        let new = DkgParameters::new(new.threshold, new.participants)?;
+        if verification_vector.threshold() != old.threshold as usize {
+            return Err(DkgError::InvalidParameters);
+        }
+        if let Some(share) = share.as_ref() {
+            Self::check_share(&old, &verification_vector, share)?;
+        }
+        if let Some(index) = new_index {
+            if index == 0 || index > new.participants {
+                return Err(DkgError::UnknownParticipant(index));
+            }
+        }
+        let dealer = match share.as_ref() {
+            Some(share) => Some(
+                Dealer::new(&share.secret, new.threshold as usize, &mut rng)
+                    .map_err(|_| DkgError::InvalidParameters)?,
+            ),
+            None => None,
+        };
+        let transcript = Transcript::new(
+            old.participants,
+            new.participants,
+            new.threshold,
+            share.as_ref().map(|share| share.index),
+            new_index,
+        );
+        Ok(Resharing {
+            old,
+            new,
+            verification_vector,
+            share,
+            refresh: false,
+            dealer,
+            transcript,
+        })
+    }
+
+    /// Refresh our `share` of the secret behind `verification_vector`,
+    /// keeping the committee and its indices.
+    pub fn refresh<R: Rng>(
+        parameters: DkgParameters,
+        verification_vector: VerificationVector<E>,
+        share: SecretShare<E>,
+        mut rng: R,
+    ) -> Result<Self, DkgError> {
+        let parameters = DkgParameters::new(parameters.threshold, parameters.participants)?;
+        if verification_vector.threshold() != parameters.threshold as usize {
+            return Err(DkgError::InvalidParameters);
+        }
+        Self::check_share(&parameters, &verification_vector, &share)?;
+        let dealer = Dealer::new(
+            &SecretKeyVT(E::Scalar::zero()),
+            parameters.threshold as usize,
+            &mut rng,
+        )
+        .map_err(|_| DkgError::InvalidParameters)?;
+        let transcript = Transcript::new(
+            parameters.participants,
+            parameters.participants,
+            parameters.threshold,
+            Some(share.index),
+            Some(share.index),
+        );
+        Ok(Resharing {
+            old: parameters,
+            new: parameters,
+            verification_vector,
+            share: Some(share),
+            refresh: true,
+            dealer: Some(dealer),
+            transcript,
+        })
+    }
+
+    fn check_share(
+        parameters: &DkgParameters,
+        verification_vector: &VerificationVector<E>,
+        share: &SecretShare<E>,
+    ) -> Result<(), DkgError> {
+        if share.index == 0 || share.index > parameters.participants {
+            return Err(DkgError::UnknownParticipant(share.index));
+        }
+        if !verification_vector.verify_share(share) {
+            return Err(DkgError::InvalidShare);
+        }
+        Ok(())
+    }
+
+    /// Our index in the old committee, if we deal.
+    pub fn old_index(&self) -> Option<u32> {
+        self.share.as_ref().map(|share| share.index)
+    }
+
+    /// Our index in the new committee, if we receive.
+    pub fn new_index(&self) -> Option<u32> {
+        self.transcript.recipient()
+    }
+
+    pub fn phase(&self) -> DkgPhase {
+        self.transcript.phase()
+    }
+
+    /// Our first round messages, if we belong to the old committee.
+    pub fn deal(&mut self) -> Result<Vec<DkgMessage<E>>, DkgError> {
+        match self.dealer.as_ref() {
+            Some(dealer) => self.transcript.deal(dealer),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Process a message from another participant.
+    pub fn handle(&mut self, message: DkgMessage<E>) -> Result<(), DkgError> {
+        self.transcript.handle(message)
+    }
+
+    /// Close the current round and return our messages for the next one,
+    /// as in `dkg::Participant::advance`.
+    pub fn advance(&mut self) -> Result<Vec<DkgMessage<E>>, DkgError> {
+        self.transcript.advance(self.dealer.as_ref())
+    }
+
+    /// Sorted indices of the old shareholders who dealt correctly,
+    /// including dealing the right constant term.
+    pub fn qualified(&self) -> Result<Vec<u32>, DkgError> {
+        Ok(self
+            .transcript
+            .qualified()?
+            .into_iter()
+            .filter(|dealer| {
+                let constant = self.transcript.commitments(*dealer).0[0];
+                if self.refresh {
+                    constant.is_zero()
+                } else {
+                    constant == self.verification_vector.public_share(*dealer).0
+                }
+            })
+            .collect())
+    }
+
+    /// Our new share and the new verification vector, whose group
+    /// public key equals the old one.
+    pub fn finalize(&self) -> Result<DkgOutput<E>, DkgError> {
+        let qualified = self.qualified()?;
+        let index = self.new_index().ok_or(DkgError::NotARecipient)?;
+        let share_from = |dealer: &u32| {
+            self.transcript
+                .share_from(*dealer)
+                .expect("qualified dealers gave us a share")
+        };
+
+        let (secret, verification_vector) = if self.refresh {
+            let mut secret = self
+                .share
+                .as_ref()
+                .expect("refresh requires a share")
+                .secret
+                .0;
+            let mut verification_vector = self.verification_vector.0.clone();
+            for dealer in qualified.iter() {
+                secret += share_from(dealer);
+                for (sum, c) in verification_vector
+                    .iter_mut()
+                    .zip(self.transcript.commitments(*dealer).0.iter())
+                {
+                    *sum += c;
+                }
+            }
+            (secret, verification_vector)
+        } else {
+            if qualified.len() < self.old.threshold as usize {
+                return Err(DkgError::TooFewQualifiedDealers(qualified.len()));
+            }
+            let mut secret = E::Scalar::zero();
+            let mut verification_vector =
+                alloc::vec![E::PublicKeyGroup::zero(); self.new.threshold as usize];
+            for dealer in qualified.iter() {
+                let lambda: E::Scalar = lagrange_coefficient_at_zero(*dealer, &qualified);
+                secret += lambda * share_from(dealer);
+                for (sum, c) in verification_vector
+                    .iter_mut()
+                    .zip(self.transcript.commitments(*dealer).0.iter())
+                {
+                    *sum += *c * lambda;
+                }
+            }
+            (secret, verification_vector)
+        };
+
+        Ok(DkgOutput {
+            share: SecretShare {
+                index,
+                secret: SecretKeyVT(secret),
+            },
+            verification_vector: VerificationVector(verification_vector),
+            qualified,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::threshold::{combine_partial_signatures, reconstruct_secret};
+    use crate::{Message, TinyBLS381, ZBLS};
+
+    fn transmit<E: EngineBLS>(message: &DkgMessage<E>) -> DkgMessage<E> {
+        let mut bytes = Vec::new();
+        message.serialize_compressed(&mut bytes).unwrap();
+        DkgMessage::deserialize_compressed(&bytes[..]).unwrap()
+    }
+
+    /// Shares of a fresh secret from a trusted dealer.
+    fn deal<E: EngineBLS>(
+        t: u32,
+        n: u32,
+        rng: &mut ChaCha8Rng,
+    ) -> (VerificationVector<E>, Vec<SecretShare<E>>) {
+        let secret = SecretKeyVT::generate(&mut *rng);
+        let dealer = Dealer::new(&secret, t as usize, rng).unwrap();
+        (dealer.verification_vector(), dealer.shares(n))
+    }
+
+    /// Run all rounds in-process, delivering private shares by their
+    /// recipient's new index and broadcasts to everyone else, after
+    /// `adversary` possibly tampered with or dropped them.
+    fn simulate<E: EngineBLS>(
+        parties: &mut [Resharing<E>],
+        mut adversary: impl FnMut(&mut DkgMessage<E>) -> bool,
+    ) {
+        for round in 0..4 {
+            let mut outbox = Vec::new();
+            for (sender, party) in parties.iter_mut().enumerate() {
+                let messages = match round {
+                    0 => party.deal().unwrap(),
+                    _ => party.advance().unwrap(),
+                };
+                outbox.extend(messages.into_iter().map(|m| (sender, m)));
+            }
+            for (sender, mut message) in outbox {
+                if !adversary(&mut message) {
+                    continue;
+                }
+                for (receiver, party) in parties.iter_mut().enumerate() {
+                    let addressed = match message.recipient() {
+                        Some(recipient) => Some(recipient) == party.new_index(),
+                        None => receiver != sender,
+                    };
+                    if addressed {
+                        party.handle(transmit(&message)).unwrap();
+                    }
+                }
+            }
+        }
+        assert!(parties.iter().all(|p| p.phase() == DkgPhase::Finished));
+    }
+
+    /// Check that the new shares agree and sign under the old group key.
+    fn check_outputs<E: EngineBLS>(
+        outputs: &[DkgOutput<E>],
+        t: usize,
+        verification_vector: &VerificationVector<E>,
+    ) {
+        for output in outputs {
+            assert!(output.verification_vector == outputs[0].verification_vector);
+            assert!(output.verification_vector.verify_share(&output.share));
+        }
+        let group_public_key = verification_vector.group_public_key();
+        assert!(outputs[0].group_public_key() == group_public_key);
+        assert_eq!(outputs[0].verification_vector.threshold(), t);
+
+        let shares = outputs.iter().map(|o| o.share.clone()).collect::<Vec<_>>();
+        let secret = reconstruct_secret(&shares[shares.len() - t..]).unwrap();
+        assert!(secret.into_public() == group_public_key);
+
+        let message = Message::new(b"ctx", b"reshared message");
+        let partials = shares[..t]
+            .iter()
+            .map(|s| s.sign(&message))
+            .collect::<Vec<_>>();
+        let signature = combine_partial_signatures(&partials).unwrap();
+        assert!(signature.verify(&message, &group_public_key));
+    }
+
+    /// Parties `0..5` hold shares of a `(3, 5)` secret, and parties `2..6`
+    /// form the new `(2, 4)` committee.
+    fn committees<E: EngineBLS>(
+        rng: &mut ChaCha8Rng,
+    ) -> (VerificationVector<E>, Vec<Resharing<E>>) {
+        let old = DkgParameters::new(3, 5).unwrap();
+        let new = DkgParameters::new(2, 4).unwrap();
+        let (verification_vector, shares) = deal::<E>(3, 5, rng);
+        let parties = (0..6)
+            .map(|k| {
+                let share = shares.get(k).cloned();
+                let new_index = if k >= 2 { Some(k as u32 - 1) } else { None };
+                Resharing::new(
+                    old,
+                    verification_vector.clone(),
+                    share,
+                    new,
+                    new_index,
+                    &mut *rng,
+                )
+                .unwrap()
+            })
+            .collect();
+        (verification_vector, parties)
+    }
+
+    fn reshare<E: EngineBLS>() {
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        let (verification_vector, mut parties) = committees::<E>(&mut rng);
+        simulate(&mut parties, |_| true);
+
+        for party in parties[..2].iter() {
+            assert!(matches!(party.finalize(), Err(DkgError::NotARecipient)));
+        }
+        let outputs = parties[2..]
+            .iter()
+            .map(|p| p.finalize().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(outputs[0].qualified, [1, 2, 3, 4, 5]);
+        check_outputs(&outputs, 2, &verification_vector);
+    }
+
+    #[test]
+    fn reshare_keeps_group_public_key() {
+        reshare::<ZBLS>();
+        reshare::<TinyBLS381>();
+    }
+
+    #[test]
+    fn reshare_is_deterministic() {
+        let run = || {
+            let mut rng = ChaCha8Rng::seed_from_u64(7);
+            let (_, mut parties) = committees::<ZBLS>(&mut rng);
+            simulate(&mut parties, |_| true);
+            parties[5].finalize().unwrap()
+        };
+        let (a, b) = (run(), run());
+        assert!(a.verification_vector == b.verification_vector);
+        assert_eq!(a.share.secret.0, b.share.secret.0);
+    }
+
+    #[test]
+    fn reshare_disqualifies_wrong_constant_terms() {
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        let (verification_vector, mut parties) = committees::<ZBLS>(&mut rng);
+        // Old shareholder 1 deals consistent shares of the wrong secret,
+        // and old shareholder 5 never deals.
+        let wrong = SecretKeyVT::generate(&mut rng);
+        parties[0].dealer = Some(Dealer::new(&wrong, 2, &mut rng).unwrap());
+        simulate(&mut parties, |message| match message {
+            DkgMessage::Deal(m) => m.dealer != 5,
+            _ => true,
+        });
+        // Old shareholder 5 sees its own commitments, so we only check
+        // the outputs of honest new shareholders.
+        let outputs = [2, 3, 5]
+            .iter()
+            .map(|k| parties[*k].finalize().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(outputs[0].qualified, [2, 3, 4]);
+        check_outputs(&outputs, 2, &verification_vector);
+
+        // Without a third qualified old shareholder we cannot reshare.
+        let (_, mut parties) = committees::<ZBLS>(&mut rng);
+        simulate(&mut parties, |message| match message {
+            DkgMessage::Deal(m) => m.dealer > 3,
+            _ => true,
+        });
+        assert!(matches!(
+            parties[5].finalize(),
+            Err(DkgError::TooFewQualifiedDealers(2))
+        ));
+    }
+
+    #[test]
+    fn reshare_ignores_unsolicited_justifications() {
+        let mut rng = ChaCha8Rng::seed_from_u64(17);
+        let (verification_vector, mut parties) = committees::<ZBLS>(&mut rng);
+        // Old shareholder 3 justifies a bad share for new shareholder 2,
+        // who never complained.
+        simulate(&mut parties, |message| {
+            if let DkgMessage::Justification(m) = message {
+                if m.dealer == 3 {
+                    m.shares.push((2, <ZBLS as EngineBLS>::Scalar::from(7u64)));
+                }
+            }
+            true
+        });
+        let outputs = parties[2..]
+            .iter()
+            .map(|p| p.finalize().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(outputs[0].qualified, [1, 2, 3, 4, 5]);
+        assert_eq!(outputs[1].share.index, 2);
+        assert!(outputs[1]
+            .verification_vector
+            .verify_share(&outputs[1].share));
+        check_outputs(&outputs, 2, &verification_vector);
+    }
+
+    #[test]
+    fn refresh_rerandomizes_shares() {
+        let mut rng = ChaCha8Rng::seed_from_u64(13);
+        let parameters = DkgParameters::new(3, 4).unwrap();
+        let (verification_vector, shares) = deal::<TinyBLS381>(3, 4, &mut rng);
+        let mut parties = shares
+            .iter()
+            .map(|share| {
+                Resharing::refresh(
+                    parameters,
+                    verification_vector.clone(),
+                    share.clone(),
+                    &mut rng,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        // Corrupt one share in transit, which its dealer justifies, and
+        // let that dealer also justify a bad share nobody asked for.
+        simulate(&mut parties, |message| {
+            match message {
+                DkgMessage::Share(m) if m.dealer == 4 && m.recipient == 1 => {
+                    m.share += m.share;
+                }
+                DkgMessage::Justification(m) if m.dealer == 4 => {
+                    m.shares
+                        .push((2, <TinyBLS381 as EngineBLS>::Scalar::from(7u64)));
+                }
+                _ => {}
+            }
+            true
+        });
+        let outputs = parties
+            .iter()
+            .map(|p| p.finalize().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(outputs[0].qualified, [1, 2, 3, 4]);
+        check_outputs(&outputs, 3, &verification_vector);
+        for (output, share) in outputs.iter().zip(shares.iter()) {
+            assert_eq!(output.share.index, share.index);
+            assert!(output.share.secret.0 != share.secret.0);
+        }
+
+        // Old and new shares no longer combine.
+        let mixed = [
+            shares[0].clone(),
+            outputs[1].share.clone(),
+            outputs[2].share.clone(),
+        ];
+        let secret = reconstruct_secret(&mixed).unwrap();
+        assert!(secret.into_public() != verification_vector.group_public_key());
+
+        assert!(matches!(
+            Resharing::refresh(
+                parameters,
+                verification_vector,
+                SecretShare {
+                    index: 1,
+                    secret: shares[1].secret.clone(),
+                },
+                &mut rng,
+            ),
+            Err(DkgError::InvalidShare)
+        ));
+    }
+}
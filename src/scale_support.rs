@@ -0,0 +1,234 @@
+//! ## SCALE codec support
+//!
+//! We implement `parity-scale-codec`'s `Encode`, `Decode` and
+//! `MaxEncodedLen`, and `scale-info`'s `TypeInfo`, for keys, signatures
+//! and proofs-of-possession, so Substrate runtimes can keep them in
+//! storage and extrinsics directly.
+//!
+//! We encode through `SerializableToBytes`, so as fixed length byte
+//! arrays without any length prefix, which `TypeInfo` describes as
+//! `[u8; SERIALIZED_BYTES_SIZE]`, under the path of the module defining
+//! the type, with the engine `E` as a type parameter.  We decode with the same subgroup
+//! checks as `SerializableToBytes::from_bytes`, and reject identity
+//! public keys too.
+
+use alloc::{vec, vec::Vec};
+
+use codec::{
+    Decode, DecodeWithMemTracking, Encode, EncodeLike, Error, Input, MaxEncodedLen, Output,
+};
+use scale_info::{MetaType, Path, Type, TypeDefArray, TypeInfo, TypeParameter};
+
+use crate::double::{
+    BatchableDoubleSignature, DoublePublicKey, DoubleSignature, PublicKeyInSignatureGroup,
+};
+use crate::double_pop::{NuggetBLSPoP, NuggetBLSnCPPoP};
+use crate::error::{self, BlsError};
+use crate::schnorr_pop::{BatchableSchnorrPoP, SchnorrPoP};
+use crate::serialize::SerializableToBytes;
+use crate::single::{PublicKey, Signature};
+use crate::EngineBLS;
+
+fn decode_bytes<I: Input, T: SerializableToBytes>(input: &mut I) -> Result<T, Error> {
+    let mut b = vec![0u8; T::SERIALIZED_BYTES_SIZE];
+    input.read(&mut b)?;
+    T::from_bytes(&b).map_err(|_| Error::from("Invalid point encoding or point not in subgroup"))
+}
+
+fn reject(check: Result<(), BlsError>) -> Result<(), Error> {
+    check.map_err(|e| match e {
+        BlsError::IdentityPublicKey => Error::from("Public key is the identity point"),
+        _ => Error::from("Point is not in the prime order subgroup"),
+    })
+}
+
+macro_rules! scale_via_bytes {
+    ($wrapper:ident in $module:ident, $x:ident => $validate:expr) => {
+        impl<E: EngineBLS> Encode for $wrapper<E> {
+            fn size_hint(&self) -> usize {
+                <Self as SerializableToBytes>::SERIALIZED_BYTES_SIZE
+            }
+
+            fn encode_to<O: Output + ?Sized>(&self, dest: &mut O) {
+                dest.write(&self.to_bytes())
+            }
+
+            fn encoded_size(&self) -> usize {
+                <Self as SerializableToBytes>::SERIALIZED_BYTES_SIZE
+            }
+        }
+
+        impl<E: EngineBLS> EncodeLike for $wrapper<E> {}
+
+        impl<E: EngineBLS> Decode for $wrapper<E> {
+            fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+                let $x: $wrapper<E> = decode_bytes(input)?;
+                reject($validate)?;
+                Ok($x)
+            }
+
+            fn encoded_fixed_size() -> Option<usize> {
+                Some(<Self as SerializableToBytes>::SERIALIZED_BYTES_SIZE)
+            }
+        }
+
+        /// We allocate only the fixed size buffer while decoding.
+        impl<E: EngineBLS> DecodeWithMemTracking for $wrapper<E> {}
+
+        impl<E: EngineBLS> MaxEncodedLen for $wrapper<E> {
+            fn max_encoded_len() -> usize {
+                <Self as SerializableToBytes>::SERIALIZED_BYTES_SIZE
+            }
+        }
+
+        impl<E: EngineBLS + 'static> TypeInfo for $wrapper<E> {
+            type Identity = Self;
+
+            fn type_info() -> Type {
+                Type::new(
+                    Path::new(
+                        stringify!($wrapper),
+                        concat!(env!("CARGO_CRATE_NAME"), "::", stringify!($module)),
+                    ),
+                    vec![TypeParameter::new("E", None)],
+                    TypeDefArray::new(
+                        <Self as SerializableToBytes>::SERIALIZED_BYTES_SIZE as u32,
+                        MetaType::new::<u8>(),
+                    ),
+                    Vec::new(),
+                )
+            }
+        }
+    };
+}
+
+scale_via_bytes!(PublicKey in single, pk => pk.validate());
+scale_via_bytes!(Signature in single, _signature => Ok(()));
+scale_via_bytes!(DoublePublicKey in double, pk => pk.validate());
+scale_via_bytes!(DoubleSignature in double, _signature => Ok(()));
+scale_via_bytes!(BatchableDoubleSignature in double, _signature => Ok(()));
+scale_via_bytes!(PublicKeyInSignatureGroup in double, pk => error::check_publickey(&pk.0));
+scale_via_bytes!(NuggetBLSPoP in double_pop, _pop => Ok(()));
+scale_via_bytes!(NuggetBLSnCPPoP in double_pop, _pop => Ok(()));
+scale_via_bytes!(SchnorrPoP in schnorr_pop, _pop => Ok(()));
+scale_via_bytes!(BatchableSchnorrPoP in schnorr_pop, _pop => Ok(()));
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use ark_ff::Zero;
+    use rand::thread_rng;
+    use scale_info::{Registry, TypeDef};
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::double::DoublePublicKeyScheme;
+    use crate::single::Keypair;
+    use crate::{Message, ProofOfPossession, ProofOfPossessionGenerator, TinyBLS381, ZBLS};
+
+    fn round_trip<T: Encode + Decode + MaxEncodedLen + TypeInfo + SerializableToBytes + 'static>(
+        x: &T,
+        module: &str,
+    ) {
+        let encoded = x.encode();
+        assert_eq!(encoded, x.to_bytes());
+        assert_eq!(encoded.len(), T::max_encoded_len());
+        assert_eq!(
+            T::decode(&mut &encoded[..]).unwrap().to_bytes(),
+            x.to_bytes()
+        );
+        // Truncated inputs fail, and decoding consumes exactly the encoding.
+        assert!(T::decode(&mut &encoded[1..]).is_err());
+        let mut longer = encoded.clone();
+        longer.push(7);
+        let mut input = &longer[..];
+        assert!(T::decode(&mut input).is_ok());
+        assert_eq!(input, &[7]);
+
+        let mut registry = Registry::new();
+        let id = registry.register_type(&MetaType::new::<T>());
+        let registry: scale_info::PortableRegistry = registry.into();
+        let ty = registry.resolve(id.id).unwrap();
+        match &ty.type_def {
+            TypeDef::Array(array) => assert_eq!(array.len as usize, encoded.len()),
+            _ => panic!("Expected an array type"),
+        }
+        // We name the defining module, and the engine as a type parameter.
+        assert_eq!(ty.path.segments[..2], ["tnt_bls", module]);
+        assert_eq!(ty.type_params.len(), 1);
+        assert_eq!(ty.type_params[0].name, "E");
+    }
+
+    #[test]
+    fn scale_round_trips() {
+        let mut keypair = Keypair::<ZBLS>::generate(thread_rng());
+        let message = Message::new(b"ctx", b"scale");
+        round_trip(&keypair.public, "single");
+        round_trip(&keypair.sign(&message), "single");
+
+        let mut keypair = Keypair::<TinyBLS381>::generate(thread_rng());
+        let double: DoublePublicKey<TinyBLS381> =
+            DoublePublicKeyScheme::into_double_public_key(&keypair);
+        round_trip(&double, "double");
+        let publickey_in_signature_group: PublicKeyInSignatureGroup<TinyBLS381> =
+            DoublePublicKeyScheme::into_public_key_in_signature_group(&keypair);
+        round_trip(&publickey_in_signature_group, "double");
+        let signature: DoubleSignature<TinyBLS381> =
+            DoublePublicKeyScheme::sign(&mut keypair, &message);
+        round_trip(&signature, "double");
+        let signature: BatchableDoubleSignature<TinyBLS381> =
+            DoublePublicKeyScheme::sign_batchable(&mut keypair, &message);
+        round_trip(&signature, "double");
+
+        let pop: NuggetBLSPoP<TinyBLS381> = ProofOfPossessionGenerator::<
+            TinyBLS381,
+            Sha256,
+            DoublePublicKey<TinyBLS381>,
+            NuggetBLSPoP<TinyBLS381>,
+        >::generate_pok(&mut keypair);
+        round_trip(&pop, "double_pop");
+        let decoded = NuggetBLSPoP::<TinyBLS381>::decode(&mut &pop.encode()[..]).unwrap();
+        assert!(ProofOfPossession::<TinyBLS381, Sha256, _>::verify(
+            &decoded, &double
+        ));
+        let pop: NuggetBLSnCPPoP<TinyBLS381> = ProofOfPossessionGenerator::<
+            TinyBLS381,
+            Sha256,
+            DoublePublicKey<TinyBLS381>,
+            NuggetBLSnCPPoP<TinyBLS381>,
+        >::generate_pok(&mut keypair);
+        round_trip(&pop, "double_pop");
+        let pop: SchnorrPoP<TinyBLS381> = ProofOfPossessionGenerator::<
+            TinyBLS381,
+            Sha256,
+            PublicKey<TinyBLS381>,
+            SchnorrPoP<TinyBLS381>,
+        >::generate_pok(&mut keypair);
+        round_trip(&pop, "schnorr_pop");
+        let decoded = SchnorrPoP::<TinyBLS381>::decode(&mut &pop.encode()[..]).unwrap();
+        assert!(ProofOfPossession::<TinyBLS381, Sha256, _>::verify(
+            &decoded,
+            &keypair.public
+        ));
+        let pop: BatchableSchnorrPoP<TinyBLS381> = ProofOfPossessionGenerator::<
+            TinyBLS381,
+            Sha256,
+            PublicKey<TinyBLS381>,
+            BatchableSchnorrPoP<TinyBLS381>,
+        >::generate_pok(&mut keypair);
+        round_trip(&pop, "schnorr_pop");
+    }
+
+    #[test]
+    fn scale_rejects_invalid_inputs() {
+        let identity = PublicKey::<ZBLS>(Zero::zero());
+        assert!(PublicKey::<ZBLS>::decode(&mut &identity.encode()[..]).is_err());
+        let identity = PublicKeyInSignatureGroup::<ZBLS>(Zero::zero());
+        assert!(PublicKeyInSignatureGroup::<ZBLS>::decode(&mut &identity.encode()[..]).is_err());
+
+        let keypair = Keypair::<ZBLS>::generate(thread_rng());
+        let mut b = keypair.public.encode();
+        // Flipped bits leave the curve or the subgroup.
+        b[5] ^= 0xff;
+        assert!(PublicKey::<ZBLS>::decode(&mut &b[..]).is_err());
+    }
+}
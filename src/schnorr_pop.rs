@@ -4,15 +4,20 @@ use ark_ff::field_hashers::{DefaultFieldHasher, HashToField};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 
 use crate::engine::EngineBLS;
+use crate::error::{self, BlsError};
 use crate::{ProofOfPossession, ProofOfPossessionGenerator};
 
 use crate::serialize::SerializableToBytes;
 use crate::single::{Keypair, PublicKey};
 
 use alloc::vec::Vec;
-use ark_ec::PrimeGroup;
+use ark_ec::{scalar_mul::variable_base::VariableBaseMSM, CurveGroup, PrimeGroup};
+use ark_ff::Zero;
 use digest::DynDigest;
 use digest::FixedOutputReset;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::Rng;
 
 pub type SchnorrProof<E> = (<E as EngineBLS>::Scalar, <E as EngineBLS>::Scalar);
 
@@ -25,6 +30,11 @@ impl<E: EngineBLS> Clone for SchnorrPoP<E> {
     }
 }
 
+/// Serialization for SchnorrPoP, so the response `s` followed by the challenge `k`
+impl<E: EngineBLS> SerializableToBytes for SchnorrPoP<E> {
+    const SERIALIZED_BYTES_SIZE: usize = 2 * E::SECRET_KEY_SIZE;
+}
+
 /// Generate Schnorr Signature for an arbitrary message using a key ment to use in BLS scheme
 trait BLSSchnorrPoPGenerator<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>:
     ProofOfPossessionGenerator<E, H, PublicKey<E>, SchnorrPoP<E>>
@@ -77,26 +87,43 @@ impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>
         // so either we need to two into_affine and one curve addition or or two curve additions.
         // instead we actually doing H(s*G - H(R|M)*Publickey|M) == H(R|M) == k
         // avoiding one curve addition (or two field divisions) in expense of a hash.
-        let mut r = <dyn BLSSchnorrPoPGenerator<E, H>>::witness_scalar(self);
+        let (_, s, k) = commit_n_respond::<E, H>(self);
+        SchnorrPoP::<E>((s, k))
+    }
+}
 
-        let mut r_point = <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::generator();
-        r_point *= r; //todo perhaps we need to mandate E to have  a hard coded point
+/// Schnorr commitment `R`, response `s` and challenge `k` proving
+/// possession of `keypair`'s secret key.
+fn commit_n_respond<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>(
+    keypair: &Keypair<E>,
+) -> (E::PublicKeyGroup, E::Scalar, E::Scalar) {
+    let mut r = <dyn BLSSchnorrPoPGenerator<E, H>>::witness_scalar(keypair);
 
-        let r_point_as_bytes = <E as EngineBLS>::public_key_point_to_byte(&r_point);
-        let public_key_as_bytes = <E as EngineBLS>::public_key_point_to_byte(&self.public.0); //it *must* be the public key (fixed) otherwise secret key can be recovered from the two different proves
+    let mut r_point = <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::generator();
+    r_point *= r; //todo perhaps we need to mandate E to have  a hard coded point
 
-        let proof_basis = [r_point_as_bytes, public_key_as_bytes].concat();
-        let hasher = <DefaultFieldHasher<H> as HashToField<
-            <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField,
-        >>::new(&[]);
-        let k = hasher.hash_to_field::<1>(proof_basis.as_slice())[0];
+    let k = challenge::<E, H>(&r_point, &keypair.public.0);
 
-        let s = (k * self.secret.into_vartime().0) + r;
+    let s = (k * keypair.secret.into_vartime().0) + r;
 
-        ::zeroize::Zeroize::zeroize(&mut r); //clear secret witness from memory
+    ::zeroize::Zeroize::zeroize(&mut r); //clear secret witness from memory
 
-        SchnorrPoP::<E>((s, k))
-    }
+    (r_point, s, k)
+}
+
+/// Fiat-Shamir challenge `k = H(R|Publickey)`
+fn challenge<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>(
+    r_point: &E::PublicKeyGroup,
+    public_key: &E::PublicKeyGroup,
+) -> E::Scalar {
+    let r_point_as_bytes = <E as EngineBLS>::public_key_point_to_byte(r_point);
+    let public_key_as_bytes = <E as EngineBLS>::public_key_point_to_byte(public_key); //it *must* be the public key (fixed) otherwise secret key can be recovered from the two different proves
+
+    let proof_basis = [r_point_as_bytes, public_key_as_bytes].concat();
+    let hasher = <DefaultFieldHasher<H> as HashToField<
+        <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField,
+    >>::new(&[]);
+    hasher.hash_to_field::<1>(proof_basis.as_slice())[0]
 }
 
 impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>
@@ -112,18 +139,128 @@ impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>
         k_public_key *= -self.0 .1;
         schnorr_point += k_public_key;
 
-        let schnorr_point_as_bytes = <E as EngineBLS>::public_key_point_to_byte(&schnorr_point);
-        let public_key_as_bytes =
-            <E as EngineBLS>::public_key_point_to_byte(&public_key_of_prover.0); //it *must* be the public key (fixed) otherwise secret key can be recovered from the two different proves
+        challenge::<E, H>(&schnorr_point, &public_key_of_prover.0) == self.0 .1
+    }
+
+    /// Reject the identity, for which anyone can forge a proof, and
+    /// otherwise report a mismatched challenge.
+    fn try_verify(&self, public_key_of_prover: &PublicKey<E>) -> Result<(), BlsError> {
+        error::check_publickey(&public_key_of_prover.0)?;
+        error::ensure(
+            <Self as ProofOfPossession<E, H, PublicKey<E>>>::verify(self, public_key_of_prover),
+            BlsError::FiatShamirMismatch,
+        )
+    }
+}
 
-        let resulting_proof_basis = [schnorr_point_as_bytes, public_key_as_bytes].concat();
+/// Schnorr proof-of-possession which carries the commitment `R = rG`
+/// instead of the challenge `k`, so that verifiers may check many
+/// proofs together with `verify_batch`.
+///
+/// We derive `R`, `k = H(R|Publickey)` and `s = k*private_key + r`
+/// exactly like `SchnorrPoP`, so either form converts into the other.
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BatchableSchnorrPoP<E: EngineBLS>(pub E::PublicKeyGroup, pub E::Scalar);
 
-        let hasher = <DefaultFieldHasher<H> as HashToField<
-            <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::ScalarField,
-        >>::new(&[]);
-        let random_scalar: E::Scalar =
-            hasher.hash_to_field::<1>(resulting_proof_basis.as_slice())[0];
-        random_scalar == self.0 .1
+impl<E: EngineBLS> Clone for BatchableSchnorrPoP<E> {
+    fn clone(&self) -> Self {
+        BatchableSchnorrPoP(self.0, self.1)
+    }
+}
+
+/// Serialization for BatchableSchnorrPoP, so the commitment `R` followed by the response `s`
+impl<E: EngineBLS> SerializableToBytes for BatchableSchnorrPoP<E> {
+    const SERIALIZED_BYTES_SIZE: usize = E::PUBLICKEY_SERIALIZED_SIZE + E::SECRET_KEY_SIZE;
+}
+
+impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>
+    ProofOfPossessionGenerator<E, H, PublicKey<E>, BatchableSchnorrPoP<E>> for Keypair<E>
+{
+    fn generate_pok(&mut self) -> BatchableSchnorrPoP<E> {
+        let (r_point, s, _) = commit_n_respond::<E, H>(self);
+        BatchableSchnorrPoP::<E>(r_point, s)
+    }
+}
+
+impl<E: EngineBLS> BatchableSchnorrPoP<E> {
+    /// Compact form without the commitment, which verifies only alone.
+    pub fn to_schnorr_pop<H: DynDigest + FixedOutputReset + Default + Clone>(
+        &self,
+        public_key_of_prover: &PublicKey<E>,
+    ) -> SchnorrPoP<E> {
+        SchnorrPoP((self.1, challenge::<E, H>(&self.0, &public_key_of_prover.0)))
+    }
+
+    /// Verify the proofs of possession for `publickeys` together,
+    /// by checking one random linear combination
+    ///
+    /// `sum z_i (s_i G - R_i - k_i Publickey_i) = 0`
+    ///
+    /// of their equations with 128 bit weights `z_i` drawn from `rng`,
+    /// using one multi-scalar multiplication.
+    ///
+    /// We reject identity public keys, for which anyone can forge a
+    /// proof, and batches whose lengths differ.  We say only whether
+    /// all proofs verify, so callers who need the invalid ones should
+    /// verify individually after a failure.
+    pub fn verify_batch_with_rng<H, R>(
+        publickeys: &[PublicKey<E>],
+        proofs: &[BatchableSchnorrPoP<E>],
+        mut rng: R,
+    ) -> bool
+    where
+        H: DynDigest + FixedOutputReset + Default + Clone,
+        R: Rng,
+    {
+        if publickeys.len() != proofs.len() || publickeys.iter().any(|pk| pk.0.is_zero()) {
+            return false;
+        }
+        let mut s = E::Scalar::zero();
+        let mut points = Vec::with_capacity(2 * proofs.len());
+        let mut scalars = Vec::with_capacity(2 * proofs.len() + 1);
+        for (publickey, proof) in publickeys.iter().zip(proofs) {
+            let z = E::Scalar::from(rng.gen::<u128>());
+            s += z * proof.1;
+            points.push(proof.0);
+            scalars.push(-z);
+            points.push(publickey.0);
+            scalars.push(-z * challenge::<E, H>(&proof.0, &publickey.0));
+        }
+        points.push(<<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::generator());
+        scalars.push(s);
+        let points = E::PublicKeyGroup::normalize_batch(&points);
+        E::PublicKeyGroup::msm_unchecked(&points, &scalars).is_zero()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn verify_batch<H: DynDigest + FixedOutputReset + Default + Clone>(
+        publickeys: &[PublicKey<E>],
+        proofs: &[BatchableSchnorrPoP<E>],
+    ) -> bool {
+        Self::verify_batch_with_rng::<H, _>(publickeys, proofs, thread_rng())
+    }
+}
+
+impl<E: EngineBLS, H: DynDigest + FixedOutputReset + Default + Clone>
+    ProofOfPossession<E, H, PublicKey<E>> for BatchableSchnorrPoP<E>
+{
+    /// verify `s*G == R + k*Publickey` with `k = H(R|Publickey)`
+    fn verify(&self, public_key_of_prover: &PublicKey<E>) -> bool {
+        let mut schnorr_point = <<E as EngineBLS>::PublicKeyGroup as PrimeGroup>::generator();
+        schnorr_point *= self.1;
+        let mut k_public_key = public_key_of_prover.0;
+        k_public_key *= challenge::<E, H>(&self.0, &public_key_of_prover.0);
+        schnorr_point == self.0 + k_public_key
+    }
+
+    /// Reject the identity, for which anyone can forge a proof, and
+    /// otherwise report a mismatched challenge.
+    fn try_verify(&self, public_key_of_prover: &PublicKey<E>) -> Result<(), BlsError> {
+        error::check_publickey(&public_key_of_prover.0)?;
+        error::ensure(
+            <Self as ProofOfPossession<E, H, PublicKey<E>>>::verify(self, public_key_of_prover),
+            BlsError::FiatShamirMismatch,
+        )
     }
 }
 
@@ -187,4 +324,79 @@ mod tests {
             "invalid pok of unrelated public key should not verify"
         );
     }
+
+    fn batch_verification<E: crate::EngineBLS>() {
+        use super::BatchableSchnorrPoP;
+        use crate::{ProofOfPossession, ProofOfPossessionGenerator};
+        use ark_ff::Zero;
+
+        let mut keypairs = (0..16)
+            .map(|_| Keypair::<E>::generate(thread_rng()))
+            .collect::<Vec<_>>();
+        let publickeys = keypairs.iter().map(|k| k.public).collect::<Vec<_>>();
+        let mut proofs = keypairs
+            .iter_mut()
+            .map(|k| {
+                ProofOfPossessionGenerator::<E, Sha512, PublicKey<E>, BatchableSchnorrPoP<E>>::generate_pok(k)
+            })
+            .collect::<Vec<_>>();
+        assert!(BatchableSchnorrPoP::verify_batch::<Sha512>(
+            &publickeys,
+            &proofs
+        ));
+        assert!(BatchableSchnorrPoP::<E>::verify_batch::<Sha512>(&[], &[]));
+
+        // Both forms agree, and verify alone.
+        let compact: SchnorrPoP<E> =
+            ProofOfPossessionGenerator::<E, Sha512, PublicKey<E>, SchnorrPoP<E>>::generate_pok(
+                &mut keypairs[0],
+            );
+        let converted = proofs[0].to_schnorr_pop::<Sha512>(&publickeys[0]);
+        assert!(converted.0 == compact.0);
+        assert!(ProofOfPossession::<E, Sha512, PublicKey<E>>::verify(
+            &proofs[0],
+            &publickeys[0]
+        ));
+        assert!(!ProofOfPossession::<E, Sha512, PublicKey<E>>::verify(
+            &proofs[0],
+            &publickeys[1]
+        ));
+
+        // Swapped keys, one bad response and mismatched lengths all fail.
+        let mut swapped = publickeys.clone();
+        swapped.swap(3, 4);
+        assert!(!BatchableSchnorrPoP::verify_batch::<Sha512>(
+            &swapped, &proofs
+        ));
+        assert!(!BatchableSchnorrPoP::verify_batch::<Sha512>(
+            &publickeys[1..],
+            &proofs
+        ));
+        proofs[7].1 += E::Scalar::from(1u64);
+        assert!(!BatchableSchnorrPoP::verify_batch::<Sha512>(
+            &publickeys,
+            &proofs
+        ));
+        assert!(!ProofOfPossession::<E, Sha512, PublicKey<E>>::verify(
+            &proofs[7],
+            &publickeys[7]
+        ));
+
+        // Anyone can prove possession for the identity.
+        let identity = PublicKey::<E>(Zero::zero());
+        let forged = BatchableSchnorrPoP::<E>(E::PublicKeyGroup::zero(), E::Scalar::zero());
+        assert!(ProofOfPossession::<E, Sha512, PublicKey<E>>::verify(
+            &forged, &identity
+        ));
+        assert!(!BatchableSchnorrPoP::verify_batch::<Sha512>(
+            &[identity],
+            &[forged]
+        ));
+    }
+
+    #[test]
+    fn schnorr_bls_pop_batch_verification() {
+        batch_verification::<ZBLS>();
+        batch_verification::<crate::TinyBLS381>();
+    }
 }
@@ -0,0 +1,403 @@
+//! ## Serde support
+//!
+//! We serialize keys, signatures and proofs through their
+//! `SerializableToBytes` encodings, so as hex strings in human
+//! readable formats like JSON, and as raw bytes in binary formats
+//! like bincode.  We deserialize with the same subgroup checks as
+//! `SerializableToBytes::from_bytes`, and reject identity public keys
+//! and zero secret keys too.
+//!
+//! We serialize a `Message` as its context and message bytes, and
+//! recompute its digest when deserializing, so peers cannot pair some
+//! bytes with another digest.  We never serialize proof-of-possession
+//! messages, so peers cannot choose that domain separation tag either.
+
+use alloc::{string::String, vec::Vec};
+use core::{convert::TryFrom, fmt};
+
+use ark_ff::Zero;
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::double::{
+    BatchableDoubleSignature, DoublePublicKey, DoubleSignature, PublicKeyInSignatureGroup,
+};
+use crate::double_pop::{NuggetBLSPoP, NuggetBLSnCPPoP};
+use crate::error::{self, BlsError};
+use crate::schnorr_pop::{BatchableSchnorrPoP, SchnorrPoP};
+use crate::serialize::SerializableToBytes;
+use crate::single::{Keypair, PublicKey, SecretKeyVT, Signature};
+use crate::{EngineBLS, Message, MessageType};
+
+/// Byte strings as hex in human readable formats, and raw bytes otherwise.
+///
+/// Usable with `#[serde(with = "crate::serde_support::bytes")]`.
+pub(crate) mod bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer, T: AsRef<[u8]>>(
+        bytes: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(bytes))
+        } else {
+            serializer.serialize_bytes(bytes.as_ref())
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: TryFrom<Vec<u8>>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let bytes = if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            hex::decode(s.trim_start_matches("0x")).map_err(D::Error::custom)?
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)?
+        };
+        let l = bytes.len();
+        T::try_from(bytes).map_err(|_| D::Error::invalid_length(l, &"the expected length"))
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a byte string")
+        }
+
+        fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(v.to_vec())
+        }
+
+        fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(v)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+            let mut v = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(b) = seq.next_element()? {
+                v.push(b);
+            }
+            Ok(v)
+        }
+    }
+}
+
+fn from_bytes<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: SerializableToBytes,
+{
+    let b: Vec<u8> = bytes::deserialize(deserializer)?;
+    if b.len() != T::SERIALIZED_BYTES_SIZE {
+        return Err(D::Error::invalid_length(b.len(), &"a serialized point"));
+    }
+    T::from_bytes(&b).map_err(D::Error::custom)
+}
+
+fn reject<E: Error>(check: Result<(), BlsError>) -> Result<(), E> {
+    check.map_err(E::custom)
+}
+
+macro_rules! serde_via_bytes {
+    ($wrapper:ident, $x:ident => $validate:expr) => {
+        impl<E: EngineBLS> Serialize for $wrapper<E> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                bytes::serialize(&self.to_bytes(), serializer)
+            }
+        }
+
+        impl<'de, E: EngineBLS> Deserialize<'de> for $wrapper<E> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let $x: $wrapper<E> = from_bytes(deserializer)?;
+                reject::<D::Error>($validate)?;
+                Ok($x)
+            }
+        }
+    };
+}
+
+serde_via_bytes!(PublicKey, pk => pk.validate());
+serde_via_bytes!(Signature, _signature => Ok(()));
+serde_via_bytes!(DoublePublicKey, pk => pk.validate());
+serde_via_bytes!(DoubleSignature, _signature => Ok(()));
+serde_via_bytes!(BatchableDoubleSignature, _signature => Ok(()));
+serde_via_bytes!(PublicKeyInSignatureGroup, pk => error::check_publickey(&pk.0));
+serde_via_bytes!(NuggetBLSPoP, _pop => Ok(()));
+serde_via_bytes!(NuggetBLSnCPPoP, _pop => Ok(()));
+serde_via_bytes!(SchnorrPoP, _pop => Ok(()));
+serde_via_bytes!(BatchableSchnorrPoP, _pop => Ok(()));
+
+/// Wire format of a `Message`, which omits its digest.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Message", deny_unknown_fields)]
+struct MessageParts {
+    #[serde(with = "bytes")]
+    context: Vec<u8>,
+    #[serde(with = "bytes")]
+    message: Vec<u8>,
+    assuming_pop: bool,
+}
+
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let assuming_pop = match self.2 {
+            MessageType::ProofOfPossession => {
+                return Err(serde::ser::Error::custom(
+                    "proof-of-possession messages are not serializable",
+                ))
+            }
+            MessageType::NormalAssumingPoP => true,
+            MessageType::NormalBasic => false,
+        };
+        MessageParts {
+            context: self.1[..self.3].to_vec(),
+            message: self.1[self.3..].to_vec(),
+            assuming_pop,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let parts = MessageParts::deserialize(deserializer)?;
+        Ok(if parts.assuming_pop {
+            Message::new_assuming_pop(&parts.context, &parts.message)
+        } else {
+            Message::new(&parts.context, &parts.message)
+        })
+    }
+}
+
+impl<E: EngineBLS> Serialize for SecretKeyVT<E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut b = self.to_bytes();
+        let r = bytes::serialize(&b, serializer);
+        ::zeroize::Zeroize::zeroize(&mut b[..]); //clear secret key bytes from memory
+        r
+    }
+}
+
+impl<'de, E: EngineBLS> Deserialize<'de> for SecretKeyVT<E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let secret: SecretKeyVT<E> = from_bytes(deserializer)?;
+        if secret.0.is_zero() {
+            return Err(D::Error::custom("zero secret key"));
+        }
+        Ok(secret)
+    }
+}
+
+/// We serialize only the secret key, and recompute the public key.
+impl<E: EngineBLS> Serialize for Keypair<E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.secret.into_vartime().serialize(serializer)
+    }
+}
+
+impl<'de, E: EngineBLS> Deserialize<'de> for Keypair<E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let secret = SecretKeyVT::<E>::deserialize(deserializer)?;
+        Ok(Keypair {
+            secret: secret.into_split_dirty(),
+            public: secret.into_public(),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand::thread_rng;
+    use serde::de::DeserializeOwned;
+    use sha2::Sha256;
+
+    use super::*;
+    use crate::double::DoublePublicKeyScheme;
+    use crate::single::SignedMessage;
+    use crate::{Message, ProofOfPossession, ProofOfPossessionGenerator, TinyBLS381, ZBLS};
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq>(x: &T) {
+        let json = serde_json::to_string(x).unwrap();
+        assert!(serde_json::from_str::<T>(&json).unwrap() == *x);
+        let binary = bincode::serialize(x).unwrap();
+        assert!(bincode::deserialize::<T>(&binary).unwrap() == *x);
+    }
+
+    fn round_trip_bytes<T: Serialize + DeserializeOwned + SerializableToBytes>(x: &T) {
+        let json = serde_json::to_string(x).unwrap();
+        assert_eq!(json, format!("\"{}\"", hex::encode(x.to_bytes())));
+        assert_eq!(
+            serde_json::from_str::<T>(&json).unwrap().to_bytes(),
+            x.to_bytes()
+        );
+        let binary = bincode::serialize(x).unwrap();
+        // bincode prefixes byte strings with their length as a u64.
+        assert_eq!(binary[8..], x.to_bytes()[..]);
+        assert_eq!(
+            bincode::deserialize::<T>(&binary).unwrap().to_bytes(),
+            x.to_bytes()
+        );
+    }
+
+    #[test]
+    fn serde_round_trips() {
+        let mut keypair = Keypair::<ZBLS>::generate(thread_rng());
+        let message = Message::new(b"ctx", b"serde");
+        let signed = keypair.signed_message(&message);
+        round_trip(&keypair.public);
+        round_trip_bytes(&keypair.public);
+        round_trip(&signed.signature);
+        round_trip_bytes(&signed.signature);
+        round_trip_bytes(&keypair.secret.into_vartime());
+        round_trip(&message);
+        round_trip(&Message::new_assuming_pop(b"ctx", b"serde"));
+        round_trip(&Message::new(b"", b"no context"));
+        let bytes = bincode::serialize(&message).unwrap();
+        assert!(bincode::deserialize::<Message>(&bytes).unwrap() == message);
+        round_trip(&signed);
+
+        let json = serde_json::to_string(&keypair).unwrap();
+        let restored: Keypair<ZBLS> = serde_json::from_str(&json).unwrap();
+        assert!(restored.public == keypair.public);
+        assert!(restored.into_vartime().secret.0 == keypair.into_vartime().secret.0);
+        let binary = bincode::serialize(&keypair).unwrap();
+        let restored: Keypair<ZBLS> = bincode::deserialize(&binary).unwrap();
+        assert!(restored.public == keypair.public);
+
+        let mut keypair = Keypair::<TinyBLS381>::generate(thread_rng());
+        let double: DoublePublicKey<TinyBLS381> =
+            DoublePublicKeyScheme::into_double_public_key(&keypair);
+        round_trip_bytes(&double);
+        let publickey_in_signature_group: PublicKeyInSignatureGroup<TinyBLS381> =
+            DoublePublicKeyScheme::into_public_key_in_signature_group(&keypair);
+        round_trip_bytes(&publickey_in_signature_group);
+        let signature: DoubleSignature<TinyBLS381> =
+            DoublePublicKeyScheme::sign(&mut keypair, &message);
+        round_trip_bytes(&signature);
+        let signature: BatchableDoubleSignature<TinyBLS381> =
+            DoublePublicKeyScheme::sign_batchable(&mut keypair, &message);
+        round_trip_bytes(&signature);
+
+        let pop: NuggetBLSPoP<TinyBLS381> = ProofOfPossessionGenerator::<
+            TinyBLS381,
+            Sha256,
+            DoublePublicKey<TinyBLS381>,
+            NuggetBLSPoP<TinyBLS381>,
+        >::generate_pok(&mut keypair);
+        round_trip_bytes(&pop);
+        let pop: NuggetBLSnCPPoP<TinyBLS381> = ProofOfPossessionGenerator::<
+            TinyBLS381,
+            Sha256,
+            DoublePublicKey<TinyBLS381>,
+            NuggetBLSnCPPoP<TinyBLS381>,
+        >::generate_pok(&mut keypair);
+        round_trip_bytes(&pop);
+        let pop: SchnorrPoP<TinyBLS381> = ProofOfPossessionGenerator::<
+            TinyBLS381,
+            Sha256,
+            PublicKey<TinyBLS381>,
+            SchnorrPoP<TinyBLS381>,
+        >::generate_pok(&mut keypair);
+        round_trip_bytes(&pop);
+        let restored: SchnorrPoP<TinyBLS381> =
+            bincode::deserialize(&bincode::serialize(&pop).unwrap()).unwrap();
+        assert!(ProofOfPossession::<TinyBLS381, Sha256, _>::verify(
+            &restored,
+            &keypair.public
+        ));
+        let pop: BatchableSchnorrPoP<TinyBLS381> = ProofOfPossessionGenerator::<
+            TinyBLS381,
+            Sha256,
+            PublicKey<TinyBLS381>,
+            BatchableSchnorrPoP<TinyBLS381>,
+        >::generate_pok(&mut keypair);
+        round_trip_bytes(&pop);
+    }
+
+    #[test]
+    fn serde_rejects_invalid_inputs() {
+        let identity = PublicKey::<ZBLS>(Zero::zero());
+        let json = serde_json::to_string(&identity).unwrap();
+        assert!(serde_json::from_str::<PublicKey<ZBLS>>(&json).is_err());
+        let zero = SecretKeyVT::<ZBLS>(Zero::zero());
+        assert!(
+            serde_json::from_str::<SecretKeyVT<ZBLS>>(&serde_json::to_string(&zero).unwrap())
+                .is_err()
+        );
+        assert!(
+            serde_json::from_str::<Keypair<ZBLS>>(&serde_json::to_string(&zero).unwrap()).is_err()
+        );
+
+        let keypair = Keypair::<ZBLS>::generate(thread_rng());
+        let mut b = keypair.public.to_bytes();
+        assert!(
+            serde_json::from_str::<PublicKey<ZBLS>>(&format!("\"{}\"", hex::encode(&b[1..])))
+                .is_err()
+        );
+        assert!(serde_json::from_str::<PublicKey<ZBLS>>("\"not hex\"").is_err());
+        // Flipped bits leave the curve or the subgroup.
+        b[5] ^= 0xff;
+        assert!(
+            serde_json::from_str::<PublicKey<ZBLS>>(&format!("\"{}\"", hex::encode(&b))).is_err()
+        );
+        let signed = SignedMessage {
+            message: Message::new(b"ctx", b"serde"),
+            publickey: identity,
+            signature: Signature(Zero::zero()),
+        };
+        let json = serde_json::to_string(&signed).unwrap();
+        assert!(serde_json::from_str::<SignedMessage<ZBLS>>(&json).is_err());
+    }
+
+    #[test]
+    fn serde_recomputes_message_digests() {
+        let message = Message::new(b"ctx", b"serde");
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                "{{\"context\":\"{}\",\"message\":\"{}\",\"assuming_pop\":false}}",
+                hex::encode(b"ctx"),
+                hex::encode(b"serde")
+            )
+        );
+
+        // A tampered digest is rejected, rather than trusted.
+        let tampered = format!(
+            "{{\"digest\":\"{}\",\"context\":\"{}\",\"message\":\"{}\",\"assuming_pop\":false}}",
+            hex::encode([7u8; 32]),
+            hex::encode(b"ctx"),
+            hex::encode(b"serde")
+        );
+        assert!(serde_json::from_str::<Message>(&tampered).is_err());
+
+        // Moving bytes between context and message changes the digest,
+        // so distinct encodings never share a digest.
+        let moved = format!(
+            "{{\"context\":\"{}\",\"message\":\"{}\",\"assuming_pop\":false}}",
+            hex::encode(b"ctxs"),
+            hex::encode(b"erde")
+        );
+        let moved: Message = serde_json::from_str(&moved).unwrap();
+        assert!(moved == Message::new(b"ctxs", b"erde"));
+        assert!(moved.0 != message.0);
+
+        // Peers cannot choose the proof-of-possession domain.
+        assert!(serde_json::to_string(&Message::new_pop_message(b"ctx", b"serde")).is_err());
+        let kind = json.replace("false", "\"ProofOfPossession\"");
+        assert!(serde_json::from_str::<Message>(&kind).is_err());
+
+        let mut keypair = Keypair::<ZBLS>::generate(thread_rng());
+        let signed = keypair.signed_message(&message);
+        let restored: SignedMessage<ZBLS> =
+            serde_json::from_str(&serde_json::to_string(&signed).unwrap()).unwrap();
+        assert!(restored.message.0 == message.0);
+        assert!(restored
+            .signature
+            .verify(&restored.message, &restored.publickey));
+    }
+}
@@ -1,4 +1,6 @@
 use alloc::{vec, vec::Vec};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, Field, PrimeField};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
 
 /// Serialization code that is used by multiple modules.
@@ -12,10 +14,166 @@ pub trait SerializableToBytes: CanonicalSerialize + CanonicalDeserialize {
         self.serialize_compressed(&mut serialized_representation[..])
             .unwrap();
 
-        return serialized_representation;
+        serialized_representation
     }
 
     fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
         Self::deserialize_compressed(bytes)
     }
 }
+
+/// Byte encodings supported by `SerializableWithEncoding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// arkworks' `serialize_compressed` format, as produced by `SerializableToBytes::to_bytes`.
+    Arkworks,
+    /// The standard ZCash encoding used by eth2, Chia, Filecoin and blst,
+    /// so big-endian compressed points whose most significant bits carry
+    /// the compression, infinity and sign flags, and big-endian scalars.
+    ///
+    /// Arkworks already uses this format for BLS12-381 points, but not
+    /// for BLS12-377 points or for any secret keys.
+    ZCash,
+}
+
+/// Keys and signatures which also support the standard ZCash encoding.
+pub trait SerializableWithEncoding: SerializableToBytes {
+    fn to_zcash_bytes(&self) -> Vec<u8>;
+
+    /// Decode from the ZCash encoding, rejecting non-canonical encodings,
+    /// points outside the prime order subgroup and zero secret keys.
+    fn from_zcash_bytes(bytes: &[u8]) -> Result<Self, SerializationError>;
+
+    fn to_bytes_with_encoding(&self, encoding: Encoding) -> Vec<u8> {
+        match encoding {
+            Encoding::Arkworks => self.to_bytes(),
+            Encoding::ZCash => self.to_zcash_bytes(),
+        }
+    }
+
+    fn from_bytes_with_encoding(
+        bytes: &[u8],
+        encoding: Encoding,
+    ) -> Result<Self, SerializationError> {
+        match encoding {
+            Encoding::Arkworks => Self::from_bytes(bytes),
+            Encoding::ZCash => Self::from_zcash_bytes(bytes),
+        }
+    }
+}
+
+const ZCASH_COMPRESSION_FLAG: u8 = 1 << 7;
+const ZCASH_INFINITY_FLAG: u8 = 1 << 6;
+const ZCASH_SIGN_FLAG: u8 = 1 << 5;
+const ZCASH_FLAGS: u8 = ZCASH_COMPRESSION_FLAG | ZCASH_INFINITY_FLAG | ZCASH_SIGN_FLAG;
+
+fn prime_field_byte_size<F: PrimeField>() -> usize {
+    (F::MODULUS_BIT_SIZE as usize).div_ceil(8)
+}
+
+/// Size of a compressed point in the ZCash encoding.
+pub(crate) fn zcash_point_size<G: CurveGroup>() -> usize {
+    prime_field_byte_size::<<G::BaseField as Field>::BasePrimeField>()
+        * G::BaseField::extension_degree() as usize
+}
+
+fn prime_field_to_be_bytes<F: PrimeField>(x: &F, bytes: &mut [u8]) {
+    let be = x.into_bigint().to_bytes_be();
+    bytes.copy_from_slice(&be[be.len() - bytes.len()..]);
+}
+
+fn prime_field_from_be_bytes<F: PrimeField>(bytes: &[u8]) -> Result<F, SerializationError> {
+    let x = F::from_be_bytes_mod_order(bytes);
+    let mut canonical = vec![0; bytes.len()];
+    prime_field_to_be_bytes(&x, &mut canonical);
+    if canonical != bytes {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(x)
+}
+
+/// Compressed ZCash encoding of a point, which places the coefficients of
+/// extension field elements in descending order, i.e. `x.c1 || x.c0`.
+pub(crate) fn point_to_zcash_bytes<G: CurveGroup>(point: &G) -> Vec<u8> {
+    let mut bytes = vec![0; zcash_point_size::<G>()];
+    match point.into_affine().xy() {
+        None => bytes[0] = ZCASH_COMPRESSION_FLAG | ZCASH_INFINITY_FLAG,
+        Some((x, y)) => {
+            let field_size = prime_field_byte_size::<<G::BaseField as Field>::BasePrimeField>();
+            let coefficients = x.to_base_prime_field_elements().collect::<Vec<_>>();
+            for (chunk, c) in bytes.chunks_mut(field_size).zip(coefficients.iter().rev()) {
+                prime_field_to_be_bytes(c, chunk);
+            }
+            bytes[0] |= ZCASH_COMPRESSION_FLAG;
+            if y > -y {
+                bytes[0] |= ZCASH_SIGN_FLAG;
+            }
+        }
+    }
+    bytes
+}
+
+/// Decode a compressed ZCash encoded point, checking subgroup membership.
+pub(crate) fn point_from_zcash_bytes<G: CurveGroup>(bytes: &[u8]) -> Result<G, SerializationError> {
+    if bytes.len() != zcash_point_size::<G>() {
+        return Err(SerializationError::InvalidData);
+    }
+    let flags = bytes[0] & ZCASH_FLAGS;
+    if flags & ZCASH_COMPRESSION_FLAG == 0 {
+        return Err(SerializationError::InvalidData);
+    }
+    let mut body = bytes.to_vec();
+    body[0] &= !ZCASH_FLAGS;
+
+    if flags & ZCASH_INFINITY_FLAG != 0 {
+        if flags & ZCASH_SIGN_FLAG != 0 || body.iter().any(|b| *b != 0) {
+            return Err(SerializationError::InvalidData);
+        }
+        return Ok(G::zero());
+    }
+
+    let field_size = prime_field_byte_size::<<G::BaseField as Field>::BasePrimeField>();
+    let coefficients = body
+        .chunks(field_size)
+        .rev()
+        .map(prime_field_from_be_bytes)
+        .collect::<Result<Vec<_>, _>>()?;
+    let x = G::BaseField::from_base_prime_field_elems(coefficients)
+        .ok_or(SerializationError::InvalidData)?;
+
+    // Arkworks recovers `y` from `x` but its sign convention differs
+    // between curves, so we pick the root ourselves.
+    let mut x_bytes = Vec::new();
+    x.serialize_compressed(&mut x_bytes)?;
+    let point = G::Affine::from_random_bytes(&x_bytes).ok_or(SerializationError::InvalidData)?;
+    let (_, y) = point.xy().ok_or(SerializationError::InvalidData)?;
+    let point = if (y > -y) == (flags & ZCASH_SIGN_FLAG != 0) {
+        point.into_group()
+    } else {
+        -point.into_group()
+    };
+
+    if !point.mul_bigint(G::ScalarField::MODULUS).is_zero() {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(point)
+}
+
+/// Big-endian encoding of a secret scalar.
+pub(crate) fn scalar_to_be_bytes<F: PrimeField>(scalar: &F) -> Vec<u8> {
+    let mut bytes = vec![0; prime_field_byte_size::<F>()];
+    prime_field_to_be_bytes(scalar, &mut bytes);
+    bytes
+}
+
+/// Decode a big-endian secret scalar, rejecting zero as the IETF draft does.
+pub(crate) fn scalar_from_be_bytes<F: PrimeField>(bytes: &[u8]) -> Result<F, SerializationError> {
+    if bytes.len() != prime_field_byte_size::<F>() {
+        return Err(SerializationError::InvalidData);
+    }
+    let scalar = prime_field_from_be_bytes::<F>(bytes)?;
+    if scalar.is_zero() {
+        return Err(SerializationError::InvalidData);
+    }
+    Ok(scalar)
+}
@@ -26,7 +26,7 @@
 use alloc::{vec, vec::Vec};
 
 use ark_ff::field_hashers::{DefaultFieldHasher, HashToField};
-use ark_ff::{UniformRand, Zero};
+use ark_ff::{PrimeField, UniformRand, Zero};
 
 use ark_ec::{AffineRepr, CurveGroup};
 
@@ -48,8 +48,17 @@ use digest::Digest;
 
 use core::iter::once;
 
-use crate::serialize::SerializableToBytes;
+use crate::error::{self, BlsError};
+use crate::serialize::{
+    point_from_zcash_bytes, point_to_zcash_bytes, scalar_from_be_bytes, scalar_to_be_bytes,
+    SerializableToBytes, SerializableWithEncoding,
+};
 use crate::{EngineBLS, Message, Signed};
+
+/// Initial salt for `KeyGen` from the BLS signature IETF draft.
+const KEYGEN_SALT: &[u8] = b"BLS-SIG-KEYGEN-SALT-";
+/// Output length `L` for `KeyGen`, or `ceil((3 * ceil(log2(r))) / 16)`.
+const KEYGEN_OKM_LENGTH: u16 = 48;
 // //////////////// SECRETS //////////////// //
 
 /// Secret signing key lacking the side channel protections from
@@ -71,7 +80,33 @@ impl<E: EngineBLS> SecretKeyVT<E> {
 
     pub fn from_seed(seed: &[u8]) -> Self {
         let hasher = <DefaultFieldHasher<Sha256> as HashToField<E::Scalar>>::new(&[]);
-        return SecretKeyVT(hasher.hash_to_field::<1>(seed)[0]);
+        SecretKeyVT(hasher.hash_to_field::<1>(seed)[0])
+    }
+
+    /// Derive a secret key from input keying material `ikm` and
+    /// optional `key_info` by the `KeyGen` procedure of the BLS signature
+    /// IETF draft (version 4 onwards), so keys interoperate with other
+    /// conforming implementations.
+    ///
+    /// Returns `None` if `ikm` is shorter than the required 32 bytes.
+    pub fn keygen(ikm: &[u8], key_info: &[u8]) -> Option<Self> {
+        if ikm.len() < 32 {
+            return None;
+        }
+        let ikm = [ikm, &[0u8]].concat();
+        let info = [key_info, &KEYGEN_OKM_LENGTH.to_be_bytes()].concat();
+        let mut salt = sha2::Sha256::digest(KEYGEN_SALT);
+        loop {
+            let mut okm = [0u8; KEYGEN_OKM_LENGTH as usize];
+            hkdf::Hkdf::<Sha256>::new(Some(&salt[..]), &ikm)
+                .expand(&info, &mut okm)
+                .expect("48 bytes is a valid HKDF-SHA256 output length");
+            let key = E::Scalar::from_be_bytes_mod_order(&okm);
+            if !key.is_zero() {
+                return Some(SecretKeyVT(key));
+            }
+            salt = sha2::Sha256::digest(salt);
+        }
     }
 }
 
@@ -89,7 +124,7 @@ impl<E: EngineBLS> SecretKeyVT<E> {
     /// but does not itself resplit the key.
     pub fn into_split_dirty(&self) -> SecretKey<E> {
         SecretKey {
-            key: [self.0.clone(), E::Scalar::zero()],
+            key: [self.0, E::Scalar::zero()],
             old_unsigned: E::SignatureGroup::zero(),
             old_signed: E::SignatureGroup::zero(),
         }
@@ -160,7 +195,7 @@ impl<E: EngineBLS> SecretKeyVT<E> {
 ///
 /// TODO: Is Pippenger’s algorithm, or another fast MSM algorithm,
 /// secure when used with key splitting?
-
+///
 /// Secret signing key including the side channel protections from
 /// key splitting.
 pub struct SecretKey<E: EngineBLS> {
@@ -172,9 +207,9 @@ pub struct SecretKey<E: EngineBLS> {
 impl<E: EngineBLS> Clone for SecretKey<E> {
     fn clone(&self) -> Self {
         SecretKey {
-            key: self.key.clone(),
-            old_unsigned: self.old_unsigned.clone(),
-            old_signed: self.old_signed.clone(),
+            key: self.key,
+            old_unsigned: self.old_unsigned,
+            old_signed: self.old_signed,
         }
     }
 }
@@ -223,7 +258,7 @@ impl<E: EngineBLS> SecretKey<E> {
     /// Create a representative usable for operations lacking
     /// side channel protections.  
     pub fn into_vartime(&self) -> SecretKeyVT<E> {
-        let mut secret = self.key[0].clone();
+        let mut secret = self.key[0];
         secret += &self.key[1];
         SecretKeyVT(secret)
     }
@@ -250,13 +285,13 @@ impl<E: EngineBLS> SecretKey<E> {
     pub fn sign_once(&mut self, message: &Message) -> Signature<E> {
         let mut z = message.hash_to_signature_curve::<E>();
         z -= &self.old_unsigned;
-        self.old_unsigned = z.clone();
-        let mut t = z.clone();
+        self.old_unsigned = z;
+        let mut t = z;
         t *= self.key[0];
         z *= self.key[1];
         z += &t;
-        let old_signed = self.old_signed.clone();
-        self.old_signed = z.clone();
+        let old_signed = self.old_signed;
+        self.old_signed = z;
         z += &old_signed;
         // s.normalize();   // VRFs are faster if we only normalize once, but no normalize method exists.
         // E::SignatureGroup::batch_normalization(&mut [&mut s]);
@@ -313,7 +348,7 @@ macro_rules! broken_derives {
     ($wrapper:tt) => {
         impl<E: EngineBLS> Clone for $wrapper<E> {
             fn clone(&self) -> Self {
-                $wrapper(self.0)
+                *self
             }
         }
         impl<E: EngineBLS> Copy for $wrapper<E> {}
@@ -381,7 +416,7 @@ where
 {
     fn check(&self) -> Result<(), SerializationError> {
         //TODO probabaly turn into vartime and check that because vartime impl valid
-        match (self.key[1].check(), self.key[2].check()) {
+        match (self.key[0].check(), self.key[1].check()) {
             (Ok(()), Ok(())) => Ok(()),
             _ => Err(SerializationError::InvalidData),
         }
@@ -443,13 +478,53 @@ impl<E: EngineBLS> SerializableToBytes for SecretKey<E> {
     const SERIALIZED_BYTES_SIZE: usize = E::SECRET_KEY_SIZE;
 }
 
+impl<E: EngineBLS> SerializableWithEncoding for Signature<E> {
+    fn to_zcash_bytes(&self) -> Vec<u8> {
+        point_to_zcash_bytes(&self.0)
+    }
+
+    fn from_zcash_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        point_from_zcash_bytes(bytes).map(Signature)
+    }
+}
+
+impl<E: EngineBLS> SerializableWithEncoding for PublicKey<E> {
+    fn to_zcash_bytes(&self) -> Vec<u8> {
+        point_to_zcash_bytes(&self.0)
+    }
+
+    fn from_zcash_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        point_from_zcash_bytes(bytes).map(PublicKey)
+    }
+}
+
+/// Secret keys use the big-endian `I2OSP(SK, 32)` form of the IETF draft.
+impl<E: EngineBLS> SerializableWithEncoding for SecretKeyVT<E> {
+    fn to_zcash_bytes(&self) -> Vec<u8> {
+        scalar_to_be_bytes(&self.0)
+    }
+
+    fn from_zcash_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        scalar_from_be_bytes(bytes).map(SecretKeyVT)
+    }
+}
+
+impl<E: EngineBLS> SerializableWithEncoding for SecretKey<E> {
+    fn to_zcash_bytes(&self) -> Vec<u8> {
+        self.into_vartime().to_zcash_bytes()
+    }
+
+    fn from_zcash_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
+        SecretKeyVT::from_zcash_bytes(bytes).map(|secret| secret.into_split_dirty())
+    }
+}
+
 /// because SecretKey is not canonically serializable and that we need to convert
 /// it to vartime first we need to manually re-implement this trait for secret keys
 //, CanonicalSerialize, CanonicalDeserialize)]
 /// Detached BLS Signature
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct Signature<E: EngineBLS>(pub E::SignatureGroup);
-// TODO: Serialization
 
 broken_derives!(Signature); // Actually the derive works for this one, not sure why.
 
@@ -471,12 +546,26 @@ impl<E: EngineBLS> Signature<E> {
         // TODO: Compare benchmarks on variants
         E::verify_prepared(signature, &[(publickey, message)])
     }
+
+    /// Reject the identity and points outside the prime order subgroup.
+    ///
+    /// We deserialize with these checks anyway, except the identity
+    /// check, but signatures built in other ways should be validated.
+    pub fn validate(&self) -> Result<(), BlsError> {
+        error::check_signature(&self.0)
+    }
+
+    /// Verify a single BLS signature, explaining any failure.
+    pub fn try_verify(&self, message: &Message, publickey: &PublicKey<E>) -> Result<(), BlsError> {
+        error::check_publickey(&publickey.0)?;
+        error::check_subgroup(&self.0)?;
+        error::ensure(self.verify(message, publickey), BlsError::PairingMismatch)
+    }
 }
 
 /// BLS Public Key
 #[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct PublicKey<E: EngineBLS>(pub E::PublicKeyGroup);
-// TODO: Serialization
 
 // impl<E: EngineBLS> PublicKey<E> where E: DeserializePublicKey {
 //     pub fn i_have_checked_this_proof_of_possession(self) -> PublicKey<PoP<E>> {
@@ -490,7 +579,17 @@ broken_derives!(PublicKey);
 impl<E: EngineBLS> PublicKey<E> {
     //const DESCRIPTION : &'static str = "A BLS signature";
     pub fn verify(&self, message: &Message, signature: &Signature<E>) -> bool {
-        signature.verify(&message, self)
+        signature.verify(message, self)
+    }
+
+    pub fn try_verify(&self, message: &Message, signature: &Signature<E>) -> Result<(), BlsError> {
+        signature.try_verify(message, self)
+    }
+
+    /// Reject the identity and points outside the prime order subgroup,
+    /// as in KeyValidate from the IETF BLS signature draft.
+    pub fn validate(&self) -> Result<(), BlsError> {
+        error::check_publickey(&self.0)
     }
 }
 
@@ -510,7 +609,7 @@ impl<E: EngineBLS> Clone for KeypairVT<E> {
     fn clone(&self) -> Self {
         KeypairVT {
             secret: self.secret.clone(),
-            public: self.public.clone(),
+            public: self.public,
         }
     }
 }
@@ -540,10 +639,10 @@ impl<E: EngineBLS> KeypairVT<E> {
 
     /// Sign a message creating a `SignedMessage` using a user supplied CSPRNG for the key splitting.
     pub fn signed_message(&self, message: &Message) -> SignedMessage<E> {
-        let signature = self.secret.sign(&message);
+        let signature = self.secret.sign(message);
         SignedMessage {
             message: message.clone(),
-            publickey: self.public.clone(),
+            publickey: self.public,
             signature,
         }
     }
@@ -565,12 +664,11 @@ impl<E: EngineBLS> Clone for Keypair<E> {
     fn clone(&self) -> Self {
         Keypair {
             secret: self.secret.clone(),
-            public: self.public.clone(),
+            public: self.public,
         }
     }
 }
 
-// TODO: Serialization
 impl<E: EngineBLS> Keypair<E> {
     /// Generate a `Keypair`
     pub fn generate<R: Rng>(rng: R) -> Self {
@@ -591,7 +689,7 @@ impl<E: EngineBLS> Keypair<E> {
 
     /// Sign a message creating a `Signature` using a user supplied CSPRNG for the key splitting.
     pub fn sign_with_rng<R: Rng>(&mut self, message: &Message, rng: R) -> Signature<E> {
-        self.secret.sign(&message, rng)
+        self.secret.sign(message, rng)
     }
 
     /// Sign a message using a Seedabale RNG created from user supplied seed
@@ -628,7 +726,7 @@ impl<E: EngineBLS> Keypair<E> {
 
     /// Create a `SignedMessage` using the default `ThreadRng`.
     pub fn signed_message(&mut self, message: &Message) -> SignedMessage<E> {
-        let signature = self.sign(&message);
+        let signature = self.sign(message);
         SignedMessage {
             message: message.clone(),
             publickey: self.public,
@@ -641,12 +739,13 @@ impl<E: EngineBLS> Keypair<E> {
 ///
 ///
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct SignedMessage<E: EngineBLS> {
     pub message: Message,
     pub publickey: PublicKey<E>,
     pub signature: Signature<E>,
 }
-// TODO: Serialization
 
 // borrow_wrapper!(Signature,SignatureGroup,signature);
 // borrow_wrapper!(PublicKey,PublicKeyGroup,publickey);
@@ -661,7 +760,7 @@ impl<E: EngineBLS> PartialEq<Self> for SignedMessage<E> {
 
 impl<E: EngineBLS> Eq for SignedMessage<E> {}
 
-impl<'a, E: EngineBLS> Signed for &'a SignedMessage<E> {
+impl<E: EngineBLS> Signed for &SignedMessage<E> {
     type E = E;
 
     type M = Message;
@@ -680,6 +779,10 @@ impl<'a, E: EngineBLS> Signed for &'a SignedMessage<E> {
     fn verify(self) -> bool {
         self.signature.verify(&self.message, &self.publickey)
     }
+
+    fn try_verify(self) -> Result<(), BlsError> {
+        self.signature.try_verify(&self.message, &self.publickey)
+    }
 }
 
 impl<E: EngineBLS> SignedMessage<E> {
@@ -766,9 +869,9 @@ mod tests {
     use ark_ec::pairing::Pairing as PairingEngine;
 
     use super::*;
+    use crate::serialize::Encoding;
     use crate::{CurveExtraConfig, TinyBLS, UsualBLS};
 
-    use core::convert::TryInto;
     use hex_literal::hex;
 
     fn bls_engine_serialization_test<
@@ -836,17 +939,13 @@ mod tests {
         <P as Bls12Config>::G2Config: WBConfig,
         WBMap<<P as Bls12Config>::G2Config>: MapToCurve<<E as PairingEngine>::G2>,
     {
-        match SecretKey::<UsualBLS<E, P>>::from_bytes(
-            random_seed
-                .try_into()
-                .expect("the size of the seed be 32 Bytes."),
-        ) {
-            Ok(_) => assert!(
-                false,
-                "random seed should not be canonically deserializable to a secret key."
-            ),
+        assert_eq!(random_seed.len(), 32, "the size of the seed be 32 Bytes.");
+        match SecretKey::<UsualBLS<E, P>>::from_bytes(random_seed) {
+            Ok(_) => {
+                panic!("random seed should not be canonically deserializable to a secret key.")
+            }
             Err(SerializationError::InvalidData) => (),
-            _ => assert!(false, "unexpected deserialization error."),
+            _ => panic!("unexpected deserialization error."),
         }
     }
 
@@ -992,4 +1091,309 @@ mod tests {
             random_seed.as_slice(),
         );
     }
+
+    fn zcash_encoding_roundtrip<E: EngineBLS>() {
+        let keypair = KeypairVT::<E>::generate(thread_rng());
+        let signature = keypair.sign(&Message::new(b"ctx", b"test message"));
+
+        let bytes = keypair.public.to_bytes_with_encoding(Encoding::ZCash);
+        assert_eq!(bytes.len(), E::PUBLICKEY_SERIALIZED_SIZE);
+        assert_eq!(bytes[0] & 0x80, 0x80, "compression flag must be set");
+        assert!(
+            PublicKey::<E>::from_bytes_with_encoding(&bytes, Encoding::ZCash).unwrap()
+                == keypair.public
+        );
+        let bytes = signature.to_bytes_with_encoding(Encoding::ZCash);
+        assert_eq!(bytes.len(), E::SIGNATURE_SERIALIZED_SIZE);
+        assert!(Signature::<E>::from_zcash_bytes(&bytes).unwrap() == signature);
+
+        let bytes = keypair.secret.to_zcash_bytes();
+        let mut little_endian = keypair.secret.to_bytes();
+        little_endian.reverse();
+        assert_eq!(bytes, little_endian);
+        assert_eq!(
+            SecretKeyVT::<E>::from_zcash_bytes(&bytes).unwrap().0,
+            keypair.secret.0
+        );
+        let split = keypair.secret.into_split(thread_rng());
+        assert_eq!(split.to_zcash_bytes(), bytes);
+        assert!(
+            SecretKey::<E>::from_zcash_bytes(&bytes)
+                .unwrap()
+                .into_public()
+                == keypair.public
+        );
+        assert_eq!(
+            SecretKeyVT::<E>::from_bytes_with_encoding(
+                &keypair.secret.to_bytes_with_encoding(Encoding::Arkworks),
+                Encoding::Arkworks
+            )
+            .unwrap()
+            .0,
+            keypair.secret.0
+        );
+
+        let identity = PublicKey::<E>(E::PublicKeyGroup::zero());
+        let bytes = identity.to_zcash_bytes();
+        assert_eq!(bytes[0], 0xc0);
+        assert!(bytes[1..].iter().all(|b| *b == 0));
+        assert!(PublicKey::<E>::from_zcash_bytes(&bytes).unwrap() == identity);
+
+        let mut bytes = keypair.public.to_zcash_bytes();
+        bytes[0] &= 0x7f;
+        assert!(
+            PublicKey::<E>::from_zcash_bytes(&bytes).is_err(),
+            "uncompressed flag accepted"
+        );
+        let mut bytes = signature.to_zcash_bytes();
+        bytes[0] |= 0x40;
+        assert!(
+            Signature::<E>::from_zcash_bytes(&bytes).is_err(),
+            "infinity flag on a non identity point accepted"
+        );
+        assert!(Signature::<E>::from_zcash_bytes(&bytes[1..]).is_err());
+        let mut bytes = vec![0xffu8; E::PUBLICKEY_SERIALIZED_SIZE];
+        bytes[0] = 0x9f;
+        assert!(
+            PublicKey::<E>::from_zcash_bytes(&bytes).is_err(),
+            "non canonical coordinate accepted"
+        );
+        assert!(SecretKeyVT::<E>::from_zcash_bytes(&[0u8; 32]).is_err());
+        assert!(SecretKeyVT::<E>::from_zcash_bytes(&[0xffu8; 32]).is_err());
+    }
+
+    #[test]
+    fn zcash_encoding_for_all_engines() {
+        zcash_encoding_roundtrip::<crate::ZBLS>();
+        zcash_encoding_roundtrip::<crate::BLS377>();
+        zcash_encoding_roundtrip::<crate::TinyBLS381>();
+        zcash_encoding_roundtrip::<crate::TinyBLS377>();
+    }
+
+    /// Arkworks already encodes BLS12-381 points in the ZCash format.
+    #[test]
+    fn zcash_encoding_matches_arkworks_on_bls12_381() {
+        let keypair = KeypairVT::<crate::ZBLS>::generate(thread_rng());
+        let signature = keypair.sign(&Message::new(b"ctx", b"test message"));
+        assert_eq!(keypair.public.to_zcash_bytes(), keypair.public.to_bytes());
+        assert_eq!(signature.to_zcash_bytes(), signature.to_bytes());
+
+        let keypair = KeypairVT::<crate::TinyBLS381>::generate(thread_rng());
+        let signature = keypair.sign(&Message::new(b"ctx", b"test message"));
+        assert_eq!(keypair.public.to_zcash_bytes(), keypair.public.to_bytes());
+        assert_eq!(signature.to_zcash_bytes(), signature.to_bytes());
+    }
+
+    /// Interoperability vectors for the IETF BLS signature ciphersuites, taken
+    /// from RFC 9380, the Ethereum consensus specs, and blst for the G1 variants.
+    mod ietf {
+        use super::*;
+        use crate::{TinyBLS381, ZBLS};
+
+        fn secret_key<E: EngineBLS>(be_bytes: &[u8]) -> SecretKeyVT<E> {
+            let secret = SecretKeyVT::<E>::from_zcash_bytes(be_bytes).unwrap();
+            assert_eq!(secret.to_zcash_bytes(), be_bytes);
+            secret
+        }
+
+        fn check_signature<E: EngineBLS>(
+            secret: &SecretKeyVT<E>,
+            message: &Message,
+            publickey: &[u8],
+            signature: &[u8],
+        ) {
+            let public = secret.into_public();
+            assert_eq!(public.to_bytes(), publickey);
+            let sig = secret.sign(message);
+            assert_eq!(sig.to_bytes(), signature);
+
+            assert_eq!(public.to_zcash_bytes(), publickey);
+            assert_eq!(sig.to_zcash_bytes(), signature);
+
+            let public = PublicKey::<E>::from_zcash_bytes(publickey).unwrap();
+            assert!(public == PublicKey::<E>::from_bytes(publickey).unwrap());
+            let sig = Signature::<E>::from_bytes(signature).unwrap();
+            assert!(sig.verify(message, &public));
+            assert!(!sig.verify(&Message::new(b"", b"wrong message"), &public));
+        }
+
+        const ETH2_SECRET_KEYS: [[u8; 32]; 3] = [
+            hex!("263dbd792f5b1be47ed85f8938c0f29586af0d3ac7b977f21c278fe1462040e3"),
+            hex!("47b8192d77bf871b62e87859d653922725724a5c031afeabc60bcef5ff665138"),
+            hex!("328388aff0d4a5b7dc9205abd374e7e98f3cd9f3418edb4eafda5fb16473d216"),
+        ];
+
+        #[test]
+        fn keygen() {
+            let ikm = b"hello world (it's a secret!) very secret stuff";
+            let secret = SecretKeyVT::<ZBLS>::keygen(ikm, b"").unwrap();
+            assert_eq!(
+                secret.0,
+                secret_key::<ZBLS>(&hex!(
+                    "4a73baed5cb75109cc9e063a0ea0009c2d80f7b7f5ea6cc48a223b0f9e257f7d"
+                ))
+                .0
+            );
+            assert!(SecretKeyVT::<ZBLS>::keygen(&ikm[..31], b"").is_none());
+        }
+
+        /// RFC 9380 `BLS12381G1_XMD:SHA-256_SSWU_RO_` and `BLS12381G2_XMD:SHA-256_SSWU_RO_`
+        /// vectors for `msg = "abc"`, compared in uncompressed form.
+        #[test]
+        fn hash_to_curve() {
+            let point = TinyBLS381::hash_to_signature_curve(
+                &b"abc"[..],
+                b"QUUX-V01-CS02-with-BLS12381G1_XMD:SHA-256_SSWU_RO_",
+            );
+            let mut bytes = Vec::new();
+            point.serialize_uncompressed(&mut bytes).unwrap();
+            assert_eq!(
+                bytes,
+                [
+                    &hex!("03567bc5ef9c690c2ab2ecdf6a96ef1c139cc0b2f284dca0a9a7943388a49a3aee664ba5379a7655d3c68900be2f6903")[..],
+                    &hex!("0b9c15f3fe6e5cf4211f346271d7b01c8f3b28be689c8429c85b67af215533311f0b8dfaaa154fa6b88176c229f2885d")[..],
+                ]
+                .concat()
+            );
+
+            let point = ZBLS::hash_to_signature_curve(
+                &b"abc"[..],
+                b"QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_",
+            );
+            let mut bytes = Vec::new();
+            point.serialize_uncompressed(&mut bytes).unwrap();
+            assert_eq!(
+                bytes,
+                [
+                    &hex!("139cddbccdc5e91b9623efd38c49f81a6f83f175e80b06fc374de9eb4b41dfe4ca3a230ed250fbe3a2acf73a41177fd8")[..],
+                    &hex!("02c2d18e033b960562aae3cab37a27ce00d80ccd5ba4b7fe0e7a210245129dbec7780ccc7954725f4168aff2787776e6")[..],
+                    &hex!("00aa65dae3c8d732d10ecd2c50f8a1baf3001578f71c694e03866e9f3d49ac1e1ce70dd94a733534f106d4cec0eddd16")[..],
+                    &hex!("1787327b68159716a37440985269cf584bcb1e621d3a7202be6ea05c4cfe244aeb197642555a0645fb87bf7466b2ba48")[..],
+                ]
+                .concat()
+            );
+        }
+
+        #[test]
+        fn message_uses_ciphersuite_as_dst() {
+            let msg = hex!("d76df8");
+            assert_eq!(
+                Message::new(b"", &msg).hash_to_signature_curve::<ZBLS>(),
+                ZBLS::hash_to_signature_curve(
+                    &msg[..],
+                    b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_"
+                )
+            );
+            assert_eq!(
+                Message::new_assuming_pop(b"ctx", &msg).hash_to_signature_curve::<TinyBLS381>(),
+                TinyBLS381::hash_to_signature_curve(
+                    &[&b"ctx"[..], &msg[..]].concat()[..],
+                    b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_"
+                )
+            );
+            assert_eq!(
+                Message::new_pop_message(b"", &msg).hash_to_signature_curve::<ZBLS>(),
+                ZBLS::hash_to_signature_curve(
+                    &msg[..],
+                    b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_"
+                )
+            );
+        }
+
+        #[test]
+        fn basic_scheme_g2_signatures() {
+            let secret = secret_key::<ZBLS>(&ETH2_SECRET_KEYS[0]);
+            let publickey = hex!("a491d1b0ecd9bb917989f0e74f0dea0422eac4a873e5e2644f368dffb9a6e20fd6e10c1b77654d067c0618f6e5a7f79a");
+            check_signature(
+                &secret,
+                &Message::new(b"", &[0xabu8; 32]),
+                &publickey,
+                &hex!("a56561556b8e51e70f037aadc9939ab6079928f65e809a4e415089c100e644c8e88e889364d4a4db5821471ce271d86a1958895057637cdb4f80bf1083cc251cc2fd221db227b2a00f757d0b029d1f300aaf2297d5381af389d4991999e110c0"),
+            );
+            check_signature(
+                &secret,
+                &Message::new(b"", b""),
+                &publickey,
+                &hex!("b02c82008ed0b01c4a1d7b2f32d4a3f5ccf91b330a68ca2da591357c97001d636b6ed18383bf4d83ac58222f2d4ad72c0119274de098126ff3b18a4590c5540e350ce2714ec50ce1074220fd9c1048ec7a00499736c28c8a9faa32fb3476eccc"),
+            );
+        }
+
+        /// Signer index, message, public key and signature.
+        type PopVector = (usize, [u8; 32], [u8; 48], [u8; 96]);
+
+        #[test]
+        fn pop_scheme_g2_signatures() {
+            let vectors: [PopVector; 4] = [
+                (
+                    0,
+                    [0u8; 32],
+                    hex!("a491d1b0ecd9bb917989f0e74f0dea0422eac4a873e5e2644f368dffb9a6e20fd6e10c1b77654d067c0618f6e5a7f79a"),
+                    hex!("b6ed936746e01f8ecf281f020953fbf1f01debd5657c4a383940b020b26507f6076334f91e2366c96e9ab279fb5158090352ea1c5b0c9274504f4f0e7053af24802e51e4568d164fe986834f41e55c8e850ce1f98458c0cfc9ab380b55285a55"),
+                ),
+                (
+                    0,
+                    [0x56u8; 32],
+                    hex!("a491d1b0ecd9bb917989f0e74f0dea0422eac4a873e5e2644f368dffb9a6e20fd6e10c1b77654d067c0618f6e5a7f79a"),
+                    hex!("882730e5d03f6b42c3abc26d3372625034e1d871b65a8a6b900a56dae22da98abbe1b68f85e49fe7652a55ec3d0591c20767677e33e5cbb1207315c41a9ac03be39c2e7668edc043d6cb1d9fd93033caa8a1c5b0e84bedaeb6c64972503a43eb"),
+                ),
+                (
+                    1,
+                    [0xabu8; 32],
+                    hex!("b301803f8b5ac4a1133581fc676dfedc60d891dd5fa99028805e5ea5b08d3491af75d0707adab3b70c6a6a580217bf81"),
+                    hex!("9674e2228034527f4c083206032b020310face156d4a4685e2fcaec2f6f3665aa635d90347b6ce124eb879266b1e801d185de36a0a289b85e9039662634f2eea1e02e670bc7ab849d006a70b2f93b84597558a05b879c8d445f387a5d5b653df"),
+                ),
+                (
+                    2,
+                    [0x00u8; 32],
+                    hex!("b53d21a4cfd562c469cc81514d4ce5a6b577d8403d32a394dc265dd190b47fa9f829fdd7963afdf972e5e77854051f6f"),
+                    hex!("948a7cb99f76d616c2c564ce9bf4a519f1bea6b0a624a02276443c245854219fabb8d4ce061d255af5330b078d5380681751aa7053da2c98bae898edc218c75f07e24d8802a17cd1f6833b71e58f5eb5b94208b4d0bb3848cecb075ea21be115"),
+                ),
+            ];
+            for (i, msg, publickey, signature) in vectors.iter() {
+                let secret = secret_key::<ZBLS>(&ETH2_SECRET_KEYS[*i]);
+                let message = Message::new_assuming_pop(b"", msg);
+                check_signature(&secret, &message, publickey, signature);
+                // The basic scheme uses a different domain separation tag.
+                assert_ne!(secret.sign(&Message::new(b"", msg)).to_bytes(), signature);
+            }
+        }
+
+        #[test]
+        fn g1_signatures() {
+            let secret = secret_key::<TinyBLS381>(&ETH2_SECRET_KEYS[0]);
+            let publickey = hex!("ac400b70f6f8cd35648f5c126cce5417f3be4d8eefbd42ceb4286a14df7e03135313fe5845e3a575faab3e8b949d248814856c22d8cdb2967c720e963eedc999e738373b14172f06fc915769d3cc5ab7ae0a1b9c38f48b5585fb09d4bd2733bb");
+            let msg = [0xabu8; 32];
+            check_signature(
+                &secret,
+                &Message::new(b"", &msg),
+                &publickey,
+                &hex!("ac20aa7325d56e19b8d0e6cef3e90fde302cf185824babad323c1187d942a4a990ced9173de091280d64bb6e8c19ab39"),
+            );
+            check_signature(
+                &secret,
+                &Message::new_assuming_pop(b"", &msg),
+                &publickey,
+                &hex!("945b268e7fbc953e95f8f1d5592683f5494e7d24d7e6352b7225617d8b9c595ee0d9e4f1dfabe5c0b8ce6fdefbe90610"),
+            );
+        }
+
+        #[test]
+        fn pop_prove() {
+            let secret = secret_key::<ZBLS>(&ETH2_SECRET_KEYS[0]);
+            let publickey = secret.into_public().to_bytes();
+            let proof = secret.sign(&Message::new_pop_message(b"", &publickey));
+            assert_eq!(
+                proof.to_bytes(),
+                hex!("b803eb0ed93ea10224a73b6b9c725796be9f5fefd215ef7a5b97234cc956cf6870db6127b7e4d824ec62276078e787db05584ce1adbf076bc0808ca0f15b73d59060254b25393d95dfc7abe3cda566842aaedf50bbb062aae1bbb6ef3b1f77e1")
+            );
+
+            let secret = secret_key::<TinyBLS381>(&ETH2_SECRET_KEYS[0]);
+            let publickey = secret.into_public().to_bytes();
+            let proof = secret.sign(&Message::new_pop_message(b"", &publickey));
+            assert_eq!(
+                proof.to_bytes(),
+                hex!("85cd8b8b8e2677c1e6e861e6c720d08ff986bc39862de8f975fbb287f34a550402277ab6fd5fad7ae0d4f57a6ba80e19")
+            );
+        }
+    }
 }
@@ -34,9 +34,13 @@
 // Aside about proof-of-possession in the DLOG setting
 // https://twitter.com/btcVeg/status/1085490561082183681
 
+use core::borrow::Borrow;
+use core::iter::once;
+
 use ark_ff::Zero;
 
 use super::verifiers::{
+    try_verify_simple, try_verify_strict, try_verify_using_aggregated_auxiliary_public_keys,
     verify_using_aggregated_auxiliary_public_keys, verify_with_distinct_messages,
 };
 use super::*;
@@ -60,6 +64,8 @@ use digest::{DynDigest, FixedOutputReset};
 /// Distinct message aggregation is always optimal for checking
 /// proofs-of-possession.  Please see the module level doumentation
 /// for additional discussion and notes on security.
+/// We therefore recommend `VerifiedSignatureAggregator`, which accepts
+/// only `VerifiedPublicKey`s, perhaps taken from a `PopRegistry`.
 ///
 /// We foresee this type primarily being used to batch several
 /// `BitPoPSignedMessage`s into one verification.  We do not track
@@ -80,34 +86,63 @@ use digest::{DynDigest, FixedOutputReset};
 /// but this sounds complex or worse fragile.
 ///
 /// TODO: Implement gaussian elimination verification scheme.
-use core::iter::once;
-
 use double::PublicKeyInSignatureGroup;
-use single::PublicKey;
+use error::StrictInputs;
+use pop_registry::VerifiedPublicKey;
+use single::{PublicKey, SignedMessage};
 
-#[derive(Clone)]
 pub struct SignatureAggregatorAssumingPoP<E: EngineBLS> {
     message: Message,
     aggregated_publickey: PublicKey<E>,
     signature: Signature<E>,
     aggregated_auxiliary_public_key: PublicKeyInSignatureGroup<E>,
+    inputs: StrictInputs,
+}
+
+impl<E: EngineBLS> Clone for SignatureAggregatorAssumingPoP<E> {
+    fn clone(&self) -> SignatureAggregatorAssumingPoP<E> {
+        SignatureAggregatorAssumingPoP {
+            message: self.message.clone(),
+            aggregated_publickey: self.aggregated_publickey,
+            signature: self.signature,
+            aggregated_auxiliary_public_key: self.aggregated_auxiliary_public_key,
+            inputs: self.inputs,
+        }
+    }
 }
 
 impl<E: EngineBLS> SignatureAggregatorAssumingPoP<E> {
     pub fn new(message: Message) -> SignatureAggregatorAssumingPoP<E> {
         SignatureAggregatorAssumingPoP {
-            message: message,
+            message,
             aggregated_publickey: PublicKey(E::PublicKeyGroup::zero()),
             signature: Signature(E::SignatureGroup::zero()),
             aggregated_auxiliary_public_key: PublicKeyInSignatureGroup(E::SignatureGroup::zero()),
+            inputs: StrictInputs::default(),
         }
     }
 
+    /// Validate every signature and public key we add, see `StrictInputs`.
+    pub fn strict(mut self) -> SignatureAggregatorAssumingPoP<E> {
+        self.inputs = StrictInputs::strict();
+        self
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.inputs.is_strict()
+    }
+
+    pub fn invalid(&self) -> Option<BlsError> {
+        self.inputs.invalid()
+    }
+
     /// Add only a `Signature<E>` to our internal signature.
     ///
     /// Useful for constructing an aggregate signature, but we
     pub fn add_signature(&mut self, signature: &Signature<E>) {
-        self.signature.0 += &signature.0;
+        if self.inputs.accept(|| signature.validate()) {
+            self.signature.0 += &signature.0;
+        }
     }
 
     /// Add only a `PublicKey<E>` to our internal data.
@@ -115,7 +150,14 @@ impl<E: EngineBLS> SignatureAggregatorAssumingPoP<E> {
     /// Useful for constructing an aggregate signature, but we
     /// recommend instead using a custom types like `BitPoPSignedMessage`.
     pub fn add_publickey(&mut self, publickey: &PublicKey<E>) {
-        self.aggregated_publickey.0 += publickey.0;
+        if self.inputs.accept(|| publickey.validate()) {
+            self.aggregated_publickey.0 += publickey.0;
+        }
+    }
+
+    /// Add a `PublicKey<E>` whose proof-of-possession we checked.
+    fn add_verified_publickey(&mut self, publickey: &VerifiedPublicKey<E>) {
+        self.aggregated_publickey.0 += publickey.publickey().0;
     }
 
     /// Aggregate the auxiliary public keys in the signature group to be used verification using aux key
@@ -123,7 +165,12 @@ impl<E: EngineBLS> SignatureAggregatorAssumingPoP<E> {
         &mut self,
         publickey_in_signature_group: &PublicKeyInSignatureGroup<E>,
     ) {
-        self.aggregated_auxiliary_public_key.0 += publickey_in_signature_group.0;
+        if self
+            .inputs
+            .accept(|| error::check_publickey(&publickey_in_signature_group.0))
+        {
+            self.aggregated_auxiliary_public_key.0 += publickey_in_signature_group.0;
+        }
     }
 
     /// Returns the aggergated public key.
@@ -132,6 +179,83 @@ impl<E: EngineBLS> SignatureAggregatorAssumingPoP<E> {
         self.aggregated_publickey
     }
 
+    /// Merge another partial aggregate on the same message into ours.
+    ///
+    /// We validate `other`'s points as `StrictInputs::merge` requires.
+    pub fn merge(&mut self, other: &SignatureAggregatorAssumingPoP<E>) -> Result<(), BlsError> {
+        error::ensure(self.message == other.message, BlsError::MessageMismatch)?;
+        if self.inputs.merge(&other.inputs)
+            && !self.inputs.accept(|| {
+                error::check_subgroup(&other.signature.0)?;
+                error::check_subgroup(&other.aggregated_publickey.0)?;
+                error::check_subgroup(&other.aggregated_auxiliary_public_key.0)
+            })
+        {
+            return Ok(());
+        }
+        self.signature.0 += other.signature.0;
+        self.aggregated_publickey.0 += other.aggregated_publickey.0;
+        self.aggregated_auxiliary_public_key.0 += other.aggregated_auxiliary_public_key.0;
+        Ok(())
+    }
+
+    /// Remove a signer's contribution, so both their signature and
+    /// public key, like when we learn they equivocated.
+    ///
+    /// We refuse to remove signatures on other messages, and invalid
+    /// points as in `StrictInputs::require`.  We cannot know if `signed`
+    /// was really aggregated here, so callers should remove only what
+    /// they previously added.
+    ///
+    /// We also subtract the signer's `auxiliary_publickey`, which callers
+    /// must supply if they added one, or else verification using
+    /// auxiliary public keys fails afterwards.
+    pub fn remove_signed<'a, S>(
+        &mut self,
+        signed: &'a S,
+        auxiliary_publickey: Option<&PublicKeyInSignatureGroup<E>>,
+    ) -> Result<(), BlsError>
+    where
+        &'a S: Signed<E = E>,
+    {
+        let signature = signed.signature();
+        let mut publickey = E::PublicKeyGroup::zero();
+        for (message, pk) in signed.messages_and_publickeys() {
+            error::ensure(*message.borrow() == self.message, BlsError::MessageMismatch)?;
+            self.inputs.require(|| pk.borrow().validate())?;
+            publickey += pk.borrow().0;
+        }
+        self.inputs.require(|| signature.validate())?;
+        if let Some(aux) = auxiliary_publickey {
+            self.inputs.require(|| error::check_publickey(&aux.0))?;
+        }
+        self.signature.0 -= signature.0;
+        self.aggregated_publickey.0 -= publickey;
+        if let Some(aux) = auxiliary_publickey {
+            self.aggregated_auxiliary_public_key.0 -= aux.0;
+        }
+        Ok(())
+    }
+
+    /// Split off a partial aggregate `other` of ours, returning the
+    /// aggregate of the remaining signers.
+    ///
+    /// We require that `other` verifies, so that the difference verifies
+    /// if and only if we do, and subtracting `other` could never turn
+    /// an invalid aggregate of ours into a valid one.
+    pub fn difference(
+        &self,
+        other: &SignatureAggregatorAssumingPoP<E>,
+    ) -> Result<SignatureAggregatorAssumingPoP<E>, BlsError> {
+        error::ensure(self.message == other.message, BlsError::MessageMismatch)?;
+        other.try_verify()?;
+        let mut difference = self.clone();
+        difference.signature.0 -= other.signature.0;
+        difference.aggregated_publickey.0 -= other.aggregated_publickey.0;
+        difference.aggregated_auxiliary_public_key.0 -= other.aggregated_auxiliary_public_key.0;
+        Ok(difference)
+    }
+
     // /// Aggregage BLS signatures assuming they have proofs-of-possession
     // /// TODO this function should return Result refusing to aggregate messages
     // /// different than the message the aggregator is initiated at
@@ -152,15 +276,27 @@ impl<E: EngineBLS> SignatureAggregatorAssumingPoP<E> {
     >(
         &self,
     ) -> bool {
-        verify_using_aggregated_auxiliary_public_keys::<E, RandomOracle>(
+        self.inputs.invalid().is_none()
+            && verify_using_aggregated_auxiliary_public_keys::<_, RandomOracle, _>(
+                self,
+                once((&self.message, &self.aggregated_auxiliary_public_key)),
+            )
+    }
+
+    pub fn try_verify_using_aggregated_auxiliary_public_keys<
+        RandomOracle: DynDigest + FixedOutputReset + Default + Clone,
+    >(
+        &self,
+    ) -> Result<(), BlsError> {
+        self.inputs.check()?;
+        try_verify_using_aggregated_auxiliary_public_keys::<_, RandomOracle, _>(
             self,
-            true,
-            self.aggregated_auxiliary_public_key.0,
+            once((&self.message, &self.aggregated_auxiliary_public_key)),
         )
     }
 }
 
-impl<'a, E: EngineBLS> Signed for &'a SignatureAggregatorAssumingPoP<E> {
+impl<E: EngineBLS> Signed for &SignatureAggregatorAssumingPoP<E> {
     type E = E;
 
     type M = Message;
@@ -181,9 +317,185 @@ impl<'a, E: EngineBLS> Signed for &'a SignatureAggregatorAssumingPoP<E> {
         // except the public keys might not be normalized here.
         // We foresee verification via gaussian elimination being faster,
         // but requires affine keys or normalization.
-        verify_with_distinct_messages(self, true)
+        self.inputs.invalid().is_none() && verify_with_distinct_messages(self, true)
         // TODO: verify_with_gaussian_elimination(self)
     }
+
+    fn try_verify(self) -> Result<(), BlsError> {
+        self.inputs.check()?;
+        if self.inputs.is_strict() {
+            try_verify_strict(self)
+        } else {
+            try_verify_simple(self)
+        }
+    }
+}
+
+/// Aggregate BLS signatures on one message by signers whose
+/// proofs-of-possession we checked.
+///
+/// We wrap a strict `SignatureAggregatorAssumingPoP`, but accept public
+/// keys only as `VerifiedPublicKey`s, so we never aggregate rogue keys.
+///
+/// ```
+/// use rand::thread_rng;
+/// use sha2::Sha256;
+/// use tnt_bls::single_pop_aggregator::VerifiedSignatureAggregator;
+/// use tnt_bls::schnorr_pop::SchnorrPoP;
+/// use tnt_bls::{Keypair, Message, ProofOfPossessionGenerator, PublicKey, Signed};
+/// use tnt_bls::{VerifiedPublicKey, ZBLS};
+///
+/// let message = Message::new(b"ctx", b"vote");
+/// let mut keypair = Keypair::<ZBLS>::generate(thread_rng());
+/// let pop: SchnorrPoP<ZBLS> = ProofOfPossessionGenerator::<
+///     ZBLS, Sha256, PublicKey<ZBLS>, SchnorrPoP<ZBLS>,
+/// >::generate_pok(&mut keypair);
+/// let verified = VerifiedPublicKey::from_schnorr_pop::<Sha256>(&keypair.public, &pop).unwrap();
+///
+/// let mut aggregator = VerifiedSignatureAggregator::<ZBLS>::new(message.clone());
+/// aggregator.add_verified_publickey(&verified);
+/// aggregator.add_signature(&keypair.sign(&message));
+/// assert!(aggregator.verify());
+/// ```
+///
+/// Raw public keys are refused:
+///
+/// ```compile_fail
+/// use rand::thread_rng;
+/// use tnt_bls::single_pop_aggregator::VerifiedSignatureAggregator;
+/// use tnt_bls::{Keypair, Message, ZBLS};
+///
+/// let message = Message::new(b"ctx", b"vote");
+/// let keypair = Keypair::<ZBLS>::generate(thread_rng());
+/// let mut aggregator = VerifiedSignatureAggregator::<ZBLS>::new(message);
+/// aggregator.add_publickey(&keypair.public);
+/// ```
+pub struct VerifiedSignatureAggregator<E: EngineBLS>(SignatureAggregatorAssumingPoP<E>);
+
+impl<E: EngineBLS> Clone for VerifiedSignatureAggregator<E> {
+    fn clone(&self) -> VerifiedSignatureAggregator<E> {
+        VerifiedSignatureAggregator(self.0.clone())
+    }
+}
+
+impl<E: EngineBLS> VerifiedSignatureAggregator<E> {
+    pub fn new(message: Message) -> VerifiedSignatureAggregator<E> {
+        VerifiedSignatureAggregator(SignatureAggregatorAssumingPoP::new(message).strict())
+    }
+
+    /// Aggregator for `message` by the given public keys.
+    pub fn from_verified_publickeys<'a, I>(
+        message: Message,
+        publickeys: I,
+    ) -> VerifiedSignatureAggregator<E>
+    where
+        E: 'a,
+        I: IntoIterator<Item = &'a VerifiedPublicKey<E>>,
+    {
+        let mut aggregator = VerifiedSignatureAggregator::new(message);
+        for publickey in publickeys {
+            aggregator.add_verified_publickey(publickey);
+        }
+        aggregator
+    }
+
+    /// First input rejected, if any.
+    pub fn invalid(&self) -> Option<BlsError> {
+        self.0.invalid()
+    }
+
+    /// See `SignatureAggregatorAssumingPoP::add_signature`.
+    pub fn add_signature(&mut self, signature: &Signature<E>) {
+        self.0.add_signature(signature)
+    }
+
+    pub fn add_verified_publickey(&mut self, publickey: &VerifiedPublicKey<E>) {
+        self.0.add_verified_publickey(publickey)
+    }
+
+    /// See `SignatureAggregatorAssumingPoP::add_auxiliary_public_key`.
+    pub fn add_auxiliary_public_key(
+        &mut self,
+        publickey_in_signature_group: &PublicKeyInSignatureGroup<E>,
+    ) {
+        self.0
+            .add_auxiliary_public_key(publickey_in_signature_group)
+    }
+
+    pub fn aggregated_publickey(&self) -> PublicKey<E> {
+        self.0.aggregated_publickey()
+    }
+
+    /// See `SignatureAggregatorAssumingPoP::merge`.
+    pub fn merge(&mut self, other: &VerifiedSignatureAggregator<E>) -> Result<(), BlsError> {
+        self.0.merge(&other.0)
+    }
+
+    /// Remove the contribution of the signer with `publickey`, see
+    /// `SignatureAggregatorAssumingPoP::remove_signed`.
+    pub fn remove_signer(
+        &mut self,
+        publickey: &VerifiedPublicKey<E>,
+        signature: &Signature<E>,
+        auxiliary_publickey: Option<&PublicKeyInSignatureGroup<E>>,
+    ) -> Result<(), BlsError> {
+        let signed = SignedMessage {
+            message: self.0.message.clone(),
+            publickey: *publickey.publickey(),
+            signature: *signature,
+        };
+        self.0.remove_signed(&signed, auxiliary_publickey)
+    }
+
+    /// See `SignatureAggregatorAssumingPoP::difference`.
+    pub fn difference(
+        &self,
+        other: &VerifiedSignatureAggregator<E>,
+    ) -> Result<VerifiedSignatureAggregator<E>, BlsError> {
+        self.0.difference(&other.0).map(VerifiedSignatureAggregator)
+    }
+
+    pub fn verify_using_aggregated_auxiliary_public_keys<
+        RandomOracle: DynDigest + FixedOutputReset + Default + Clone,
+    >(
+        &self,
+    ) -> bool {
+        self.0
+            .verify_using_aggregated_auxiliary_public_keys::<RandomOracle>()
+    }
+
+    pub fn try_verify_using_aggregated_auxiliary_public_keys<
+        RandomOracle: DynDigest + FixedOutputReset + Default + Clone,
+    >(
+        &self,
+    ) -> Result<(), BlsError> {
+        self.0
+            .try_verify_using_aggregated_auxiliary_public_keys::<RandomOracle>()
+    }
+}
+
+impl<E: EngineBLS> Signed for &VerifiedSignatureAggregator<E> {
+    type E = E;
+
+    type M = Message;
+    type PKG = PublicKey<Self::E>;
+    type PKnM = ::core::iter::Once<(Message, PublicKey<E>)>;
+
+    fn messages_and_publickeys(self) -> Self::PKnM {
+        (&self.0).messages_and_publickeys()
+    }
+
+    fn signature(&self) -> Signature<E> {
+        self.0.signature
+    }
+
+    fn verify(self) -> bool {
+        (&self.0).verify()
+    }
+
+    fn try_verify(self) -> Result<(), BlsError> {
+        (&self.0).try_verify()
+    }
 }
 
 #[cfg(all(test, feature = "std"))]
@@ -233,7 +545,7 @@ mod tests {
         aggregated_sigs.add_publickey(&keypair1.public);
 
         assert!(
-            aggregated_sigs.verify() == true,
+            aggregated_sigs.verify(),
             "good aggregated signature of a single message with multiple key does not verify"
         );
     }
@@ -255,11 +567,36 @@ mod tests {
         aggregated_sigs.add_publickey(&keypair.public);
 
         assert!(
-            aggregated_sigs.verify() == true,
+            aggregated_sigs.verify(),
             "good aggregate of a repetitive signature does not verify"
         );
     }
 
+    #[test]
+    fn strict_aggregation_rejects_identity_keys() {
+        type E = UsualBLS<Bls12_381, ark_bls12_381::Config>;
+        let good = Message::new(b"ctx", b"test message");
+        let mut keypair = Keypair::<E>::generate(thread_rng());
+        let good_sig = keypair.sign(&good);
+        let identity = PublicKey::<E>(Zero::zero());
+
+        let mut lax = SignatureAggregatorAssumingPoP::<E>::new(good.clone());
+        lax.add_signature(&good_sig);
+        lax.add_publickey(&keypair.public);
+        lax.add_publickey(&identity);
+        assert!(lax.verify());
+
+        let mut strict = SignatureAggregatorAssumingPoP::<E>::new(good).strict();
+        assert!(strict.is_strict());
+        strict.add_signature(&good_sig);
+        strict.add_publickey(&keypair.public);
+        assert!(strict.verify());
+        strict.add_publickey(&identity);
+        assert!(strict.invalid() == Some(crate::BlsError::IdentityPublicKey));
+        assert!(!strict.verify());
+        assert!(strict.try_verify() == Err(crate::BlsError::IdentityPublicKey));
+    }
+
     #[test]
     fn aggregate_of_signature_of_a_wrong_message_should_not_verify() {
         let good0 = Message::new(b"ctx", b"Space over Tab");
@@ -283,7 +620,7 @@ mod tests {
         aggregated_sigs.add_publickey(&keypair1.public);
 
         assert!(
-            aggregated_sigs.verify() == false,
+            !aggregated_sigs.verify(),
             "aggregated signature of a wrong message should not verify"
         );
     }
@@ -292,7 +629,6 @@ mod tests {
     fn test_aggregate_tiny_sigs_and_verify_in_g1() {
         let message = Message::new(b"ctx", b"test message");
         let mut keypairs: Vec<_> = (0..3)
-            .into_iter()
             .map(|_| Keypair::<TinyBLS<Bls12_377, ark_bls12_377::Config>>::generate(thread_rng()))
             .collect();
         let pub_keys_in_sig_grp: Vec<PublicKeyInSignatureGroup<TinyBLS377>> = keypairs
@@ -331,4 +667,142 @@ mod tests {
             "verification using non-matching auxilary public key should fail"
         );
     }
+
+    fn check_fast_aggregate_verify<E: EngineBLS>(
+        publickeys: &[&[u8]],
+        signatures: &[&[u8]],
+        aggregated_signature: &[u8],
+    ) {
+        let message = Message::new_assuming_pop(b"", &[0xabu8; 32]);
+        let mut aggregator = SignatureAggregatorAssumingPoP::<E>::new(message.clone());
+        for signature in signatures {
+            aggregator.add_signature(&Signature::from_bytes(signature).unwrap());
+        }
+        assert_eq!((&aggregator).signature().to_bytes(), aggregated_signature);
+
+        let mut verifier = SignatureAggregatorAssumingPoP::<E>::new(message);
+        verifier.add_signature(&Signature::from_bytes(aggregated_signature).unwrap());
+        for publickey in &publickeys[..2] {
+            verifier.add_publickey(&PublicKey::from_bytes(publickey).unwrap());
+        }
+        assert!(
+            !verifier.verify(),
+            "aggregate signature verified with a missing signer"
+        );
+        verifier.add_publickey(&PublicKey::from_bytes(publickeys[2]).unwrap());
+        assert!(verifier.verify(), "IETF FastAggregateVerify vector failed");
+    }
+
+    /// `Aggregate` and `FastAggregateVerify` vectors from the Ethereum consensus specs.
+    #[test]
+    fn ietf_fast_aggregate_verify_g2() {
+        check_fast_aggregate_verify::<ZBLS>(
+            &[
+                &hex_literal::hex!("a491d1b0ecd9bb917989f0e74f0dea0422eac4a873e5e2644f368dffb9a6e20fd6e10c1b77654d067c0618f6e5a7f79a"),
+                &hex_literal::hex!("b301803f8b5ac4a1133581fc676dfedc60d891dd5fa99028805e5ea5b08d3491af75d0707adab3b70c6a6a580217bf81"),
+                &hex_literal::hex!("b53d21a4cfd562c469cc81514d4ce5a6b577d8403d32a394dc265dd190b47fa9f829fdd7963afdf972e5e77854051f6f"),
+            ],
+            &[
+                &hex_literal::hex!("91347bccf740d859038fcdcaf233eeceb2a436bcaaee9b2aa3bfb70efe29dfb2677562ccbea1c8e061fb9971b0753c240622fab78489ce96768259fc01360346da5b9f579e5da0d941e4c6ba18a0e64906082375394f337fa1af2b7127b0d121"),
+                &hex_literal::hex!("9674e2228034527f4c083206032b020310face156d4a4685e2fcaec2f6f3665aa635d90347b6ce124eb879266b1e801d185de36a0a289b85e9039662634f2eea1e02e670bc7ab849d006a70b2f93b84597558a05b879c8d445f387a5d5b653df"),
+                &hex_literal::hex!("ae82747ddeefe4fd64cf9cedb9b04ae3e8a43420cd255e3c7cd06a8d88b7c7f8638543719981c5d16fa3527c468c25f0026704a6951bde891360c7e8d12ddee0559004ccdbe6046b55bae1b257ee97f7cdb955773d7cf29adf3ccbb9975e4eb9"),
+            ],
+            &hex_literal::hex!("9712c3edd73a209c742b8250759db12549b3eaf43b5ca61376d9f30e2747dbcf842d8b2ac0901d2a093713e20284a7670fcf6954e9ab93de991bb9b313e664785a075fc285806fa5224c82bde146561b446ccfc706a64b8579513cfc4ff1d930"),
+        );
+    }
+
+    /// The same signers under `BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_`, checked against blst.
+    #[test]
+    fn ietf_fast_aggregate_verify_g1() {
+        check_fast_aggregate_verify::<TinyBLS381>(
+            &[
+                &hex_literal::hex!("ac400b70f6f8cd35648f5c126cce5417f3be4d8eefbd42ceb4286a14df7e03135313fe5845e3a575faab3e8b949d248814856c22d8cdb2967c720e963eedc999e738373b14172f06fc915769d3cc5ab7ae0a1b9c38f48b5585fb09d4bd2733bb"),
+                &hex_literal::hex!("a4b8f49c3bac0247a09487049492b0ed99cf90c56263141daa35f011330d3ced3f3ad78d252c51a3bb42fc7d8f1825940bc2357c6782bbb6a078d9e171fc7a81f7bd8ca73eb485e76317359908bb09bd372fd362a637512a9d48019b383e5489"),
+                &hex_literal::hex!("b0b39dda41e997feedd65253bd98bb1a150584dc23aca4c16d967b725ce86736ccdd33845de3058aafda88485750759908fd5505c6c3daf58fde81bdadbbefbc625dd9885faef3fca406a086f743d5eab6b6cb36b1984cbf08c6a4effcb3018d"),
+            ],
+            &[
+                &hex_literal::hex!("945b268e7fbc953e95f8f1d5592683f5494e7d24d7e6352b7225617d8b9c595ee0d9e4f1dfabe5c0b8ce6fdefbe90610"),
+                &hex_literal::hex!("a59abf76f1cc5cbfc8038906e081b800547c1a98908195d5cab7fc2b09638f299fef7bec2ef791c18baab6ebd9e2047d"),
+                &hex_literal::hex!("992d1d66d89f98903a46bb8dd18e90233b626f718ce22f3189964734146fd1c14a0224187921d32b9f06ae5943c5853c"),
+            ],
+            &hex_literal::hex!("94925582e03de5b2a8f35a50f54049a9a953a5e9290597b036fd041e3eca78846cd96a26b9cf60d867bb739b2136213a"),
+        );
+    }
+
+    #[test]
+    fn merge_remove_and_split_aggregates() {
+        let message = Message::new(b"ctx", b"vote");
+        let mut keypairs: Vec<_> = (0..4)
+            .map(|_| Keypair::<TinyBLS377>::generate(thread_rng()))
+            .collect();
+        let signed: Vec<_> = keypairs
+            .iter_mut()
+            .map(|k| k.signed_message(&message))
+            .collect();
+
+        let mut left = SignatureAggregatorAssumingPoP::<TinyBLS377>::new(message.clone()).strict();
+        let mut right = SignatureAggregatorAssumingPoP::<TinyBLS377>::new(message.clone());
+        for (i, s) in signed.iter().enumerate() {
+            let partial = if i < 2 { &mut left } else { &mut right };
+            partial.add_signature(&s.signature);
+            partial.add_publickey(&s.publickey);
+            partial.add_auxiliary_public_key(&keypairs[i].into_public_key_in_signature_group());
+        }
+
+        let mut merged = left.clone();
+        merged.merge(&right).unwrap();
+        assert!(merged.verify());
+        assert!(merged.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        assert!(merged.invalid().is_none());
+
+        // Splitting off `right` recovers `left`.
+        let split = merged.difference(&right).unwrap();
+        assert!(split.verify());
+        assert!(split.aggregated_publickey() == left.aggregated_publickey());
+        assert!(split.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+
+        // Removing an equivocating signer leaves a valid aggregate.
+        let mut removed = merged.clone();
+        removed
+            .remove_signed(
+                &signed[0],
+                Some(&keypairs[0].into_public_key_in_signature_group()),
+            )
+            .unwrap();
+        assert!(removed.verify());
+        assert!(removed.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        removed
+            .remove_signed(
+                &signed[3],
+                Some(&keypairs[3].into_public_key_in_signature_group()),
+            )
+            .unwrap();
+        assert!(removed.verify());
+        assert!(removed.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+        assert!((&removed).signature().0 == signed[1].signature.0 + signed[2].signature.0);
+
+        // Forgetting the auxiliary public key breaks only auxiliary verification.
+        let mut forgetful = merged.clone();
+        forgetful.remove_signed(&signed[0], None).unwrap();
+        assert!(forgetful.verify());
+        assert!(!forgetful.verify_using_aggregated_auxiliary_public_keys::<Sha256>());
+
+        // We refuse other messages.
+        let other = Message::new(b"ctx", b"other vote");
+        let equivocation = keypairs[0].signed_message(&other);
+        assert!(
+            removed.remove_signed(&equivocation, None) == Err(crate::BlsError::MessageMismatch)
+        );
+        assert!(
+            merged.merge(&SignatureAggregatorAssumingPoP::new(other))
+                == Err(crate::BlsError::MessageMismatch)
+        );
+        assert!(merged.verify());
+
+        // We refuse to subtract aggregates which do not verify.
+        let mut bogus = SignatureAggregatorAssumingPoP::<TinyBLS377>::new(message);
+        bogus.add_signature(&signed[1].signature);
+        bogus.add_publickey(&signed[0].publickey);
+        assert!(merged.difference(&bogus).err() == Some(crate::BlsError::PairingMismatch));
+    }
 }
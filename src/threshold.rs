@@ -0,0 +1,410 @@
+//! ## Threshold BLS signatures from Shamir secret sharing
+//!
+//! A dealer splits a `SecretKeyVT` into `n` shares of a random
+//! polynomial `f` of degree `t - 1` with `f(0)` the secret key, and
+//! publishes a Feldman verification vector holding the coefficients
+//! of `f` multiplied by the public key group generator.  Anyone may
+//! derive the public share `f(i) g` of the share holder `i`, as well
+//! as the group public key `f(0) g`, from this verification vector.
+//!
+//! Each share holder signs with its share to produce a partial
+//! signature `f(i) H(m)`.  Any `t` partial signatures interpolate to
+//! `f(0) H(m)` by Lagrange interpolation at zero, which is an ordinary
+//! `Signature` verifiable under the group `PublicKey`.  We verify
+//! partial signatures against public shares before combining them,
+//! because one bad partial signature spoils the combined signature.
+//!
+//! Share indices must be distinct and non-zero, as the share at zero
+//! is the secret key itself.
+
+use alloc::vec::Vec;
+
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::Rng;
+
+use crate::single::{PublicKey, SecretKeyVT, Signature};
+use crate::{EngineBLS, Message};
+
+/// Error type for threshold key generation and signature combination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThresholdError {
+    /// A threshold of zero shares cannot reconstruct anything.
+    ZeroThreshold,
+    /// Share index zero holds the secret key itself.
+    ZeroIndex,
+    /// The same share index appeared twice.
+    DuplicateIndex(u32),
+    /// Combination requires at least one partial signature.
+    NoPartialSignatures,
+}
+
+impl ::core::fmt::Display for ThresholdError {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        use self::ThresholdError::*;
+        match self {
+            ZeroThreshold => write!(f, "The threshold must be at least one."),
+            ZeroIndex => write!(f, "Share index zero is reserved for the secret key."),
+            DuplicateIndex(i) => write!(f, "Share index {} appears more than once.", i),
+            NoPartialSignatures => write!(f, "No partial signatures to combine."),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for ThresholdError {}
+
+/// Lagrange coefficient of the share `index` for interpolating at zero
+/// from the shares at `indices`, which must contain `index`.
+///
+/// We panic on duplicate indices, so callers must ensure distinct
+/// indices, like `check_indices` does.
+pub(crate) fn lagrange_coefficient_at_zero<F: Field>(index: u32, indices: &[u32]) -> F {
+    let x_i = F::from(index as u64);
+    let (numerator, denominator) = indices.iter().filter(|j| **j != index).fold(
+        (F::one(), F::one()),
+        |(numerator, denominator), j| {
+            let x_j = F::from(*j as u64);
+            (numerator * x_j, denominator * (x_j - x_i))
+        },
+    );
+    numerator * denominator.inverse().expect("share indices are distinct")
+}
+
+/// Check that share indices are non-zero and distinct.
+pub(crate) fn check_indices(indices: &[u32]) -> Result<(), ThresholdError> {
+    for (k, i) in indices.iter().enumerate() {
+        if *i == 0 {
+            return Err(ThresholdError::ZeroIndex);
+        }
+        if indices[..k].contains(i) {
+            return Err(ThresholdError::DuplicateIndex(*i));
+        }
+    }
+    Ok(())
+}
+
+/// Dealer holding the secret sharing polynomial.
+///
+/// The dealer learns the secret key, so prefer dealerless key generation
+/// whenever no single party should ever hold it.
+pub struct Dealer<E: EngineBLS> {
+    coefficients: Vec<E::Scalar>,
+}
+
+impl<E: EngineBLS> Dealer<E> {
+    /// Share `secret` so that any `threshold` shares reconstruct it.
+    pub fn new<R: Rng>(
+        secret: &SecretKeyVT<E>,
+        threshold: usize,
+        mut rng: R,
+    ) -> Result<Self, ThresholdError> {
+        if threshold == 0 {
+            return Err(ThresholdError::ZeroThreshold);
+        }
+        let mut coefficients = Vec::with_capacity(threshold);
+        coefficients.push(secret.0);
+        coefficients.extend((1..threshold).map(|_| E::Scalar::rand(&mut rng)));
+        Ok(Dealer { coefficients })
+    }
+
+    /// Number of shares required to sign.
+    pub fn threshold(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    /// Secret share for the holder at `index`.
+    pub fn share(&self, index: u32) -> Result<SecretShare<E>, ThresholdError> {
+        if index == 0 {
+            return Err(ThresholdError::ZeroIndex);
+        }
+        let x = E::Scalar::from(index as u64);
+        let secret = self
+            .coefficients
+            .iter()
+            .rev()
+            .fold(E::Scalar::zero(), |acc, c| acc * x + c);
+        Ok(SecretShare {
+            index,
+            secret: SecretKeyVT(secret),
+        })
+    }
+
+    /// Secret shares for the holders at indices `1..=n`.
+    pub fn shares(&self, n: u32) -> Vec<SecretShare<E>> {
+        (1..=n)
+            .map(|index| self.share(index).expect("index is non-zero"))
+            .collect()
+    }
+
+    /// Feldman commitments to our polynomial for verifying shares.
+    pub fn verification_vector(&self) -> VerificationVector<E> {
+        let generator = <E::PublicKeyGroup as CurveGroup>::Affine::generator();
+        VerificationVector(self.coefficients.iter().map(|c| generator * c).collect())
+    }
+}
+
+/// Commitments `a_k g` to the coefficients `a_k` of a sharing polynomial.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct VerificationVector<E: EngineBLS>(pub Vec<E::PublicKeyGroup>);
+
+impl<E: EngineBLS> Clone for VerificationVector<E> {
+    fn clone(&self) -> Self {
+        VerificationVector(self.0.clone())
+    }
+}
+
+impl<E: EngineBLS> PartialEq<Self> for VerificationVector<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq(&other.0)
+    }
+}
+
+impl<E: EngineBLS> Eq for VerificationVector<E> {}
+
+impl<E: EngineBLS> VerificationVector<E> {
+    /// Number of shares required to sign.
+    pub fn threshold(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The public key under which combined signatures verify.
+    pub fn group_public_key(&self) -> PublicKey<E> {
+        PublicKey(self.0.first().copied().unwrap_or_else(Zero::zero))
+    }
+
+    /// Public key of the share holder at `index`.
+    pub fn public_share(&self, index: u32) -> PublicKey<E> {
+        let x = E::Scalar::from(index as u64);
+        PublicKey(
+            self.0
+                .iter()
+                .rev()
+                .fold(E::PublicKeyGroup::zero(), |acc, c| acc * x + c),
+        )
+    }
+
+    /// Check a secret share against this verification vector.
+    pub fn verify_share(&self, share: &SecretShare<E>) -> bool {
+        share.index != 0 && share.public_share() == self.public_share(share.index)
+    }
+}
+
+/// One holder's share of a threshold secret key.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct SecretShare<E: EngineBLS> {
+    pub index: u32,
+    pub secret: SecretKeyVT<E>,
+}
+
+impl<E: EngineBLS> Clone for SecretShare<E> {
+    fn clone(&self) -> Self {
+        SecretShare {
+            index: self.index,
+            secret: self.secret.clone(),
+        }
+    }
+}
+
+impl<E: EngineBLS> SecretShare<E> {
+    /// Our public share, which verifies our partial signatures.
+    pub fn public_share(&self) -> PublicKey<E> {
+        self.secret.into_public()
+    }
+
+    /// Produce our partial signature on `message`.
+    pub fn sign(&self, message: &Message) -> PartialSignature<E> {
+        PartialSignature {
+            index: self.index,
+            signature: self.secret.sign(message),
+        }
+    }
+}
+
+/// Signature by one share holder, which combines with others into
+/// a signature under the group public key.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct PartialSignature<E: EngineBLS> {
+    pub index: u32,
+    pub signature: Signature<E>,
+}
+
+impl<E: EngineBLS> Clone for PartialSignature<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E: EngineBLS> Copy for PartialSignature<E> {}
+
+impl<E: EngineBLS> PartialEq<Self> for PartialSignature<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.signature == other.signature
+    }
+}
+
+impl<E: EngineBLS> Eq for PartialSignature<E> {}
+
+impl<E: EngineBLS> PartialSignature<E> {
+    /// Verify against the public share of the signer at `self.index`,
+    /// normally given by `VerificationVector::public_share`.
+    pub fn verify(&self, message: &Message, public_share: &PublicKey<E>) -> bool {
+        self.signature.verify(message, public_share)
+    }
+}
+
+/// Combine partial signatures by Lagrange interpolation at zero.
+///
+/// We require exactly the threshold number of partial signatures for
+/// the result to verify under the group public key, although more
+/// cost only extra time.  We do not verify partial signatures here.
+pub fn combine_partial_signatures<E: EngineBLS>(
+    partials: &[PartialSignature<E>],
+) -> Result<Signature<E>, ThresholdError> {
+    if partials.is_empty() {
+        return Err(ThresholdError::NoPartialSignatures);
+    }
+    let indices = partials.iter().map(|p| p.index).collect::<Vec<_>>();
+    check_indices(&indices)?;
+    let mut signature = E::SignatureGroup::zero();
+    for partial in partials {
+        let lambda: E::Scalar = lagrange_coefficient_at_zero(partial.index, &indices);
+        signature += partial.signature.0 * lambda;
+    }
+    Ok(Signature(signature))
+}
+
+/// Reconstruct the secret key from shares by Lagrange interpolation,
+/// mostly useful for testing and recovery.
+pub fn reconstruct_secret<E: EngineBLS>(
+    shares: &[SecretShare<E>],
+) -> Result<SecretKeyVT<E>, ThresholdError> {
+    let indices = shares.iter().map(|s| s.index).collect::<Vec<_>>();
+    check_indices(&indices)?;
+    Ok(SecretKeyVT(
+        shares.iter().fold(E::Scalar::zero(), |acc, s| {
+            acc + s.secret.0 * lagrange_coefficient_at_zero::<E::Scalar>(s.index, &indices)
+        }),
+    ))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use ark_ff::One;
+    use rand::{seq::SliceRandom, thread_rng};
+
+    use super::*;
+    use crate::{TinyBLS377, TinyBLS381, BLS377, ZBLS};
+
+    fn threshold_signing<E: EngineBLS>(t: usize, n: u32) {
+        let mut rng = thread_rng();
+        let secret = SecretKeyVT::<E>::generate(&mut rng);
+        let dealer = Dealer::new(&secret, t, &mut rng).unwrap();
+        let shares = dealer.shares(n);
+        let verification_vector = dealer.verification_vector();
+        assert_eq!(verification_vector.threshold(), t);
+        assert!(verification_vector.group_public_key() == secret.into_public());
+        assert!(shares.iter().all(|s| verification_vector.verify_share(s)));
+
+        let message = Message::new(b"ctx", b"threshold message");
+        let partials = shares.iter().map(|s| s.sign(&message)).collect::<Vec<_>>();
+        for partial in &partials {
+            let public_share = verification_vector.public_share(partial.index);
+            assert!(partial.verify(&message, &public_share));
+        }
+
+        let group_public_key = verification_vector.group_public_key();
+        for _ in 0..3 {
+            let subset = partials
+                .choose_multiple(&mut rng, t)
+                .copied()
+                .collect::<Vec<_>>();
+            let signature = combine_partial_signatures(&subset).unwrap();
+            assert!(signature.verify(&message, &group_public_key));
+            assert!(signature == secret.sign(&message));
+
+            let subset_shares = subset
+                .iter()
+                .map(|p| shares[p.index as usize - 1].clone())
+                .collect::<Vec<_>>();
+            assert_eq!(reconstruct_secret(&subset_shares).unwrap().0, secret.0);
+        }
+
+        if t > 1 {
+            let signature = combine_partial_signatures(&partials[..t - 1]).unwrap();
+            assert!(
+                !signature.verify(&message, &group_public_key),
+                "fewer than threshold partial signatures verified"
+            );
+        }
+    }
+
+    #[test]
+    fn threshold_signing_for_all_engines() {
+        threshold_signing::<ZBLS>(3, 5);
+        threshold_signing::<BLS377>(1, 3);
+        threshold_signing::<TinyBLS381>(4, 7);
+        threshold_signing::<TinyBLS377>(2, 2);
+    }
+
+    #[test]
+    fn invalid_shares_and_partials_are_detected() {
+        let mut rng = thread_rng();
+        let secret = SecretKeyVT::<ZBLS>::generate(&mut rng);
+        let dealer = Dealer::new(&secret, 2, &mut rng).unwrap();
+        let verification_vector = dealer.verification_vector();
+        let mut shares = dealer.shares(3);
+
+        shares[0].secret.0 += <ZBLS as EngineBLS>::Scalar::one();
+        assert!(!verification_vector.verify_share(&shares[0]));
+
+        let message = Message::new(b"ctx", b"threshold message");
+        let bad = shares[0].sign(&message);
+        assert!(!bad.verify(&message, &verification_vector.public_share(bad.index)));
+        let good = shares[1].sign(&message);
+        assert!(!good.verify(&message, &verification_vector.public_share(bad.index)));
+
+        let signature = combine_partial_signatures(&[bad, good]).unwrap();
+        assert!(!signature.verify(&message, &verification_vector.group_public_key()));
+    }
+
+    #[test]
+    fn malformed_inputs_are_rejected() {
+        let mut rng = thread_rng();
+        let secret = SecretKeyVT::<ZBLS>::generate(&mut rng);
+        assert!(matches!(
+            Dealer::new(&secret, 0, &mut rng),
+            Err(ThresholdError::ZeroThreshold)
+        ));
+        let dealer = Dealer::new(&secret, 2, &mut rng).unwrap();
+        assert!(matches!(dealer.share(0), Err(ThresholdError::ZeroIndex)));
+
+        let message = Message::new(b"ctx", b"threshold message");
+        let partial = dealer.share(1).unwrap().sign(&message);
+        assert!(matches!(
+            combine_partial_signatures::<ZBLS>(&[]),
+            Err(ThresholdError::NoPartialSignatures)
+        ));
+        assert!(matches!(
+            combine_partial_signatures(&[partial, partial]),
+            Err(ThresholdError::DuplicateIndex(1))
+        ));
+    }
+
+    #[test]
+    fn verification_vector_serialization() {
+        let mut rng = thread_rng();
+        let secret = SecretKeyVT::<TinyBLS381>::generate(&mut rng);
+        let dealer = Dealer::new(&secret, 3, &mut rng).unwrap();
+        let verification_vector = dealer.verification_vector();
+        let mut bytes = Vec::new();
+        verification_vector
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        assert!(
+            VerificationVector::<TinyBLS381>::deserialize_compressed(&bytes[..]).unwrap()
+                == verification_vector
+        );
+    }
+}
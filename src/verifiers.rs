@@ -3,24 +3,20 @@
 //!
 //!
 
+use alloc::collections::BTreeMap;
 use core::borrow::Borrow;
-#[cfg(feature = "std")]
-use std::collections::HashMap;
 
-#[cfg(feature = "std")]
 use ark_ec::AffineRepr;
-#[cfg(feature = "std")]
 use ark_ff::field_hashers::{DefaultFieldHasher, HashToField};
-#[cfg(feature = "std")]
+use ark_ff::Zero;
 use ark_serialize::CanonicalSerialize;
-#[cfg(feature = "std")]
-use digest::DynDigest;
-#[cfg(feature = "std")]
 use digest::FixedOutputReset;
 
+use crate::double::PublicKeyInSignatureGroup;
+
 use ark_ec::CurveGroup;
 
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 
 use super::*;
 
@@ -75,25 +71,79 @@ pub fn verify_simple<S: Signed>(s: S) -> bool {
     let mut gpk = Vec::with_capacity(l);
     let mut gms = Vec::with_capacity(l + 1);
     for (message, publickey) in itr {
-        gpk.push(publickey.borrow().0.clone());
+        gpk.push(publickey.borrow().0);
+        gms.push(message.borrow().hash_to_signature_curve::<S::E>());
+    }
+    verify_points::<S::E>(signature, &gpk, gms)
+}
+
+/// Universal BLS signature verification which explains failures
+///
+/// We do everything `verify_simple` does, but first reject empty
+/// aggregates, identity public keys, and points outside the prime
+/// order subgroup, all of which `verify_simple` accepts.
+pub fn try_verify_simple<S: Signed>(s: S) -> Result<(), BlsError> {
+    try_verify_checked(s, false)
+}
+
+/// Strict universal BLS signature verification
+///
+/// We validate the signature and every public key like
+/// `Signature::validate` and `PublicKey::validate`, as the IETF BLS
+/// signature draft requires for its basic and proof-of-possession
+/// schemes, and then proceed like `verify_simple`.
+pub fn try_verify_strict<S: Signed>(s: S) -> Result<(), BlsError> {
+    try_verify_checked(s, true)
+}
+
+/// Strict universal BLS signature verification, see `try_verify_strict`.
+pub fn verify_strict<S: Signed>(s: S) -> bool {
+    try_verify_strict(s).is_ok()
+}
+
+fn try_verify_checked<S: Signed>(s: S, strict: bool) -> Result<(), BlsError> {
+    let signature = s.signature();
+    if strict {
+        signature.validate()?;
+    } else {
+        error::check_subgroup(&signature.0)?;
+    }
+    let itr = s.messages_and_publickeys();
+    let l = {
+        let (lower, upper) = itr.size_hint();
+        upper.unwrap_or(lower)
+    };
+    let mut gpk = Vec::with_capacity(l);
+    let mut gms = Vec::with_capacity(l + 1);
+    for (message, publickey) in itr {
+        publickey.borrow().validate()?;
+        gpk.push(publickey.borrow().0);
         gms.push(message.borrow().hash_to_signature_curve::<S::E>());
     }
-    let gpk = <<S as Signed>::E as EngineBLS>::PublicKeyGroup::normalize_batch(gpk.as_mut_slice());
-    gms.push(signature);
-    let mut gms =
-        <<S as Signed>::E as EngineBLS>::SignatureGroup::normalize_batch(gms.as_mut_slice());
-    let signature = <<S as Signed>::E as EngineBLS>::prepare_signature(gms.pop().unwrap());
-    let prepared = gpk
+    error::ensure(!gpk.is_empty(), BlsError::EmptyAggregate)?;
+    error::ensure(
+        verify_points::<S::E>(signature.0, &gpk, gms),
+        BlsError::PairingMismatch,
+    )
+}
+
+/// Verify `signature` against the pairs of `publickeys` and `messages`,
+/// batch normalizing all points.
+fn verify_points<E: EngineBLS>(
+    signature: E::SignatureGroup,
+    publickeys: &[E::PublicKeyGroup],
+    mut messages: Vec<E::SignatureGroup>,
+) -> bool {
+    let publickeys = E::PublicKeyGroup::normalize_batch(publickeys);
+    messages.push(signature);
+    let mut messages = E::SignatureGroup::normalize_batch(messages.as_mut_slice());
+    let signature = E::prepare_signature(messages.pop().unwrap());
+    let prepared = publickeys
         .iter()
-        .zip(gms)
-        .map(|(pk, m)| {
-            (
-                <<S as Signed>::E as EngineBLS>::prepare_public_key(*pk),
-                <<S as Signed>::E as EngineBLS>::prepare_signature(m),
-            )
-        })
+        .zip(messages)
+        .map(|(pk, m)| (E::prepare_public_key(*pk), E::prepare_signature(m)))
         .collect::<Vec<(_, _)>>();
-    S::E::verify_prepared(signature, prepared.iter())
+    E::verify_prepared(signature, prepared.iter())
 }
 
 /// BLS signature verification optimized for all unique messages
@@ -108,7 +158,9 @@ pub fn verify_simple<S: Signed>(s: S) -> bool {
 /// We optionally batch normalize the public keys in the event that
 /// they are provided by algerbaic operaations, but this sounds
 /// unlikely given our requirement that messages be distinct.
-#[cfg(feature = "std")]
+///
+/// We merge signers in a `BTreeMap`, so we work without `std` and
+/// pair in an order determined only by the public keys.
 pub fn verify_with_distinct_messages<S: Signed>(signed: S, normalize_public_keys: bool) -> bool {
     let signature = signed.signature().0;
     // We first hash the messages to the signature curve and
@@ -124,7 +176,7 @@ pub fn verify_with_distinct_messages<S: Signed>(signed: S, normalize_public_keys
     let mut publickeys = Vec::with_capacity(l);
     let mut messages = Vec::with_capacity(l + 1);
     for (m, pk) in itr {
-        publickeys.push(pk.borrow().0.clone());
+        publickeys.push(pk.borrow().0);
         messages.push(m.borrow().hash_to_signature_curve::<S::E>());
     }
     let mut affine_publickeys = if normalize_public_keys {
@@ -144,7 +196,7 @@ pub fn verify_with_distinct_messages<S: Signed>(signed: S, normalize_public_keys
     // TODO:  Impl PartialEq, Eq, Hash for pairing::EncodedPoint
     // to avoid  struct H(E::PublicKeyGroup::Affine::Uncompressed);
     type AA<E> = (PublicKeyAffine<E>, SignatureProjective<E>);
-    let mut pks_n_ms = HashMap::with_capacity(l);
+    let mut pks_n_ms = BTreeMap::new();
     for (pk, m) in affine_publickeys.drain(..).zip(messages.drain(..)) {
         let mut pk_uncompressed = vec![0; pk.uncompressed_size()];
         pk.serialize_uncompressed(&mut pk_uncompressed[..]).unwrap();
@@ -157,9 +209,9 @@ pub fn verify_with_distinct_messages<S: Signed>(signed: S, normalize_public_keys
     }
 
     let mut publickeys = Vec::with_capacity(l);
-    for (_, (pk, m)) in pks_n_ms.drain() {
+    for (pk, m) in pks_n_ms.into_values() {
         messages.push(m);
-        publickeys.push(pk.clone());
+        publickeys.push(pk);
     }
 
     // We finally normalize the messages and signature
@@ -191,156 +243,144 @@ pub fn verify_with_distinct_messages<S: Signed>(signed: S, normalize_public_keys
     //S::E::verify_prepared( &signature, prepared )
 }
 
-/// BLS signature verification optimized for all unique messages
+/// BLS signature verification which also checks auxiliary public keys
+/// in the signature group, as in Nugget BLS.
 ///
-/// Assuming all messages are distinct, the minimum number of pairings
-/// is the number of unique signers, which we achieve here.
-/// We do not verify message uniqueness here, but leave this to the
-/// aggregate signature type, like `DistinctMessages`.
+/// We group `signed`'s public keys by message, and sum both them and
+/// the `auxiliary_public_keys` for every distinct message `m_j`, into
+/// `pk_j` and `aux_j`.  We then check
 ///
-/// We merge any messages with identical signers and batch normalize
-/// message points and the signature itself.
-/// We optionally batch normalize the public keys in the event that
-/// they are provided by algerbaic operaations, but this sounds
-/// unlikely given our requirement that messages be distinct.
-#[cfg(feature = "std")]
-pub fn verify_using_aggregated_auxiliary_public_keys<
-    E: EngineBLS,
+/// `e(g, sigma + sum r_j aux_j) = prod_j e(pk_j, H(m_j) + r_j g)`
+///
+/// with `r_j = r^j` for one pseudo-random scalar `r` hashed from all
+/// inputs, so both the aggregate signature and every message group's
+/// auxiliary key verify with one pairing per distinct message.
+///
+/// We reject aggregates which lack an auxiliary public key for some
+/// message, or have auxiliary public keys for other messages.
+pub fn verify_using_aggregated_auxiliary_public_keys<'a, S, H, I>(
+    signed: S,
+    auxiliary_public_keys: I,
+) -> bool
+where
+    S: Signed,
+    S::E: 'a,
     H: DynDigest + FixedOutputReset + Default + Clone,
->(
-    signed: &single_pop_aggregator::SignatureAggregatorAssumingPoP<E>,
-    normalize_public_keys: bool,
-    aggregated_aux_pub_key: <E as EngineBLS>::SignatureGroup,
-) -> bool {
-    let signature = Signed::signature(&signed).0;
-
-    let mut signature_as_bytes = vec![0; signature.compressed_size()];
-    signature
-        .serialize_compressed(&mut signature_as_bytes[..])
-        .expect("compressed size has been alocated");
-
-    let itr = signed.messages_and_publickeys();
-    let l = {
-        let (lower, upper) = itr.size_hint();
-        upper.unwrap_or(lower)
-    };
-    let (first_message, first_public_key) = match signed.messages_and_publickeys().next() {
-        Some((first_message, first_public_key)) => (first_message, first_public_key),
-        None => return false,
-    };
-
-    let mut first_public_key_as_bytes = vec![0; first_public_key.compressed_size()];
-    first_public_key
-        .serialize_compressed(&mut first_public_key_as_bytes[..])
-        .expect("compressed size has been alocated");
+    I: IntoIterator<Item = (&'a Message, &'a PublicKeyInSignatureGroup<S::E>)>,
+{
+    match auxiliary_groups(signed, auxiliary_public_keys, false) {
+        Ok((signature, groups)) => verify_auxiliary_groups::<S::E, H>(signature, groups),
+        Err(_) => false,
+    }
+}
 
-    let first_message_point = first_message.hash_to_signature_curve::<E>();
-    let first_message_point_as_bytes = E::signature_point_to_byte(&first_message_point);
+/// Verification using aggregated auxiliary public keys which explains
+/// failures, see `verify_using_aggregated_auxiliary_public_keys`.
+pub fn try_verify_using_aggregated_auxiliary_public_keys<'a, S, H, I>(
+    signed: S,
+    auxiliary_public_keys: I,
+) -> Result<(), BlsError>
+where
+    S: Signed,
+    S::E: 'a,
+    H: DynDigest + FixedOutputReset + Default + Clone,
+    I: IntoIterator<Item = (&'a Message, &'a PublicKeyInSignatureGroup<S::E>)>,
+{
+    let (signature, groups) = auxiliary_groups(signed, auxiliary_public_keys, true)?;
+    error::ensure(
+        verify_auxiliary_groups::<S::E, H>(signature, groups),
+        BlsError::PairingMismatch,
+    )
+}
 
-    let mut aggregated_aux_pub_key_as_bytes = vec![0; aggregated_aux_pub_key.compressed_size()];
-    aggregated_aux_pub_key
-        .serialize_compressed(&mut aggregated_aux_pub_key_as_bytes[..])
-        .expect("compressed size has been alocated");
+/// Public key and auxiliary public key sums for every distinct message
+type AuxiliaryGroups<E> = BTreeMap<Message, (PublicKeyProjective<E>, SignatureProjective<E>)>;
+
+/// Aggregate signature along with its `AuxiliaryGroups`
+type AuxiliaryAggregate<E> = (SignatureProjective<E>, AuxiliaryGroups<E>);
+
+/// Group `signed`'s public keys and the auxiliary public keys by
+/// message, optionally validating every point.
+fn auxiliary_groups<'a, S, I>(
+    signed: S,
+    auxiliary_public_keys: I,
+    validate: bool,
+) -> Result<AuxiliaryAggregate<S::E>, BlsError>
+where
+    S: Signed,
+    S::E: 'a,
+    I: IntoIterator<Item = (&'a Message, &'a PublicKeyInSignatureGroup<S::E>)>,
+{
+    let signature = signed.signature().0;
+    if validate {
+        error::check_subgroup(&signature)?;
+    }
+    let mut groups: AuxiliaryGroups<S::E> = BTreeMap::new();
+    for (m, pk) in signed.messages_and_publickeys() {
+        if validate {
+            error::check_publickey(&pk.borrow().0)?;
+        }
+        groups
+            .entry(m.borrow().clone())
+            .or_insert((Zero::zero(), Zero::zero()))
+            .0 += pk.borrow().0;
+    }
+    error::ensure(!groups.is_empty(), BlsError::EmptyAggregate)?;
+    for (m, aux) in auxiliary_public_keys {
+        if validate {
+            error::check_publickey(&aux.0)?;
+        }
+        match groups.get_mut(m) {
+            Some((_, aux0)) => *aux0 += aux.0,
+            None => return Err(BlsError::AuxiliaryPublicKeyMismatch),
+        }
+    }
+    error::ensure(
+        groups.values().all(|(_, aux)| !aux.is_zero()),
+        BlsError::AuxiliaryPublicKeyMismatch,
+    )?;
+    Ok((signature, groups))
+}
 
-    // We first hash the messages to the signature curve and
-    // normalize the public keys to operate on them as bytes.
-    // TODO: Assess if we should mutate in place using interior
-    // mutability, maybe using `BorrowMut` support in
-    // `batch_normalization`.
+fn verify_auxiliary_groups<E, H>(
+    signature: SignatureProjective<E>,
+    groups: AuxiliaryGroups<E>,
+) -> bool
+where
+    E: EngineBLS,
+    H: DynDigest + FixedOutputReset + Default + Clone,
+{
+    let mut message_points = groups
+        .keys()
+        .map(|m| m.hash_to_signature_curve::<E>())
+        .collect::<Vec<_>>();
 
     // deterministic randomness for adding aggregated auxiliary pub keys
-    //TODO you can't just assume that there is one pubickey you need to stop if they were more or aggregate them
-
-    let pseudo_random_scalar_seed = [
-        first_message_point_as_bytes,
-        first_public_key_as_bytes,
-        aggregated_aux_pub_key_as_bytes,
-        signature_as_bytes,
-    ]
-    .concat();
+    let mut pseudo_random_scalar_seed = Vec::new();
+    for ((pk, aux), m) in groups.values().zip(&message_points) {
+        pseudo_random_scalar_seed.extend(E::signature_point_to_byte(m));
+        pseudo_random_scalar_seed.extend(E::public_key_point_to_byte(pk));
+        pseudo_random_scalar_seed.extend(E::signature_point_to_byte(aux));
+    }
+    pseudo_random_scalar_seed.extend(E::signature_point_to_byte(&signature));
 
     let hasher = <DefaultFieldHasher<H> as HashToField<E::Scalar>>::new(&[]);
     let pseudo_random_scalar: E::Scalar =
         hasher.hash_to_field::<1>(&pseudo_random_scalar_seed[..])[0];
 
-    let signature = signature + aggregated_aux_pub_key * pseudo_random_scalar;
-
-    let mut publickeys = Vec::with_capacity(l);
-    let mut messages = Vec::with_capacity(l + 1);
-
-    //Simplify from here on.
-    for (m, pk) in itr {
-        publickeys.push(pk.0.clone());
-        messages.push(
-            m.hash_to_signature_curve::<E>()
-                + E::SignatureGroupAffine::generator() * pseudo_random_scalar,
-        );
+    let mut signature = signature;
+    let mut r = pseudo_random_scalar;
+    let mut publickeys = Vec::with_capacity(groups.len());
+    for ((pk, aux), m) in groups.values().zip(message_points.iter_mut()) {
+        signature += *aux * r;
+        *m += E::SignatureGroupAffine::generator() * r;
+        publickeys.push(*pk);
+        r *= pseudo_random_scalar;
     }
 
-    let mut affine_publickeys = if normalize_public_keys {
-        <E as EngineBLS>::PublicKeyGroup::normalize_batch(&publickeys)
-    } else {
-        publickeys
-            .iter()
-            .map(|pk| pk.into_affine())
-            .collect::<Vec<_>>()
-    };
-
-    // We next accumulate message points with the same signer.
-    // We could avoid the allocation here if we sorted both
-    // arrays in parallel.  This might mean (a) some sort function
-    // using `ops::IndexMut` instead of slices, and (b) wrapper types
-    // types to make tuples of slices satisfy `ops::IndexMut`.
-    // TODO:  Impl PartialEq, Eq, Hash for pairing::EncodedPoint
-    // to avoid  struct H(E::PublicKeyGroup::Affine::Uncompressed);
-    type AA<E> = (PublicKeyAffine<E>, SignatureProjective<E>);
-    let mut pks_n_ms = HashMap::with_capacity(l);
-    for (pk, m) in affine_publickeys.drain(..).zip(messages.drain(..)) {
-        let mut pk_uncompressed = vec![0; pk.uncompressed_size()];
-        pk.serialize_uncompressed(&mut pk_uncompressed[..]).unwrap();
-        pks_n_ms
-            .entry(pk_uncompressed)
-            .and_modify(|(_pk0, m0): &mut AA<E>| {
-                *m0 += m;
-            })
-            .or_insert((pk, m));
-    }
-
-    let mut publickeys = Vec::with_capacity(l);
-    for (_, (pk, m)) in pks_n_ms.drain() {
-        messages.push(m);
-        publickeys.push(pk.clone());
-    }
-
-    // We finally normalize the messages and signature
-
-    messages.push(signature);
-    let mut affine_messages =
-        <E as EngineBLS>::SignatureGroup::normalize_batch(messages.as_mut_slice());
-    let signature = <E as EngineBLS>::prepare_signature(affine_messages.pop().unwrap());
-    // TODO: Assess if we could cache normalized message hashes anyplace
-    // using interior mutability, but probably this does not work well
-    // with ur optimization of collecting messages with thesame signer.
-
-    // And verify the aggregate signature.
-    let prepared = publickeys
-        .iter()
-        .zip(messages)
-        .map(|(pk, m)| {
-            (
-                <E as EngineBLS>::prepare_public_key(*pk),
-                <E as EngineBLS>::prepare_signature(m),
-            )
-        })
-        .collect::<Vec<(_, _)>>();
-
-    E::verify_prepared(signature, prepared.iter())
-
-    //let prepared = publickeys.iter().zip(&messages);
-    //S::E::verify_prepared( &signature, prepared )
+    verify_points::<E>(signature, &publickeys, message_points)
 }
 
-/*
 /// Excessively optimized BLS signature verification
 ///
 /// We minimize the number of pairing operations by doing two
@@ -377,11 +417,14 @@ pub fn verify_using_aggregated_auxiliary_public_keys<
 /// We do not require such divisions because we do not solve any
 /// system of equations and do not need ones on the diagonal.
 ///
-/// TODO:
-/// We leave implementing this optimization to near future work
-/// because it benifits from public keys being affine or having
-/// another hashable representation.
-///
+/// We use the Euclidean algorithm on the integer adjacency matrix,
+/// so that every row operation subtracts a small integer multiple of
+/// another row, and we stop once $M$ is in row echelon form.  At that
+/// point, every nonzero row of $M$ needs only one pairing between its
+/// signer and the sum of message points weighted by that row, so we
+/// do `1 + rank(M)` pairings.  In the unlikely event that our integers
+/// overflow, we instead merge whichever of the messages or signers
+/// are fewer.
 ///
 /// As a curiosity, we note one interesting but suboptimal algorithm
 /// that avoids small scalar multiplications when doing this:
@@ -410,8 +453,387 @@ pub fn verify_using_aggregated_auxiliary_public_keys<
 /// We could not however find the optimal numbers of pairings by
 /// completing dominos like this because (a+b+c,x+y+z) - (b,y),
 /// which looks optimal for itself, but only has one subtraction.
-fn verify_with_gaussian_elimination<S: Signed>(s: S) -> bool {
-    unimplemented!()
+pub fn verify_with_gaussian_elimination<S: Signed>(s: S) -> bool {
+    let signature = s.signature().0;
+    let (publickeys, messages) = gaussian_elimination(s);
+    verify_points::<S::E>(signature, &publickeys, messages)
 }
 
-*/
+type ReducedPairings<E> = (Vec<PublicKeyProjective<E>>, Vec<SignatureProjective<E>>);
+
+/// Largest number of entries of the dense signer by message matrix we
+/// row reduce, beyond which we merely aggregate by signer or by message.
+const GAUSSIAN_ELIMINATION_MAX_ENTRIES: usize = 1 << 16;
+
+/// Reduce the pairings of `s` to `rank(M)` pairs of public key and
+/// message points, excluding the signature.
+///
+/// We skip the matrix entirely when every signer or every message
+/// occurs only once, because then aggregating by the other side already
+/// attains the rank, and also when the matrix would exceed
+/// `GAUSSIAN_ELIMINATION_MAX_ENTRIES`.
+fn gaussian_elimination<S: Signed>(s: S) -> ReducedPairings<S::E> {
+    // We first index the distinct signers and messages, and count
+    // the edges between them.  We hash every distinct message only once.
+    let mut signer_index = BTreeMap::new();
+    let mut message_index = BTreeMap::new();
+    let mut publickeys = Vec::new();
+    let mut messages = Vec::new();
+    let mut edges = Vec::new();
+    for (message, publickey) in s.messages_and_publickeys() {
+        let publickey = publickey.borrow().0;
+        let mut pk_uncompressed = vec![0; publickey.uncompressed_size()];
+        publickey
+            .serialize_uncompressed(&mut pk_uncompressed[..])
+            .unwrap();
+        let i = *signer_index.entry(pk_uncompressed).or_insert_with(|| {
+            publickeys.push(publickey);
+            publickeys.len() - 1
+        });
+        let message = message.borrow();
+        let j = match message_index.get(message) {
+            Some(j) => *j,
+            None => {
+                messages.push(message.hash_to_signature_curve::<S::E>());
+                message_index.insert(message.clone(), messages.len() - 1);
+                messages.len() - 1
+            }
+        };
+        edges.push((i, j));
+    }
+    if edges.len() == publickeys.len()
+        || edges.len() == messages.len()
+        || publickeys.len().saturating_mul(messages.len()) > GAUSSIAN_ELIMINATION_MAX_ENTRIES
+    {
+        return aggregate_smaller_side::<S::E>(publickeys, messages, &edges);
+    }
+    let mut matrix = vec![vec![0i64; messages.len()]; publickeys.len()];
+    for (i, j) in edges.iter() {
+        matrix[*i][*j] += 1;
+    }
+
+    let mut basis = publickeys.clone();
+    match row_echelon(&mut matrix, &mut basis) {
+        Some(rank) => (
+            basis.drain(..rank).collect(),
+            matrix[..rank]
+                .iter()
+                .map(|row| small_combination(row, &messages))
+                .collect(),
+        ),
+        None => aggregate_smaller_side::<S::E>(publickeys, messages, &edges),
+    }
+}
+
+/// Aggregate the message points of each signer, or the public keys
+/// of each message, whichever yields fewer pairings.
+fn aggregate_smaller_side<E: EngineBLS>(
+    publickeys: Vec<PublicKeyProjective<E>>,
+    messages: Vec<SignatureProjective<E>>,
+    edges: &[(usize, usize)],
+) -> ReducedPairings<E> {
+    if publickeys.len() <= messages.len() {
+        let mut sums = vec![SignatureProjective::<E>::zero(); publickeys.len()];
+        for (i, j) in edges {
+            sums[*i] += messages[*j];
+        }
+        (publickeys, sums)
+    } else {
+        let mut sums = vec![PublicKeyProjective::<E>::zero(); messages.len()];
+        for (i, j) in edges {
+            sums[*j] += publickeys[*i];
+        }
+        (sums, messages)
+    }
+}
+
+/// Bring `matrix` into row echelon form using only swaps and integer
+/// row subtractions, while changing the `basis` so that
+/// `sum_i matrix[i] basis[i]` remains unchanged.
+///
+/// Returns the rank, or `None` if our entries overflow.
+fn row_echelon<G: CurveGroup>(matrix: &mut [Vec<i64>], basis: &mut [G]) -> Option<usize> {
+    let columns = matrix.first().map_or(0, |row| row.len());
+    let mut rank = 0;
+    for column in 0..columns {
+        if rank == matrix.len() {
+            break;
+        }
+        // Euclid's algorithm on this column, pivoting on the smallest
+        // nonzero entry until all entries below the pivot vanish.
+        while let Some(pivot) = (rank..matrix.len())
+            .filter(|i| matrix[*i][column] != 0)
+            .min_by_key(|i| matrix[*i][column].unsigned_abs())
+        {
+            matrix.swap(rank, pivot);
+            basis.swap(rank, pivot);
+            let mut reduced = true;
+            for i in rank + 1..matrix.len() {
+                let q = matrix[i][column] / matrix[rank][column];
+                if q == 0 {
+                    reduced &= matrix[i][column] == 0;
+                    continue;
+                }
+                // Subtracting `q` times the pivot row from row `i` requires
+                // adding `q` times `basis[i]` to the pivot basis point.
+                let (pivot_rows, rows) = matrix.split_at_mut(i);
+                for (x, y) in rows[0][column..]
+                    .iter_mut()
+                    .zip(&pivot_rows[rank][column..])
+                {
+                    *x = x.checked_sub(q.checked_mul(*y)?)?;
+                }
+                let b = small_multiple(basis[i], q);
+                basis[rank] += b;
+                reduced &= matrix[i][column] == 0;
+            }
+            if reduced {
+                rank += 1;
+                break;
+            }
+        }
+    }
+    Some(rank)
+}
+
+/// Multiply `point` by a small signed integer.
+fn small_multiple<G: CurveGroup>(point: G, c: i64) -> G {
+    match c {
+        0 => G::zero(),
+        1 => point,
+        -1 => -point,
+        _ => {
+            let p = point * G::ScalarField::from(c.unsigned_abs());
+            if c < 0 {
+                -p
+            } else {
+                p
+            }
+        }
+    }
+}
+
+fn small_combination<G: CurveGroup>(coefficients: &[i64], points: &[G]) -> G {
+    coefficients
+        .iter()
+        .zip(points)
+        .fold(G::zero(), |acc, (c, p)| acc + small_multiple(*p, *c))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use ark_ff::Zero;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::delinear::DelinearizedRepeatedSigners;
+    use crate::single::Keypair;
+
+    /// Signatures along random edges of a bipartite graph between
+    /// `signers` and `messages`, with repeated edges.
+    fn random_graph<E: EngineBLS>(
+        rng: &mut ChaCha8Rng,
+        signers: usize,
+        messages: usize,
+        edges: usize,
+    ) -> DelinearizedRepeatedSigners<E> {
+        let messages = (0..messages)
+            .map(|i| Message::new(b"ctx", &(i as u64).to_le_bytes()))
+            .collect::<Vec<_>>();
+        let mut keypairs = (0..signers)
+            .map(|_| Keypair::<E>::generate(&mut *rng))
+            .collect::<Vec<_>>();
+        let mut dl = DelinearizedRepeatedSigners::<E>::new_batched_rng(&mut *rng);
+        for _ in 0..edges {
+            let i = rng.gen_range(0..keypairs.len());
+            let j = rng.gen_range(0..messages.len());
+            dl.add(&keypairs[i].signed_message(&messages[j]));
+        }
+        dl
+    }
+
+    fn gaussian_elimination_agrees_for<E: EngineBLS>() {
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        for (signers, messages, edges) in [(1, 1, 3), (3, 5, 12), (5, 2, 9), (4, 4, 20)] {
+            let dl = random_graph::<E>(&mut rng, signers, messages, edges);
+            assert!(verify_simple(&dl));
+            assert!(verify_with_gaussian_elimination(&dl));
+            let (publickeys, points) = gaussian_elimination(&dl);
+            assert_eq!(publickeys.len(), points.len());
+            assert!(publickeys.len() <= signers.min(messages));
+
+            // Tampering with the signature breaks both verifiers alike.
+            let mut bad = dl.clone();
+            bad.add_delinearized_signature(&Signature(
+                Message::new(b"ctx", b"tamper").hash_to_signature_curve::<E>(),
+            ));
+            assert!(!verify_simple(&bad));
+            assert!(!verify_with_gaussian_elimination(&bad));
+        }
+    }
+
+    #[test]
+    fn gaussian_elimination_agrees_with_verify_simple() {
+        gaussian_elimination_agrees_for::<ZBLS>();
+        gaussian_elimination_agrees_for::<TinyBLS381>();
+    }
+
+    #[test]
+    fn gaussian_elimination_finds_rank() {
+        let mut rng = ChaCha8Rng::seed_from_u64(12);
+        let messages = (0..3u8)
+            .map(|i| Message::new(b"ctx", &[i]))
+            .collect::<Vec<_>>();
+        let mut keypairs = (0..4)
+            .map(|_| Keypair::<ZBLS>::generate(&mut rng))
+            .collect::<Vec<_>>();
+
+        // Every signer signs every message, so one pairing suffices.
+        let mut complete = DelinearizedRepeatedSigners::<ZBLS>::new_batched_rng(&mut rng);
+        for k in keypairs.iter_mut() {
+            for m in messages.iter() {
+                complete.add(&k.signed_message(m));
+            }
+        }
+        assert_eq!(gaussian_elimination(&complete).0.len(), 1);
+        assert!(verify_with_gaussian_elimination(&complete));
+
+        // Two signers who signed the same messages twice span the same row.
+        let mut doubled = DelinearizedRepeatedSigners::<ZBLS>::new_batched_rng(&mut rng);
+        for m in messages[..2].iter() {
+            doubled.add(&keypairs[0].signed_message(m));
+            doubled.add(&keypairs[1].signed_message(m));
+            doubled.add(&keypairs[1].signed_message(m));
+        }
+        doubled.add(&keypairs[2].signed_message(&messages[2]));
+        assert_eq!(gaussian_elimination(&doubled).0.len(), 2);
+        assert!(verify_with_gaussian_elimination(&doubled));
+
+        // A forged edge fails.
+        let mut forged = doubled.clone();
+        let mut signed = keypairs[3].signed_message(&messages[0]);
+        signed.message = messages[1].clone();
+        forged.add(&signed);
+        assert!(!verify_with_gaussian_elimination(&forged));
+
+        // Distinct signers need only one pairing per message.
+        let mut distinct = DelinearizedRepeatedSigners::<ZBLS>::new_batched_rng(&mut rng);
+        for (i, k) in keypairs.iter_mut().enumerate() {
+            distinct.add(&k.signed_message(&messages[i % 2]));
+        }
+        assert_eq!(gaussian_elimination(&distinct).0.len(), 2);
+        assert!(verify_with_gaussian_elimination(&distinct));
+    }
+
+    /// Unsigned edges between public keys and messages, for inspecting
+    /// `gaussian_elimination` without signing.
+    struct Edges<E: EngineBLS>(Vec<(Message, PublicKey<E>)>);
+
+    impl<E: EngineBLS> Signed for &Edges<E> {
+        type E = E;
+
+        type M = Message;
+        type PKG = PublicKey<E>;
+        type PKnM = ::alloc::vec::IntoIter<(Message, PublicKey<E>)>;
+
+        fn messages_and_publickeys(self) -> Self::PKnM {
+            self.0.clone().into_iter()
+        }
+
+        fn signature(&self) -> Signature<E> {
+            Signature(E::SignatureGroup::zero())
+        }
+    }
+
+    #[test]
+    fn gaussian_elimination_skips_large_matrices() {
+        // Each signer signs two neighbouring messages around a cycle, so
+        // the matrix exceeds our limit, and we aggregate by signer.
+        let n = 260;
+        assert!(n * n > GAUSSIAN_ELIMINATION_MAX_ENTRIES);
+        let messages = (0..n)
+            .map(|i| Message::new(b"ctx", &(i as u64).to_le_bytes()))
+            .collect::<Vec<_>>();
+        let mut publickey = PublicKey::<ZBLS>(PublicKeyProjective::<ZBLS>::zero());
+        let mut edges = Vec::new();
+        for i in 0..n {
+            publickey.0 += <<ZBLS as EngineBLS>::PublicKeyGroup as ark_ec::PrimeGroup>::generator();
+            edges.push((messages[i].clone(), publickey));
+            edges.push((messages[(i + 1) % n].clone(), publickey));
+        }
+        let (publickeys, points) = gaussian_elimination(&Edges(edges));
+        assert_eq!(publickeys.len(), n);
+        assert_eq!(points.len(), n);
+        assert!(
+            points[0]
+                == messages[0].hash_to_signature_curve::<ZBLS>()
+                    + messages[1].hash_to_signature_curve::<ZBLS>()
+        );
+    }
+
+    #[test]
+    fn row_echelon_preserves_sums() {
+        let mut rng = ChaCha8Rng::seed_from_u64(13);
+        let mut matrix = (0..4)
+            .map(|_| (0..3).map(|_| rng.gen_range(0..4)).collect::<Vec<i64>>())
+            .collect::<Vec<_>>();
+        let points = (0..4)
+            .map(|_| Keypair::<ZBLS>::generate(&mut rng).public.0)
+            .collect::<Vec<_>>();
+        // Each column is a linear combination of points that row
+        // operations must preserve.
+        let columns = |matrix: &[Vec<i64>], points: &[PublicKeyProjective<ZBLS>]| {
+            (0..3)
+                .map(|j| {
+                    let column = matrix.iter().map(|row| row[j]).collect::<Vec<_>>();
+                    small_combination(&column, points)
+                })
+                .collect::<Vec<_>>()
+        };
+        let before = columns(&matrix, &points);
+        let mut basis = points.clone();
+        let rank = row_echelon(&mut matrix, &mut basis).unwrap();
+        assert!(rank <= 3);
+        assert!(matrix[rank..].iter().all(|row| row.iter().all(|x| *x == 0)));
+        assert!(columns(&matrix, &basis) == before);
+    }
+
+    #[test]
+    fn strict_validation() {
+        use crate::double::{DoublePublicKey, DoublePublicKeyScheme};
+        use crate::single::SignedMessage;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(15);
+        let message = Message::new(b"ctx", b"strict");
+        let mut keypair = Keypair::<ZBLS>::generate(&mut rng);
+        let signed = keypair.signed_message(&message);
+        assert!(signed.publickey.validate().is_ok());
+        assert!(signed.signature.validate().is_ok());
+        assert!(verify_strict(&signed));
+
+        // The identity key with the identity signature passes the
+        // pairing check, but not strict validation.
+        let identity = SignedMessage::<ZBLS> {
+            message: message.clone(),
+            publickey: PublicKey(Zero::zero()),
+            signature: Signature(Zero::zero()),
+        };
+        assert!(identity.publickey.validate() == Err(BlsError::IdentityPublicKey));
+        assert!(identity.signature.validate() == Err(BlsError::IdentitySignature));
+        assert!(verify_simple(&identity));
+        assert!(!verify_strict(&identity));
+        assert!(try_verify_strict(&identity) == Err(BlsError::IdentitySignature));
+
+        let mut keypair = Keypair::<TinyBLS381>::generate(&mut rng);
+        let double: DoublePublicKey<TinyBLS381> =
+            DoublePublicKeyScheme::into_double_public_key(&keypair);
+        assert!(double.validate().is_ok());
+        assert!(
+            DoublePublicKey::<TinyBLS381>(double.0, Zero::zero()).validate()
+                == Err(BlsError::IdentityPublicKey)
+        );
+        let signed = keypair.signed_message(&message);
+        assert!(try_verify_strict(&signed).is_ok());
+    }
+}